@@ -0,0 +1,373 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, State};
+use zip::write::SimpleFileOptions;
+use zip::{ZipArchive, ZipWriter};
+use crate::commands::{get_data_directory, lock_db, DbConnection};
+use crate::{db, sandbox};
+use crate::exports::{get_export_directory, FileInfo};
+use crate::files::{write_atomic, write_atomic_with};
+
+/// Bumped when the archive layout changes in a way older apps cannot restore
+const ARCHIVE_FORMAT_VERSION: u32 = 1;
+
+const MANIFEST_NAME: &str = "manifest.json";
+const DATABASE_ENTRY: &str = "camc.db";
+const FILES_PREFIX: &str = "files/";
+const ARCHIVE_PREFIX: &str = "CAMC-Backup-";
+
+/// Largest entry a backup may hold; entries are read into memory to be verified
+const MAX_ENTRY_BYTES: u64 = 1024 * 1024 * 1024;
+
+/// Describes the contents of a backup archive; stored as `manifest.json`
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupManifest {
+    pub format_version: u32,
+    pub app_version: String,
+    pub schema_version: i64,
+    pub created_at: String,
+    pub database_encrypted: bool,
+    pub files: Vec<ManifestEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestEntry {
+    pub path: String,
+    pub size: u64,
+    pub sha256: String,
+}
+
+/// What restoring a backup does (or would do, in a dry run) to one file
+#[derive(Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum RestoreAction {
+    Create,
+    Replace,
+    Unchanged,
+    /// Legacy `-data.json` files of older backups, which would be imported over the restored database
+    Skipped,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoreFile {
+    pub path: String,
+    pub action: RestoreAction,
+    pub size: u64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoreReport {
+    pub app_version: String,
+    pub schema_version: i64,
+    pub created_at: String,
+    pub files: Vec<RestoreFile>,
+    pub dry_run: bool,
+    /// True when the restored database is encrypted and must be unlocked again
+    pub database_locked: bool,
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+/// Files from the data and export directories that belong in a backup:
+/// copies of migrated legacy model data files and generated PDFs.
+/// A `-data.json` file is never backed up: restored, it would be imported on the next start.
+fn is_backed_up_file(name: &str) -> bool {
+    name.ends_with("-data.json.migrated") || name.ends_with(".pdf")
+}
+
+fn backup_file_paths(app: &AppHandle) -> Result<Vec<PathBuf>, String> {
+    let mut dirs = vec![get_data_directory(app)?];
+    let export_dir = get_export_directory(app)?;
+    if !dirs.contains(&export_dir) {
+        dirs.push(export_dir);
+    }
+
+    let mut paths = Vec::new();
+    for dir in dirs {
+        let entries = fs::read_dir(&dir).map_err(|e| format!("Failed to read directory: {}", e))?;
+        for entry in entries {
+            let path = entry.map_err(|e| format!("Failed to read entry: {}", e))?.path();
+            let backed_up = path.is_file()
+                && path.file_name().and_then(|n| n.to_str()).is_some_and(is_backed_up_file);
+            if backed_up {
+                paths.push(path);
+            }
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+/// Where a file from the archive goes when restored.
+/// Only plain file names are accepted, so an archive cannot write outside the app's folders.
+fn restore_target(app: &AppHandle, entry_path: &str) -> Result<Option<PathBuf>, String> {
+    if entry_path == DATABASE_ENTRY {
+        return db::get_db_path(app).map(Some);
+    }
+
+    let name = entry_path
+        .strip_prefix(FILES_PREFIX)
+        .filter(|name| !name.is_empty() && !name.contains(['/', '\\']) && *name != "." && *name != "..")
+        .ok_or_else(|| format!("Unexpected file in backup: {}", entry_path))?;
+    // Older backups carry legacy data files, which are left out of the restore
+    if name.ends_with("-data.json") {
+        return Ok(None);
+    }
+    if !is_backed_up_file(name) {
+        return Err(format!("Unexpected file in backup: {}", entry_path));
+    }
+
+    let dir = if name.ends_with(".pdf") {
+        get_export_directory(app)?
+    } else {
+        get_data_directory(app)?
+    };
+    Ok(Some(dir.join(name)))
+}
+
+/// Whether a backup archive holds an encrypted database, from its manifest alone
+pub fn is_encrypted_backup(archive_path: &Path) -> Result<bool, String> {
+    let file = File::open(archive_path).map_err(|e| format!("Failed to open backup: {}", e))?;
+    let mut archive = ZipArchive::new(file).map_err(|e| format!("Not a valid backup archive: {}", e))?;
+    let entry = archive
+        .by_name(MANIFEST_NAME)
+        .map_err(|_| "Backup has no manifest".to_string())?;
+    let manifest: BackupManifest = serde_json::from_reader(entry.take(MAX_ENTRY_BYTES))
+        .map_err(|e| format!("Failed to parse backup manifest: {}", e))?;
+    Ok(manifest.database_encrypted)
+}
+
+/// A verified file from an archive and its contents
+type ArchiveFile = (ManifestEntry, Vec<u8>);
+
+/// Read the manifest and every file of an archive, checking sizes and SHA-256 hashes
+fn read_archive(archive_path: &Path) -> Result<(BackupManifest, Vec<ArchiveFile>), String> {
+    let file = File::open(archive_path).map_err(|e| format!("Failed to open backup: {}", e))?;
+    let mut archive = ZipArchive::new(file).map_err(|e| format!("Not a valid backup archive: {}", e))?;
+
+    let manifest: BackupManifest = {
+        let entry = archive
+            .by_name(MANIFEST_NAME)
+            .map_err(|_| "Backup has no manifest".to_string())?;
+        serde_json::from_reader(entry.take(MAX_ENTRY_BYTES)).map_err(|e| format!("Failed to parse backup manifest: {}", e))?
+    };
+
+    if manifest.format_version > ARCHIVE_FORMAT_VERSION {
+        return Err(format!(
+            "Backup format {} is newer than this app supports ({}). Please update the app.",
+            manifest.format_version, ARCHIVE_FORMAT_VERSION
+        ));
+    }
+    if manifest.schema_version > db::SCHEMA_VERSION {
+        return Err(format!(
+            "Backup database schema version {} is newer than this app supports ({}). Please update the app.",
+            manifest.schema_version,
+            db::SCHEMA_VERSION
+        ));
+    }
+
+    let mut files = Vec::new();
+    for listed in &manifest.files {
+        let entry = archive
+            .by_name(&listed.path)
+            .map_err(|_| format!("Backup is missing {}", listed.path))?;
+        if entry.size() > MAX_ENTRY_BYTES || listed.size > MAX_ENTRY_BYTES {
+            return Err(format!("Backup entry {} is too large to restore", listed.path));
+        }
+        // The size in the entry header is not trusted either
+        let mut contents = Vec::new();
+        entry
+            .take(MAX_ENTRY_BYTES + 1)
+            .read_to_end(&mut contents)
+            .map_err(|e| format!("Failed to read {} from backup: {}", listed.path, e))?;
+
+        if contents.len() as u64 != listed.size || sha256_hex(&contents) != listed.sha256 {
+            return Err(format!("Backup is damaged: {} does not match its checksum", listed.path));
+        }
+        files.push((
+            ManifestEntry {
+                path: listed.path.clone(),
+                size: listed.size,
+                sha256: listed.sha256.clone(),
+            },
+            contents,
+        ));
+    }
+
+    Ok((manifest, files))
+}
+
+/// Write one archive containing camc.db, the model data files and generated PDFs,
+/// plus a manifest with the app and schema version and a SHA-256 for every file.
+/// Returns the path of the archive in the export directory.
+#[tauri::command(async)]
+pub fn create_backup(app: AppHandle, db: State<'_, DbConnection>) -> Result<String, String> {
+    let db_path = db::get_db_path(&app)?;
+
+    // Hold the connection while copying so no write can land halfway through the copy,
+    // and move the write-ahead log into the file first so the copy holds everything
+    let conn = lock_db(&db)?;
+    db::checkpoint(&conn)?;
+    let database = fs::read(&db_path).map_err(|e| format!("Failed to read database: {}", e))?;
+    let database_encrypted = db::is_encrypted(&db_path)?;
+    let schema_version = db::schema_version(&conn)?;
+    drop(conn);
+
+    let mut contents = vec![(DATABASE_ENTRY.to_string(), database)];
+    for path in backup_file_paths(&app)? {
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+        let bytes = fs::read(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        contents.push((format!("{}{}", FILES_PREFIX, name), bytes));
+    }
+
+    let now = chrono::Local::now();
+    let manifest = BackupManifest {
+        format_version: ARCHIVE_FORMAT_VERSION,
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        schema_version,
+        created_at: db::now_timestamp(),
+        database_encrypted,
+        files: contents
+            .iter()
+            .map(|(path, bytes)| ManifestEntry {
+                path: path.clone(),
+                size: bytes.len() as u64,
+                sha256: sha256_hex(bytes),
+            })
+            .collect(),
+    };
+    let manifest_json = serde_json::to_vec_pretty(&manifest)
+        .map_err(|e| format!("Failed to serialize manifest: {}", e))?;
+
+    let archive_path = get_export_directory(&app)?
+        .join(format!("{}{}.zip", ARCHIVE_PREFIX, now.format("%Y%m%d-%H%M%S")));
+    write_atomic_with(&archive_path, |file| {
+        let mut zip = ZipWriter::new(file);
+        let options = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+        zip.start_file(MANIFEST_NAME, options)
+            .and_then(|_| Ok(zip.write_all(&manifest_json)?))
+            .map_err(|e| format!("Failed to write manifest: {}", e))?;
+        for (path, bytes) in &contents {
+            zip.start_file(path.as_str(), options)
+                .and_then(|_| Ok(zip.write_all(bytes)?))
+                .map_err(|e| format!("Failed to add {} to backup: {}", path, e))?;
+        }
+        zip.finish().map_err(|e| format!("Failed to finish backup: {}", e))?;
+        Ok(())
+    })?;
+
+    Ok(archive_path.to_string_lossy().to_string())
+}
+
+/// Verify a backup archive and restore it.
+/// With `dry_run` nothing is written; the report lists what would be created or replaced.
+/// Every file is verified before anything is touched.
+#[tauri::command(async)]
+pub fn restore_backup(
+    app: AppHandle,
+    db: State<'_, DbConnection>,
+    archive_path: String,
+    dry_run: bool,
+) -> Result<RestoreReport, String> {
+    let archive_path = sandbox::resolve_file(&app, &archive_path)?;
+    let (manifest, files) = read_archive(&archive_path)?;
+
+    let mut planned = Vec::new();
+    for (entry, contents) in files {
+        let target = restore_target(&app, &entry.path)?;
+        let action = match target.as_ref().map(fs::read) {
+            None => RestoreAction::Skipped,
+            Some(Ok(existing)) if sha256_hex(&existing) == entry.sha256 => RestoreAction::Unchanged,
+            Some(Ok(_)) => RestoreAction::Replace,
+            Some(Err(_)) => RestoreAction::Create,
+        };
+        planned.push((entry, target, contents, action));
+    }
+
+    let database_encrypted = manifest.database_encrypted;
+    let mut report = RestoreReport {
+        app_version: manifest.app_version,
+        schema_version: manifest.schema_version,
+        created_at: manifest.created_at,
+        files: Vec::new(),
+        dry_run,
+        database_locked: false,
+    };
+
+    let mut guard = db.lock().map_err(|e| format!("Failed to lock database: {}", e))?;
+    if dry_run {
+        let restores_database = planned
+            .iter()
+            .any(|(entry, _, _, action)| entry.path == DATABASE_ENTRY && *action != RestoreAction::Unchanged);
+        report.database_locked = if restores_database {
+            database_encrypted
+        } else {
+            guard.is_none() && db::is_encrypted(&db::get_db_path(&app)?)?
+        };
+    } else {
+        let written = planned
+            .iter()
+            .filter(|(_, _, _, action)| matches!(action, RestoreAction::Create | RestoreAction::Replace))
+            .try_for_each(|(entry, target, contents, _)| {
+                let Some(target) = target else { return Ok(()) };
+                if entry.path == DATABASE_ENTRY {
+                    // Close the live connections before their file is swapped out
+                    db.set_readers(Vec::new())?;
+                    *guard = None;
+                    db::remove_wal_files(target)?;
+                }
+                write_atomic(target, contents)
+            });
+
+        // Reopen whatever database is on disk now, even if a later file failed to restore.
+        // An encrypted one stays locked until it is unlocked with its passphrase.
+        let encrypted = db::is_encrypted(&db::get_db_path(&app)?)?;
+        if guard.is_none() && !encrypted {
+            *guard = Some(db::open_db(&app, None)?);
+            db.set_readers(db::open_readers(&app, None)?)?;
+        }
+        report.database_locked = encrypted && guard.is_none();
+        written?;
+    }
+
+    report.files = planned
+        .into_iter()
+        .map(|(entry, _, _, action)| RestoreFile { path: entry.path, action, size: entry.size })
+        .collect();
+    Ok(report)
+}
+
+/// List backup archives in the export directory, newest first
+#[tauri::command]
+pub fn list_backup_archives(app: AppHandle) -> Result<Vec<FileInfo>, String> {
+    let export_dir = get_export_directory(&app)?;
+    let mut archives = Vec::new();
+
+    let entries = fs::read_dir(&export_dir).map_err(|e| e.to_string())?;
+    for entry in entries {
+        let path = entry.map_err(|e| e.to_string())?.path();
+        let name = match path.file_name().and_then(|n| n.to_str()) {
+            Some(name) if name.starts_with(ARCHIVE_PREFIX) && name.ends_with(".zip") => name.to_string(),
+            _ => continue,
+        };
+        let metadata = fs::metadata(&path).map_err(|e| e.to_string())?;
+        archives.push(FileInfo {
+            name,
+            path: path.to_string_lossy().to_string(),
+            size: metadata.len(),
+        });
+    }
+
+    // Archive names embed their timestamp, so name order is creation order
+    archives.sort_by(|a, b| b.name.cmp(&a.name));
+    Ok(archives)
+}
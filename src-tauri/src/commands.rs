@@ -1,13 +1,156 @@
 use rusqlite::Connection;
-use std::sync::Mutex;
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Condvar, LockResult, Mutex, MutexGuard, PoisonError};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use serde_json::Value;
-use tauri::{AppHandle, Manager};
+use tauri::{AppHandle, Manager, State};
+use crate::{db, profiles, relations, revisions, sandbox};
 
-// Global database connection type
-#[allow(dead_code)]
-pub type DbConnection = Mutex<Connection>;
+/// The open database, shared by all commands
+/// One connection takes the writes, one command at a time; a few read-only connections
+/// read alongside it (the database runs in WAL mode), so a long export or PDF job does not
+/// hold up the calendar. Clones share the same connections.
+/// All connections are closed while an encrypted database is locked.
+#[derive(Clone, Default)]
+pub struct DbConnection(Arc<Connections>);
+
+#[derive(Default)]
+struct Connections {
+    writer: Mutex<Option<Connection>>,
+    readers: Mutex<ReaderPool>,
+    reader_returned: Condvar,
+}
+
+#[derive(Default)]
+struct ReaderPool {
+    idle: Vec<Connection>,
+    /// Readers handed out and not yet returned
+    in_use: usize,
+    open: bool,
+}
+
+impl DbConnection {
+    /// Lock the write connection itself, e.g. to open or close it
+    pub fn lock(&self) -> LockResult<MutexGuard<'_, Option<Connection>>> {
+        self.0.writer.lock()
+    }
+
+    /// Replace the read-only connections; an empty list closes them
+    /// Waits until readers in use are returned, so no connection to the old file stays open.
+    pub fn set_readers(&self, readers: Vec<Connection>) -> Result<(), String> {
+        let mut pool = self.0.readers.lock().map_err(|e| format!("Failed to lock database: {}", e))?;
+        pool.open = false;
+        pool.idle.clear();
+        while pool.in_use > 0 {
+            pool = self
+                .0
+                .reader_returned
+                .wait(pool)
+                .map_err(|e| format!("Failed to lock database: {}", e))?;
+        }
+        pool.open = !readers.is_empty();
+        pool.idle = readers;
+        self.0.reader_returned.notify_all();
+        Ok(())
+    }
+
+    /// Take a read-only connection, waiting for one if all are in use
+    fn reader(&self) -> Result<ReaderGuard<'_>, String> {
+        let mut pool = self.0.readers.lock().map_err(|e| format!("Failed to lock database: {}", e))?;
+        loop {
+            if !pool.open {
+                return Err("Database is locked. Unlock it with your passphrase first.".to_string());
+            }
+            if let Some(conn) = pool.idle.pop() {
+                pool.in_use += 1;
+                return Ok(ReaderGuard { connections: &self.0, conn: Some(conn) });
+            }
+            pool = self
+                .0
+                .reader_returned
+                .wait(pool)
+                .map_err(|e| format!("Failed to lock database: {}", e))?;
+        }
+    }
+
+    /// Run `task` with a read-only connection on a background thread
+    pub async fn read<T, F>(&self, task: F) -> Result<T, String>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Connection) -> Result<T, String> + Send + 'static,
+    {
+        let db = self.clone();
+        run_blocking(move || task(&*db.reader()?)).await
+    }
+
+    /// Run `task` with the write connection on a background thread
+    /// Writes wait for each other, but not for reads.
+    pub async fn write<T, F>(&self, task: F) -> Result<T, String>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Connection) -> Result<T, String> + Send + 'static,
+    {
+        let db = self.clone();
+        run_blocking(move || task(&mut *lock_db(&db)?)).await
+    }
+}
+
+/// A read-only connection taken from the pool; returned to it when dropped
+pub struct ReaderGuard<'a> {
+    connections: &'a Connections,
+    conn: Option<Connection>,
+}
+
+impl Deref for ReaderGuard<'_> {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        self.conn.as_ref().expect("reader is taken")
+    }
+}
+
+impl Drop for ReaderGuard<'_> {
+    fn drop(&mut self) {
+        let mut pool = self.connections.readers.lock().unwrap_or_else(PoisonError::into_inner);
+        pool.in_use -= 1;
+        // Readers of a pool closed in the meantime are dropped
+        if let (true, Some(conn)) = (pool.open, self.conn.take()) {
+            pool.idle.push(conn);
+        }
+        self.connections.reader_returned.notify_all();
+    }
+}
+
+/// Locked access to the open write connection
+pub struct DbGuard<'a>(MutexGuard<'a, Option<Connection>>);
+
+impl Deref for DbGuard<'_> {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        // lock_db only hands out guards for an open connection
+        self.0.as_ref().expect("database connection is open")
+    }
+}
+
+impl DerefMut for DbGuard<'_> {
+    fn deref_mut(&mut self) -> &mut Connection {
+        self.0.as_mut().expect("database connection is open")
+    }
+}
+
+/// Run blocking work (database queries, file I/O, PDF rendering) on a background thread,
+/// keeping the main thread free for the UI
+pub async fn run_blocking<T, F>(task: F) -> Result<T, String>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, String> + Send + 'static,
+{
+    tauri::async_runtime::spawn_blocking(task)
+        .await
+        .map_err(|e| format!("Background task failed: {}", e))?
+}
 
 // Learn more about Tauri commands at https://tauri.app/v1/guides/features/command
 #[tauri::command]
@@ -20,6 +163,18 @@ pub fn test_db_connection() -> String {
     "Database ready: camc.db".to_string()
 }
 
+/// Report the database schema version alongside the version this app expects
+#[tauri::command]
+pub async fn get_schema_version(db: State<'_, DbConnection>) -> Result<db::SchemaInfo, String> {
+    db.read(|conn| {
+        Ok(db::SchemaInfo {
+            schema_version: db::schema_version(conn)?,
+            app_schema_version: db::SCHEMA_VERSION,
+        })
+    })
+    .await
+}
+
 #[tauri::command]
 pub fn get_platform() -> String {
     #[cfg(target_os = "windows")]
@@ -34,8 +189,13 @@ pub fn get_platform() -> String {
     return "ios".to_string();
 }
 
-/// Get the data directory (same as export directory)
-fn get_data_directory(app: &AppHandle) -> Result<PathBuf, String> {
+/// Get the data directory of the active profile
+pub fn get_data_directory(app: &AppHandle) -> Result<PathBuf, String> {
+    profiles::active_directory(app, &data_root(app)?)
+}
+
+/// Get the base data directory (same as the base export directory), shared by all profiles
+pub fn data_root(app: &AppHandle) -> Result<PathBuf, String> {
     // Reuse the export directory logic from exports module
     // On iOS/Android: Returns app's Documents directory
     // On Desktop: Returns app's data directory
@@ -60,58 +220,129 @@ fn get_data_directory(app: &AppHandle) -> Result<PathBuf, String> {
     }
 }
 
-/// Get the file path for a model's data file
-fn get_model_data_path(app: &AppHandle, model_name: &str) -> Result<PathBuf, String> {
-    let data_dir = get_data_directory(app)?;
-    let filename = format!("{}-data.json", model_name);
-    Ok(data_dir.join(filename))
+/// Lock the shared write connection
+/// Fails while the database is encrypted and not yet unlocked, so no data command can run
+pub fn lock_db(db: &DbConnection) -> Result<DbGuard<'_>, String> {
+    let guard = db.lock().map_err(|e| format!("Failed to lock database: {}", e))?;
+    if guard.is_none() {
+        return Err("Database is locked. Unlock it with your passphrase first.".to_string());
+    }
+    Ok(DbGuard(guard))
 }
 
-/// Read model data from JSON file
-/// Returns an empty array if file doesn't exist
+/// Read all records of a model from the database, with the model's revision
+/// Returns no records if the model has none.
+/// Reference fields listed in `expand` get their target records embedded under `_expanded`.
 #[tauri::command]
-pub fn read_model_data(app: AppHandle, model_name: String) -> Result<Vec<Value>, String> {
-    let file_path = get_model_data_path(&app, &model_name)?;
-    
-    // If file doesn't exist, return empty array
-    if !file_path.exists() {
-        return Ok(Vec::new());
-    }
-    
-    // Read and parse JSON
-    let contents = fs::read_to_string(&file_path)
-        .map_err(|e| format!("Failed to read file: {}", e))?;
-    
-    let data: Vec<Value> = serde_json::from_str(&contents)
-        .map_err(|e| format!("Failed to parse JSON: {}", e))?;
-    
-    Ok(data)
+pub async fn read_model_data(
+    db: State<'_, DbConnection>,
+    model_name: String,
+    expand: Option<Vec<String>>,
+) -> Result<revisions::ModelData, String> {
+    sandbox::model_name(&model_name)?;
+    db.read(move |conn| {
+        let mut data = revisions::read_model(conn, &model_name)?;
+        if let Some(fields) = expand {
+            relations::expand_records(conn, &model_name, &mut data.records, &fields)?;
+        }
+        Ok(data)
+    })
+    .await
 }
 
-/// Write model data to JSON file
+/// Replace all records of a model in the database
+/// `revision` is the one returned by `read_model_data`; the write fails with a conflict
+/// if the model has changed since. Returns the saved records and the new revision.
 #[tauri::command]
-pub fn write_model_data(app: AppHandle, model_name: String, data: Vec<Value>) -> Result<String, String> {
-    let file_path = get_model_data_path(&app, &model_name)?;
-    
-    // Serialize to JSON with pretty printing
-    let json = serde_json::to_string_pretty(&data)
-        .map_err(|e| format!("Failed to serialize JSON: {}", e))?;
-    
-    // Write to file
-    fs::write(&file_path, json)
-        .map_err(|e| format!("Failed to write file: {}", e))?;
-    
-    Ok(file_path.to_string_lossy().to_string())
+pub async fn write_model_data(
+    db: State<'_, DbConnection>,
+    model_name: String,
+    data: Vec<Value>,
+    revision: i64,
+) -> Result<revisions::ModelData, String> {
+    sandbox::model_name(&model_name)?;
+    db.write(move |conn| {
+        revisions::check_model(conn, &model_name, revision)?;
+        let records = db::replace_records(conn, &model_name, &data)?;
+        Ok(revisions::ModelData {
+            revision: revisions::model_revision(conn, &model_name)?,
+            records,
+        })
+    })
+    .await
+}
+
+/// Read a single record by id, optionally expanding reference fields
+#[tauri::command]
+pub async fn get_record(
+    db: State<'_, DbConnection>,
+    model_name: String,
+    id: String,
+    expand: Option<Vec<String>>,
+) -> Result<Value, String> {
+    sandbox::model_name(&model_name)?;
+    db.read(move |conn| {
+        let mut record = [db::get_record(conn, &model_name, &id)?];
+        if let Some(fields) = expand {
+            relations::expand_records(conn, &model_name, &mut record, &fields)?;
+        }
+        let [record] = record;
+        Ok(record)
+    })
+    .await
+}
+
+/// Create a record; the backend assigns its id and timestamps
+#[tauri::command]
+pub async fn insert_record(db: State<'_, DbConnection>, model_name: String, record: Value) -> Result<Value, String> {
+    sandbox::model_name(&model_name)?;
+    db.write(move |conn| db::insert_record(conn, &model_name, record)).await
 }
 
-/// List all model data files (*-data.json) in the data directory
+/// Update only the fields present in `patch`
+/// `revision` is the record's revision as last read; fails with a conflict if it has changed since
 #[tauri::command]
-pub fn list_model_data_files(app: AppHandle) -> Result<Vec<String>, String> {
-    let data_dir = get_data_directory(&app)?;
+pub async fn update_record(
+    db: State<'_, DbConnection>,
+    model_name: String,
+    id: String,
+    patch: Value,
+    revision: i64,
+) -> Result<Value, String> {
+    sandbox::model_name(&model_name)?;
+    db.write(move |conn| {
+        revisions::check_record(conn, &model_name, &id, revision)?;
+        db::update_record(conn, &model_name, &id, patch)
+    })
+    .await
+}
+
+/// Delete a record by id
+/// Fails with a conflict if the record has changed since `revision` was read, and if
+/// other records still depend on it; see `relations::RELATIONS` for the rules
+#[tauri::command]
+pub async fn delete_record(db: State<'_, DbConnection>, model_name: String, id: String, revision: i64) -> Result<(), String> {
+    sandbox::model_name(&model_name)?;
+    db.write(move |conn| {
+        revisions::check_record(conn, &model_name, &id, revision)?;
+        db::delete_record(conn, &model_name, &id)
+    })
+    .await
+}
+
+/// List all models that have data stored in the database
+#[tauri::command]
+pub async fn list_model_data_files(db: State<'_, DbConnection>) -> Result<Vec<String>, String> {
+    db.read(db::list_models).await
+}
+
+/// Find legacy model data files (*-data.json) in a directory
+/// Returns the model names, sorted alphabetically
+pub fn find_model_data_files(data_dir: &Path) -> Result<Vec<String>, String> {
     let mut model_files = Vec::new();
     
     // Read directory entries
-    let entries = fs::read_dir(&data_dir)
+    let entries = fs::read_dir(data_dir)
         .map_err(|e| format!("Failed to read directory: {}", e))?;
     
     for entry in entries {
@@ -140,4 +371,3 @@ pub fn list_model_data_files(app: AppHandle) -> Result<Vec<String>, String> {
     
     Ok(model_files)
 }
-
@@ -1,29 +1,1055 @@
-use rusqlite::{Connection, Result};
-use std::path::PathBuf;
+use rusqlite::{params, Connection, OptionalExtension, Result};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 use tauri::{AppHandle, Manager};
+use crate::commands::DbConnection;
+use crate::{history, model_backups, profiles, relations, sandbox, schema, trash};
 
-#[allow(dead_code)]
-pub fn init_db(app: &AppHandle) -> Result<Connection> {
+/// Schema migrations, applied in order inside their own transaction.
+/// The schema version stored in `PRAGMA user_version` is the number of
+/// migrations applied, so entries must never be edited or reordered once shipped.
+const MIGRATIONS: &[&str] = &[
+    // 1: model records and legacy JSON import tracking
+    "CREATE TABLE IF NOT EXISTS model_records (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        model_name TEXT NOT NULL,
+        position INTEGER NOT NULL,
+        data TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS idx_model_records_model
+        ON model_records (model_name, position);
+    CREATE TABLE IF NOT EXISTS json_migrations (
+        model_name TEXT PRIMARY KEY,
+        source_path TEXT NOT NULL,
+        record_count INTEGER NOT NULL,
+        migrated_at TEXT NOT NULL
+    );",
+    // 2: stable per-model record ids with created/updated timestamps.
+    // Existing text or integer `id` fields are kept; other records get a random v4 UUID.
+    "CREATE TABLE model_records_new (
+        model_name TEXT NOT NULL,
+        id TEXT NOT NULL,
+        position INTEGER NOT NULL,
+        data TEXT NOT NULL,
+        created_at TEXT NOT NULL,
+        updated_at TEXT NOT NULL,
+        PRIMARY KEY (model_name, id)
+    );
+    INSERT INTO model_records_new (model_name, id, position, data, created_at, updated_at)
+    SELECT
+        model_name,
+        CASE WHEN json_type(data, '$.id') IN ('text', 'integer')
+            THEN CAST(json_extract(data, '$.id') AS TEXT)
+            ELSE lower(hex(randomblob(4))) || '-' || lower(hex(randomblob(2))) || '-4'
+                || substr(lower(hex(randomblob(2))), 2) || '-'
+                || substr('89ab', 1 + (abs(random()) % 4), 1)
+                || substr(lower(hex(randomblob(2))), 2) || '-' || lower(hex(randomblob(6)))
+        END,
+        position,
+        data,
+        strftime('%Y-%m-%dT%H:%M:%fZ', 'now'),
+        strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+    FROM model_records;
+    UPDATE model_records_new
+        SET data = json_set(data, '$.id', id, '$.createdAt', created_at, '$.updatedAt', updated_at);
+    DROP TABLE model_records;
+    ALTER TABLE model_records_new RENAME TO model_records;
+    CREATE INDEX idx_model_records_model ON model_records (model_name, position);",
+    // 3: full-text search over names, notes, letters and congregation names.
+    // `search_documents` decides what text is indexed; triggers keep `search_index` in step.
+    "CREATE VIEW search_documents AS
+    SELECT
+        rowid AS doc_id,
+        model_name,
+        id AS record_id,
+        trim(
+            IFNULL(json_extract(data, '$.nameFirst'), '') || ' ' ||
+            IFNULL(json_extract(data, '$.nameMiddle'), '') || ' ' ||
+            IFNULL(json_extract(data, '$.nameLast'), '') || ' ' ||
+            IFNULL(json_extract(data, '$.nameNickname'), '') || ' ' ||
+            IFNULL(json_extract(data, '$.namePreferred'), '') || ' ' ||
+            IFNULL(json_extract(data, '$.name'), '') || ' ' ||
+            IFNULL(json_extract(data, '$.nameOther'), '') || ' ' ||
+            IFNULL(json_extract(data, '$.branchOfficeCongregationName'), '') || ' ' ||
+            IFNULL(json_extract(data, '$.letterName'), '') || ' ' ||
+            IFNULL(json_extract(data, '$.letterRegarding'), '')
+        ) AS title,
+        trim(
+            IFNULL(json_extract(data, '$.notes'), '') || ' ' ||
+            IFNULL(json_extract(data, '$.coNotes'), '') || ' ' ||
+            IFNULL(json_extract(data, '$.classNotes'), '') || ' ' ||
+            IFNULL(json_extract(data, '$.territoryComments'), '') || ' ' ||
+            IFNULL(json_extract(data, '$.letterContent'), '') || ' ' ||
+            IFNULL(json_extract(data, '$.letterSummary'), '')
+        ) AS body
+    FROM model_records;
+    CREATE VIRTUAL TABLE search_index USING fts5(
+        model_name UNINDEXED,
+        record_id UNINDEXED,
+        title,
+        body,
+        tokenize = 'unicode61 remove_diacritics 2'
+    );
+    INSERT INTO search_index (rowid, model_name, record_id, title, body)
+        SELECT doc_id, model_name, record_id, title, body FROM search_documents;
+    CREATE TRIGGER model_records_search_insert AFTER INSERT ON model_records BEGIN
+        INSERT INTO search_index (rowid, model_name, record_id, title, body)
+            SELECT doc_id, model_name, record_id, title, body FROM search_documents WHERE doc_id = new.rowid;
+    END;
+    CREATE TRIGGER model_records_search_update AFTER UPDATE ON model_records BEGIN
+        DELETE FROM search_index WHERE rowid = old.rowid;
+        INSERT INTO search_index (rowid, model_name, record_id, title, body)
+            SELECT doc_id, model_name, record_id, title, body FROM search_documents WHERE doc_id = new.rowid;
+    END;
+    CREATE TRIGGER model_records_search_delete AFTER DELETE ON model_records BEGIN
+        DELETE FROM search_index WHERE rowid = old.rowid;
+    END;",
+    // 4: app settings and rolling per-model backups
+    "CREATE TABLE app_settings (
+        key TEXT PRIMARY KEY,
+        value TEXT NOT NULL
+    );
+    CREATE TABLE model_backups (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        model_name TEXT NOT NULL,
+        created_at TEXT NOT NULL,
+        record_count INTEGER NOT NULL,
+        data TEXT NOT NULL
+    );
+    CREATE INDEX idx_model_backups_model ON model_backups (model_name, id);",
+    // 5: append-only change log with field-level before/after values
+    "CREATE TABLE record_changes (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        model_name TEXT NOT NULL,
+        record_id TEXT NOT NULL,
+        operation TEXT NOT NULL,
+        changed_at TEXT NOT NULL,
+        device_id TEXT NOT NULL,
+        device_name TEXT NOT NULL,
+        changes TEXT NOT NULL
+    );
+    CREATE INDEX idx_record_changes_record ON record_changes (model_name, record_id, id);",
+    // 6: revisions for optimistic concurrency. Each record counts its own writes;
+    // `model_revisions` is bumped by triggers on every change to a model's records.
+    "ALTER TABLE model_records ADD COLUMN revision INTEGER NOT NULL DEFAULT 1;
+    UPDATE model_records SET data = json_set(data, '$.revision', revision);
+    CREATE TABLE model_revisions (
+        model_name TEXT PRIMARY KEY,
+        revision INTEGER NOT NULL
+    );
+    INSERT INTO model_revisions (model_name, revision)
+        SELECT model_name, 1 FROM model_records GROUP BY model_name;
+    CREATE TRIGGER model_records_revision_insert AFTER INSERT ON model_records BEGIN
+        INSERT INTO model_revisions (model_name, revision) VALUES (new.model_name, 1)
+            ON CONFLICT (model_name) DO UPDATE SET revision = revision + 1;
+    END;
+    CREATE TRIGGER model_records_revision_update AFTER UPDATE ON model_records BEGIN
+        INSERT INTO model_revisions (model_name, revision) VALUES (old.model_name, 1)
+            ON CONFLICT (model_name) DO UPDATE SET revision = revision + 1;
+    END;
+    CREATE TRIGGER model_records_revision_delete AFTER DELETE ON model_records BEGIN
+        INSERT INTO model_revisions (model_name, revision) VALUES (old.model_name, 1)
+            ON CONFLICT (model_name) DO UPDATE SET revision = revision + 1;
+    END;",
+    // 7: trash for deleted records, purged after a retention period
+    "CREATE TABLE trash (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        model_name TEXT NOT NULL,
+        record_id TEXT NOT NULL,
+        deleted_at TEXT NOT NULL,
+        batch_id TEXT NOT NULL,
+        data TEXT NOT NULL
+    );
+    CREATE INDEX idx_trash_batch ON trash (batch_id);
+    CREATE INDEX idx_trash_record ON trash (model_name, record_id);",
+    // 8: calendar events as last exported, to send updates and cancellations of them
+    "CREATE TABLE calendar_events (
+        uid TEXT PRIMARY KEY,
+        source TEXT NOT NULL,
+        model_name TEXT,
+        record_id TEXT,
+        sequence INTEGER NOT NULL,
+        fingerprint TEXT NOT NULL,
+        summary TEXT NOT NULL,
+        start_time TEXT NOT NULL,
+        end_time TEXT NOT NULL,
+        time_zone TEXT,
+        exported_at TEXT NOT NULL,
+        cancelled_at TEXT
+    );
+    CREATE INDEX idx_calendar_events_record ON calendar_events (model_name, record_id);",
+    // 9: key `search_index` on a stable document id per record instead of the rowid of `model_records`,
+    // which VACUUM and `sqlcipher_export` may renumber
+    "CREATE TABLE search_keys (
+        doc_id INTEGER PRIMARY KEY,
+        model_name TEXT NOT NULL,
+        record_id TEXT NOT NULL,
+        UNIQUE (model_name, record_id)
+    );
+    DROP TRIGGER model_records_search_insert;
+    DROP TRIGGER model_records_search_update;
+    DROP TRIGGER model_records_search_delete;
+    DROP VIEW search_documents;
+    CREATE VIEW search_documents AS
+    SELECT
+        search_keys.doc_id,
+        model_records.model_name,
+        model_records.id AS record_id,
+        trim(
+            IFNULL(json_extract(data, '$.nameFirst'), '') || ' ' ||
+            IFNULL(json_extract(data, '$.nameMiddle'), '') || ' ' ||
+            IFNULL(json_extract(data, '$.nameLast'), '') || ' ' ||
+            IFNULL(json_extract(data, '$.nameNickname'), '') || ' ' ||
+            IFNULL(json_extract(data, '$.namePreferred'), '') || ' ' ||
+            IFNULL(json_extract(data, '$.name'), '') || ' ' ||
+            IFNULL(json_extract(data, '$.nameOther'), '') || ' ' ||
+            IFNULL(json_extract(data, '$.branchOfficeCongregationName'), '') || ' ' ||
+            IFNULL(json_extract(data, '$.letterName'), '') || ' ' ||
+            IFNULL(json_extract(data, '$.letterRegarding'), '')
+        ) AS title,
+        trim(
+            IFNULL(json_extract(data, '$.notes'), '') || ' ' ||
+            IFNULL(json_extract(data, '$.coNotes'), '') || ' ' ||
+            IFNULL(json_extract(data, '$.classNotes'), '') || ' ' ||
+            IFNULL(json_extract(data, '$.territoryComments'), '') || ' ' ||
+            IFNULL(json_extract(data, '$.letterContent'), '') || ' ' ||
+            IFNULL(json_extract(data, '$.letterSummary'), '')
+        ) AS body
+    FROM model_records
+    JOIN search_keys ON search_keys.model_name = model_records.model_name AND search_keys.record_id = model_records.id;
+    INSERT INTO search_keys (model_name, record_id) SELECT model_name, id FROM model_records;
+    DELETE FROM search_index;
+    INSERT INTO search_index (rowid, model_name, record_id, title, body)
+        SELECT doc_id, model_name, record_id, title, body FROM search_documents;
+    CREATE TRIGGER model_records_search_insert AFTER INSERT ON model_records BEGIN
+        INSERT OR IGNORE INTO search_keys (model_name, record_id) VALUES (new.model_name, new.id);
+        INSERT INTO search_index (rowid, model_name, record_id, title, body)
+            SELECT doc_id, model_name, record_id, title, body FROM search_documents
+            WHERE model_name = new.model_name AND record_id = new.id;
+    END;
+    CREATE TRIGGER model_records_search_update AFTER UPDATE ON model_records BEGIN
+        DELETE FROM search_index WHERE rowid =
+            (SELECT doc_id FROM search_keys WHERE model_name = old.model_name AND record_id = old.id);
+        DELETE FROM search_keys WHERE model_name = old.model_name AND record_id = old.id;
+        INSERT OR IGNORE INTO search_keys (model_name, record_id) VALUES (new.model_name, new.id);
+        INSERT INTO search_index (rowid, model_name, record_id, title, body)
+            SELECT doc_id, model_name, record_id, title, body FROM search_documents
+            WHERE model_name = new.model_name AND record_id = new.id;
+    END;
+    CREATE TRIGGER model_records_search_delete AFTER DELETE ON model_records BEGIN
+        DELETE FROM search_index WHERE rowid =
+            (SELECT doc_id FROM search_keys WHERE model_name = old.model_name AND record_id = old.id);
+        DELETE FROM search_keys WHERE model_name = old.model_name AND record_id = old.id;
+    END;",
+];
+
+/// Schema version this build of the app expects
+pub const SCHEMA_VERSION: i64 = MIGRATIONS.len() as i64;
+
+/// Schema version information reported to the frontend
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SchemaInfo {
+    pub schema_version: i64,
+    pub app_schema_version: i64,
+}
+
+/// Header every unencrypted SQLite database file starts with
+const SQLITE_HEADER: &[u8] = b"SQLite format 3\0";
+
+/// Read-only connections opened next to the write connection
+const READER_COUNT: usize = 3;
+
+/// How long a connection waits for another one to release the database before giving up
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Open the active profile's database, at startup or after switching profiles,
+/// closing whatever database was open before.
+/// Leaves it closed when the database is encrypted; it stays locked until
+/// `unlock_database` is called with the passphrase.
+pub fn init_db(app: &AppHandle, db: &DbConnection) -> Result<(), String> {
+    let mut guard = db.lock().map_err(|e| format!("Failed to lock database: {}", e))?;
+    db.set_readers(Vec::new())?;
+    *guard = None;
+
+    if is_encrypted(&get_db_path(app)?)? {
+        println!("Database is encrypted, waiting for passphrase");
+        return Ok(());
+    }
+
+    *guard = Some(open_db(app, None)?);
+    db.set_readers(open_readers(app, None)?)
+}
+
+/// Check whether the database file exists and is encrypted.
+/// An encrypted file has no plain SQLite header.
+pub fn is_encrypted(db_path: &Path) -> Result<bool, String> {
+    let mut header = [0u8; 16];
+    let read = match fs::File::open(db_path) {
+        Ok(mut file) => file
+            .read(&mut header)
+            .map_err(|e| format!("Failed to read database header: {}", e))?,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+        Err(err) => return Err(format!("Failed to open database file: {}", err)),
+    };
+
+    Ok(read > 0 && header[..read] != SQLITE_HEADER[..read])
+}
+
+/// Open the database with an optional passphrase, bring its schema up to date
+/// and import any legacy JSON model files
+pub fn open_db(app: &AppHandle, passphrase: Option<&str>) -> Result<Connection, String> {
     let db_path = get_db_path(app)?;
-    let conn = Connection::open(db_path)?;
+    let mut conn = open_connection(&db_path, passphrase)?;
+
+    run_migrations(&mut conn, &db_path)?;
+    if let Err(err) = trash::purge_expired(&conn) {
+        eprintln!("Could not purge trash: {}", err);
+    }
+
+    // Pull any legacy {model}-data.json files into the database.
+    // A bad file must not keep the app from starting, so errors are only logged.
+    match crate::commands::get_data_directory(app) {
+        Ok(data_dir) => {
+            if let Err(err) = migrate_json_model_files(&conn, &data_dir) {
+                eprintln!("Could not migrate model data files: {}", err);
+            }
+        }
+        Err(err) => eprintln!("Could not locate model data files: {}", err),
+    }
+
+    Ok(conn)
+}
+
+/// Open a connection and apply the SQLCipher key before anything else touches the file.
+/// A wrong passphrase only shows up on first read, so the schema is read right away.
+pub fn open_connection(db_path: &Path, passphrase: Option<&str>) -> Result<Connection, String> {
+    let conn = Connection::open(db_path)
+        .map_err(|e| format!("Failed to open database: {}", e))?;
+
+    if let Some(passphrase) = passphrase {
+        conn.pragma_update(None, "key", passphrase)
+            .map_err(|e| format!("Failed to set database key: {}", e))?;
+    }
 
-    // Database is ready for future tables
-    // No tables needed yet, just the scaffolding
+    conn.query_row("SELECT COUNT(*) FROM sqlite_master", [], |row| row.get::<_, i64>(0))
+        .map_err(|e| match e {
+            rusqlite::Error::SqliteFailure(err, _) if err.code == rusqlite::ErrorCode::NotADatabase => {
+                "Incorrect passphrase".to_string()
+            }
+            e => format!("Failed to read database: {}", e),
+        })?;
+
+    // WAL lets the read-only connections read while the write connection writes
+    let journal_mode: String = conn
+        .pragma_update_and_check(None, "journal_mode", "WAL", |row| row.get(0))
+        .map_err(|e| format!("Failed to set journal mode: {}", e))?;
+    if !journal_mode.eq_ignore_ascii_case("wal") {
+        eprintln!("Database does not support WAL, using journal mode {}", journal_mode);
+    }
+    conn.busy_timeout(BUSY_TIMEOUT)
+        .map_err(|e| format!("Failed to set busy timeout: {}", e))?;
 
     Ok(conn)
 }
 
-#[allow(dead_code)]
-fn get_db_path(app: &AppHandle) -> Result<PathBuf> {
+/// Open the read-only connections to the active profile's database.
+/// Call after `open_db`, so they never see a schema that is still being migrated.
+pub fn open_readers(app: &AppHandle, passphrase: Option<&str>) -> Result<Vec<Connection>, String> {
+    let db_path = get_db_path(app)?;
+    (0..READER_COUNT)
+        .map(|_| {
+            let conn = open_connection(&db_path, passphrase)?;
+            conn.pragma_update(None, "query_only", true)
+                .map_err(|e| format!("Failed to open read-only connection: {}", e))?;
+            Ok(conn)
+        })
+        .collect()
+}
+
+/// Move everything in the write-ahead log into the database file itself,
+/// so the file alone holds all data and can be copied
+pub fn checkpoint(conn: &Connection) -> Result<(), String> {
+    let busy: i64 = conn
+        .query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |row| row.get(0))
+        .map_err(|e| format!("Failed to checkpoint database: {}", e))?;
+    if busy != 0 {
+        return Err("Database is busy, please try again".to_string());
+    }
+    Ok(())
+}
+
+/// Delete the write-ahead log files of a closed database before its file is replaced,
+/// so a stale log is not applied to the new file
+pub fn remove_wal_files(db_path: &Path) -> Result<(), String> {
+    for suffix in ["-wal", "-shm"] {
+        let mut path = db_path.as_os_str().to_owned();
+        path.push(suffix);
+        match fs::remove_file(PathBuf::from(path)) {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+                return Err(format!("Failed to remove database {} file: {}", suffix, err));
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Path of the active profile's database
+pub fn get_db_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = profiles::active_directory(app, &db_root(app)?)?;
+    Ok(dir.join("camc.db"))
+}
+
+/// Base directory of the databases of all profiles
+pub fn db_root(app: &AppHandle) -> Result<PathBuf, String> {
     // Use Tauri's app_data_dir which works on all platforms including Android/iOS
-    let mut path = app
+    let path = app
         .path()
         .app_data_dir()
-        .map_err(|e| rusqlite::Error::InvalidPath(format!("Failed to get app data dir: {}", e).into()))?;
-    
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+
     std::fs::create_dir_all(&path)
-        .map_err(|e| rusqlite::Error::InvalidPath(format!("Failed to create data dir: {}", e).into()))?;
-    
-    path.push("camc.db");
+        .map_err(|e| format!("Failed to create data dir: {}", e))?;
+
     Ok(path)
 }
+
+/// Read the schema version stored in the database header
+pub fn schema_version(conn: &Connection) -> Result<i64, String> {
+    conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+        .map_err(|e| format!("Failed to read schema version: {}", e))
+}
+
+/// Bring the database schema up to `SCHEMA_VERSION`.
+/// Refuses to touch a database written by a newer app, and copies an existing
+/// database to `camc.db.v{version}.bak` before the first pending migration runs.
+/// The copy is taken byte for byte, so an encrypted database stays encrypted.
+fn run_migrations(conn: &mut Connection, db_path: &Path) -> Result<(), String> {
+    let current = schema_version(conn)?;
+
+    if current > SCHEMA_VERSION {
+        return Err(format!(
+            "Database schema version {} is newer than this app supports ({}). Please update the app.",
+            current, SCHEMA_VERSION
+        ));
+    }
+    if current == SCHEMA_VERSION {
+        return Ok(());
+    }
+
+    let has_tables: bool = conn
+        .query_row("SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table')", [], |row| row.get(0))
+        .map_err(|e| format!("Failed to inspect database: {}", e))?;
+    if has_tables {
+        let mut backup_path = db_path.as_os_str().to_owned();
+        backup_path.push(format!(".v{}.bak", current));
+        let backup_path = PathBuf::from(backup_path);
+        // Nothing has been written since the connection was opened, so once the log
+        // is checkpointed the file is consistent
+        checkpoint(conn)?;
+        fs::copy(db_path, &backup_path)
+            .map_err(|e| format!("Failed to back up database before migration: {}", e))?;
+        println!("Backed up database to {} before migrating", backup_path.display());
+    }
+
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(current as usize) {
+        let version = index as i64 + 1;
+        let tx = conn
+            .transaction()
+            .map_err(|e| format!("Failed to start migration {}: {}", version, e))?;
+        tx.execute_batch(migration)
+            .map_err(|e| format!("Migration {} failed: {}", version, e))?;
+        tx.pragma_update(None, "user_version", version)
+            .map_err(|e| format!("Failed to set schema version {}: {}", version, e))?;
+        tx.commit()
+            .map_err(|e| format!("Failed to commit migration {}: {}", version, e))?;
+        println!("Migrated database to schema version {}", version);
+    }
+
+    Ok(())
+}
+
+/// An empty in-memory database at the current schema
+#[cfg(test)]
+pub fn test_connection() -> Connection {
+    let mut conn = Connection::open_in_memory().expect("in-memory database opens");
+    run_migrations(&mut conn, Path::new(":memory:")).expect("migrations apply");
+    conn
+}
+
+/// Read an app setting, if it has been set
+pub fn get_setting(conn: &Connection, key: &str) -> Result<Option<String>, String> {
+    conn.query_row("SELECT value FROM app_settings WHERE key = ?1", params![key], |row| row.get(0))
+        .optional()
+        .map_err(|e| format!("Failed to read setting {}: {}", key, e))
+}
+
+/// Store an app setting
+pub fn set_setting(conn: &Connection, key: &str, value: &str) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO app_settings (key, value) VALUES (?1, ?2)
+         ON CONFLICT (key) DO UPDATE SET value = excluded.value",
+        params![key, value],
+    )
+    .map(|_| ())
+    .map_err(|e| format!("Failed to save setting {}: {}", key, e))
+}
+
+/// Current time in the format stored in `created_at` / `updated_at`
+pub fn now_timestamp() -> String {
+    chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+}
+
+pub fn new_record_id() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+/// Read the id of a record as stored in its JSON, if it has a usable one
+pub fn record_id_of(record: &Value) -> Option<String> {
+    match record.get("id") {
+        Some(Value::String(id)) if !id.is_empty() => Some(id.clone()),
+        Some(Value::Number(id)) => Some(id.to_string()),
+        _ => None,
+    }
+}
+
+/// Read the revision stored in a record's JSON; records saved before revisions existed count as 1
+pub fn revision_of(record: &Value) -> i64 {
+    record.get("revision").and_then(Value::as_i64).unwrap_or(1)
+}
+
+/// Stamp the bookkeeping fields that the backend owns onto a record
+fn stamp_record(
+    record: &mut Value,
+    id: &str,
+    created_at: &str,
+    updated_at: &str,
+    revision: i64,
+) -> Result<(), String> {
+    let fields = record
+        .as_object_mut()
+        .ok_or_else(|| "Record must be a JSON object".to_string())?;
+    fields.remove(relations::EXPANDED_KEY);
+    fields.insert("id".to_string(), Value::String(id.to_string()));
+    fields.insert("createdAt".to_string(), Value::String(created_at.to_string()));
+    fields.insert("updatedAt".to_string(), Value::String(updated_at.to_string()));
+    fields.insert("revision".to_string(), Value::from(revision));
+    Ok(())
+}
+
+fn parse_record(data: &str) -> Result<Value, String> {
+    serde_json::from_str(data).map_err(|e| format!("Failed to parse stored record: {}", e))
+}
+
+fn serialize_record(record: &Value) -> Result<String, String> {
+    serde_json::to_string(record).map_err(|e| format!("Failed to serialize record: {}", e))
+}
+
+/// Read all records of a model, in the order they were saved
+pub fn read_records(conn: &Connection, model_name: &str) -> Result<Vec<Value>, String> {
+    let mut stmt = conn
+        .prepare("SELECT data FROM model_records WHERE model_name = ?1 ORDER BY position")
+        .map_err(|e| format!("Failed to prepare query: {}", e))?;
+
+    let rows = stmt
+        .query_map(params![model_name], |row| row.get::<_, String>(0))
+        .map_err(|e| format!("Failed to query records: {}", e))?;
+
+    let mut records = Vec::new();
+    for row in rows {
+        let data = row.map_err(|e| format!("Failed to read record: {}", e))?;
+        records.push(parse_record(&data)?);
+    }
+
+    Ok(records)
+}
+
+/// Read a single record by id
+pub fn get_record(conn: &Connection, model_name: &str, id: &str) -> Result<Value, String> {
+    find_record(conn, model_name, id)?.ok_or_else(|| format!("No {} record with id {}", model_name, id))
+}
+
+/// A record by id, or None if there is none; failures to read are errors
+pub fn find_record(conn: &Connection, model_name: &str, id: &str) -> Result<Option<Value>, String> {
+    let data: Option<String> = conn
+        .query_row(
+            "SELECT data FROM model_records WHERE model_name = ?1 AND id = ?2",
+            params![model_name, id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| format!("Failed to read record: {}", e))?;
+    data.as_deref().map(parse_record).transpose()
+}
+
+/// Insert a new record, assigning it a fresh id, timestamps and revision 1.
+/// Any `id`, `createdAt`, `updatedAt` or `revision` sent by the caller is ignored.
+pub fn insert_record(conn: &Connection, model_name: &str, record: Value) -> Result<Value, String> {
+    let tx = conn
+        .unchecked_transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let record = insert_record_in_tx(&tx, model_name, record)?;
+
+    tx.commit()
+        .map_err(|e| format!("Failed to commit insert: {}", e))?;
+    Ok(record)
+}
+
+/// Insert a record inside a transaction the caller owns
+pub fn insert_record_in_tx(conn: &Connection, model_name: &str, mut record: Value) -> Result<Value, String> {
+    let id = new_record_id();
+    let now = now_timestamp();
+    stamp_record(&mut record, &id, &now, &now, 1)?;
+    schema::check_record(model_name, &record)?;
+    relations::validate_references(conn, model_name, &record)?;
+
+    conn.execute(
+        "INSERT INTO model_records (model_name, id, position, data, created_at, updated_at)
+         VALUES (?1, ?2,
+            (SELECT COALESCE(MAX(position) + 1, 0) FROM model_records WHERE model_name = ?1),
+            ?3, ?4, ?4)",
+        params![model_name, id, serialize_record(&record)?, now],
+    )
+    .map_err(|e| format!("Failed to insert record: {}", e))?;
+    history::record_change(conn, model_name, &id, None, Some(&record))?;
+
+    Ok(record)
+}
+
+/// Put a previously deleted record back under its original id and `createdAt`.
+/// Its revision continues from the one it carries, so copies read before the delete stay stale.
+pub fn reinsert_record(conn: &Connection, model_name: &str, mut record: Value) -> Result<Value, String> {
+    let id = record_id_of(&record).ok_or_else(|| "Record to restore has no id".to_string())?;
+    let created_at = record
+        .get("createdAt")
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .unwrap_or_else(now_timestamp);
+    let now = now_timestamp();
+    let revision = revision_of(&record) + 1;
+    stamp_record(&mut record, &id, &created_at, &now, revision)?;
+    schema::check_record(model_name, &record)?;
+    relations::validate_references(conn, model_name, &record)?;
+
+    conn.execute(
+        "INSERT INTO model_records (model_name, id, position, data, created_at, updated_at, revision)
+         VALUES (?1, ?2,
+            (SELECT COALESCE(MAX(position) + 1, 0) FROM model_records WHERE model_name = ?1),
+            ?3, ?4, ?5, ?6)",
+        params![model_name, id, serialize_record(&record)?, created_at, now, revision],
+    )
+    .map_err(|e| match e {
+        rusqlite::Error::SqliteFailure(err, _) if err.code == rusqlite::ErrorCode::ConstraintViolation => {
+            format!("A {} record with id {} already exists", model_name, id)
+        }
+        e => format!("Failed to restore record: {}", e),
+    })?;
+    trash::forget(conn, model_name, &id)?;
+    history::record_change(conn, model_name, &id, None, Some(&record))?;
+
+    Ok(record)
+}
+
+/// Apply a partial update to a record.
+/// Top-level fields in `patch` replace the stored values; fields not in the
+/// patch are left untouched. `id`, `createdAt` and `revision` cannot be changed;
+/// the revision goes up by one.
+pub fn update_record(conn: &Connection, model_name: &str, id: &str, patch: Value) -> Result<Value, String> {
+    let tx = conn
+        .unchecked_transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    let record = update_record_in_tx(&tx, model_name, id, patch)?;
+
+    tx.commit()
+        .map_err(|e| format!("Failed to commit update: {}", e))?;
+    Ok(record)
+}
+
+/// Update a record inside a transaction the caller owns
+pub fn update_record_in_tx(conn: &Connection, model_name: &str, id: &str, patch: Value) -> Result<Value, String> {
+    let patch = match patch {
+        Value::Object(fields) => fields,
+        _ => return Err("Record patch must be a JSON object".to_string()),
+    };
+
+    let before = get_record(conn, model_name, id)?;
+    let mut record = before.clone();
+    if let Some(fields) = record.as_object_mut() {
+        for (key, value) in patch {
+            fields.insert(key, value);
+        }
+    }
+    write_update(conn, model_name, id, before, record)
+}
+
+/// Replace every field of a record inside a transaction the caller owns; fields left out of
+/// `record` are removed. Its id, `createdAt` and revision are kept as stored.
+pub fn replace_record_in_tx(conn: &Connection, model_name: &str, id: &str, record: Value) -> Result<Value, String> {
+    if !record.is_object() {
+        return Err("Record must be a JSON object".to_string());
+    }
+    let before = get_record(conn, model_name, id)?;
+    write_update(conn, model_name, id, before, record)
+}
+
+/// Store the new version of a record that was `before`, checked and logged like any update
+fn write_update(conn: &Connection, model_name: &str, id: &str, before: Value, mut record: Value) -> Result<Value, String> {
+    model_backups::backup_before_write(conn, model_name, false)?;
+    let created_at = before
+        .get("createdAt")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string();
+    let now = now_timestamp();
+    let revision = revision_of(&before) + 1;
+    stamp_record(&mut record, id, &created_at, &now, revision)?;
+    schema::check_record(model_name, &record)?;
+    relations::validate_references(conn, model_name, &record)?;
+
+    conn.execute(
+        "UPDATE model_records SET data = ?3, updated_at = ?4, revision = ?5 WHERE model_name = ?1 AND id = ?2",
+        params![model_name, id, serialize_record(&record)?, now, revision],
+    )
+    .map_err(|e| format!("Failed to update record: {}", e))?;
+    history::record_change(conn, model_name, id, Some(&before), Some(&record))?;
+
+    Ok(record)
+}
+
+/// Delete a record by id, applying the delete rules of relations that point at it
+pub fn delete_record(conn: &Connection, model_name: &str, id: &str) -> Result<(), String> {
+    let tx = conn
+        .unchecked_transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    delete_record_in_tx(&tx, model_name, id)?;
+
+    tx.commit()
+        .map_err(|e| format!("Failed to commit delete: {}", e))
+}
+
+/// Delete a record inside a transaction the caller owns
+pub fn delete_record_in_tx(conn: &Connection, model_name: &str, id: &str) -> Result<(), String> {
+    delete_record_in_batch(conn, model_name, id, &new_record_id())
+}
+
+/// Delete a record and move it to the trash under `batch_id`.
+/// Records deleted by its delete rules go into the same batch, so they are restored with it.
+pub fn delete_record_in_batch(conn: &Connection, model_name: &str, id: &str, batch_id: &str) -> Result<(), String> {
+    let before = get_record(conn, model_name, id)?;
+    model_backups::backup_before_write(conn, model_name, false)?;
+    relations::apply_delete_rules(conn, model_name, id, batch_id)?;
+
+    conn.execute(
+        "DELETE FROM model_records WHERE model_name = ?1 AND id = ?2",
+        params![model_name, id],
+    )
+    .map_err(|e| format!("Failed to delete record: {}", e))?;
+    trash::put(conn, model_name, id, &before, batch_id)?;
+    history::record_change(conn, model_name, id, Some(&before), None)
+}
+
+/// Replace all records of a model in a single transaction.
+/// References are checked once the whole set is written, so records may point
+/// at each other; records left out of the new set are treated as deleted and go to the trash.
+pub fn replace_records(conn: &mut Connection, model_name: &str, records: &[Value]) -> Result<Vec<Value>, String> {
+    let tx = conn
+        .transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    // Replacing a whole model is the riskiest write, so it always gets a backup
+    model_backups::backup_before_write(&tx, model_name, true)?;
+    let previous = records_by_id(&tx, model_name)?;
+    let kept_ids: HashSet<String> = records.iter().filter_map(record_id_of).collect();
+    let removed: HashMap<&String, &Value> = previous.iter().filter(|(id, _)| !kept_ids.contains(*id)).collect();
+
+    // References between the records to the ones left out are cleared like any other delete would
+    let mut records = records.to_vec();
+    for record in &mut records {
+        relations::clear_references(model_name, record, |id| removed.contains_key(&id.to_string()));
+    }
+    let records = insert_records(&tx, model_name, &records, &previous)?;
+
+    // Apply the delete rules first, so the new records are checked against what is left
+    let batch_ids: HashMap<&String, String> = removed.keys().map(|id| (*id, new_record_id())).collect();
+    for (removed_id, batch_id) in &batch_ids {
+        relations::apply_delete_rules(&tx, model_name, removed_id, batch_id)?;
+    }
+    for record in &records {
+        schema::check_record(model_name, record)?;
+        relations::validate_references(&tx, model_name, record)?;
+        if let Some(id) = record_id_of(record) {
+            history::record_change(&tx, model_name, &id, previous.get(&id), Some(record))?;
+        }
+    }
+    for (removed_id, batch_id) in &batch_ids {
+        let record = removed[removed_id];
+        trash::put(&tx, model_name, removed_id, record, batch_id)?;
+        history::record_change(&tx, model_name, removed_id, Some(record), None)?;
+    }
+
+    tx.commit()
+        .map_err(|e| format!("Failed to commit records: {}", e))?;
+    Ok(records)
+}
+
+/// Replace all records of a model with `records`.
+/// Records that carry the id of one of the `existing` records keep its `createdAt`,
+/// and only get a new `updatedAt` and revision if their contents changed. Records
+/// without an id (or with an id used twice) are given a new one.
+fn insert_records(
+    conn: &Connection,
+    model_name: &str,
+    records: &[Value],
+    existing: &HashMap<String, Value>,
+) -> Result<Vec<Value>, String> {
+    conn.execute("DELETE FROM model_records WHERE model_name = ?1", params![model_name])
+        .map_err(|e| format!("Failed to clear records: {}", e))?;
+
+    let mut stmt = conn
+        .prepare(
+            "INSERT INTO model_records (model_name, id, position, data, created_at, updated_at, revision)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        )
+        .map_err(|e| format!("Failed to prepare insert: {}", e))?;
+
+    let now = now_timestamp();
+    let mut seen = HashSet::new();
+    let mut saved = Vec::with_capacity(records.len());
+    for (position, record) in records.iter().enumerate() {
+        let mut record = record.clone();
+        let id = record_id_of(&record)
+            .filter(|id| seen.insert(id.clone()))
+            .unwrap_or_else(|| {
+                let id = new_record_id();
+                seen.insert(id.clone());
+                id
+            });
+
+        let (created_at, updated_at, revision) = match existing.get(&id) {
+            Some(previous) => {
+                let created_at = previous.get("createdAt").and_then(Value::as_str).unwrap_or(&now).to_string();
+                let previous_updated = previous.get("updatedAt").and_then(Value::as_str).unwrap_or(&now).to_string();
+                let previous_revision = revision_of(previous);
+                stamp_record(&mut record, &id, &created_at, &previous_updated, previous_revision)?;
+                if &record == previous {
+                    (created_at, previous_updated, previous_revision)
+                } else {
+                    (created_at, now.clone(), previous_revision + 1)
+                }
+            }
+            None => (now.clone(), now.clone(), 1),
+        };
+        stamp_record(&mut record, &id, &created_at, &updated_at, revision)?;
+
+        stmt.execute(params![
+            model_name,
+            id,
+            position as i64,
+            serialize_record(&record)?,
+            created_at,
+            updated_at,
+            revision
+        ])
+            .map_err(|e| format!("Failed to insert record: {}", e))?;
+        saved.push(record);
+    }
+
+    Ok(saved)
+}
+
+/// All records of a model, keyed by id
+fn records_by_id(conn: &Connection, model_name: &str) -> Result<HashMap<String, Value>, String> {
+    let mut stmt = conn
+        .prepare("SELECT id, data FROM model_records WHERE model_name = ?1")
+        .map_err(|e| format!("Failed to prepare query: {}", e))?;
+    let rows = stmt
+        .query_map(params![model_name], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+        .map_err(|e| format!("Failed to query records: {}", e))?;
+
+    let mut records = HashMap::new();
+    for row in rows {
+        let (id, data) = row.map_err(|e| format!("Failed to read record: {}", e))?;
+        records.insert(id, parse_record(&data)?);
+    }
+    Ok(records)
+}
+
+/// List the names of all models that have at least one stored record
+pub fn list_models(conn: &Connection) -> Result<Vec<String>, String> {
+    let mut stmt = conn
+        .prepare("SELECT DISTINCT model_name FROM model_records ORDER BY model_name")
+        .map_err(|e| format!("Failed to prepare query: {}", e))?;
+
+    let rows = stmt
+        .query_map([], |row| row.get::<_, String>(0))
+        .map_err(|e| format!("Failed to query models: {}", e))?;
+
+    rows.collect::<Result<Vec<_>>>()
+        .map_err(|e| format!("Failed to read model name: {}", e))
+}
+
+/// One-time import of legacy `{model}-data.json` files.
+/// Each file is loaded in its own transaction and then renamed to
+/// `{model}-data.json.migrated` so it is kept as a copy but never read again.
+fn migrate_json_model_files(conn: &Connection, data_dir: &Path) -> Result<(), String> {
+    for model_name in crate::commands::find_model_data_files(data_dir)? {
+        // Leave files of unknown models on disk untouched rather than importing them
+        if let Err(err) = sandbox::model_name(&model_name) {
+            eprintln!("Skipping {}-data.json: {}", model_name, err);
+            continue;
+        }
+        let already_migrated: bool = conn
+            .query_row(
+                "SELECT EXISTS(SELECT 1 FROM json_migrations WHERE model_name = ?1)",
+                params![model_name],
+                |row| row.get(0),
+            )
+            .map_err(|e| format!("Failed to check migration state: {}", e))?;
+        if already_migrated {
+            continue;
+        }
+
+        let file_path = data_dir.join(format!("{}-data.json", model_name));
+        // A file that turns up once the model has records (copied in or synced back) must never replace them
+        let has_records: bool = conn
+            .query_row(
+                "SELECT EXISTS(SELECT 1 FROM model_records WHERE model_name = ?1)",
+                params![model_name],
+                |row| row.get(0),
+            )
+            .map_err(|e| format!("Failed to check existing records: {}", e))?;
+        if has_records {
+            let mut skipped_path = file_path.clone().into_os_string();
+            skipped_path.push(".skipped");
+            match fs::rename(&file_path, &skipped_path) {
+                Ok(()) => eprintln!(
+                    "Not migrating {}: {} already has records. Moved it to {}",
+                    file_path.display(),
+                    model_name,
+                    Path::new(&skipped_path).display()
+                ),
+                Err(err) => eprintln!("Not migrating {}: {} already has records ({})", file_path.display(), model_name, err),
+            }
+            continue;
+        }
+
+        let contents = fs::read_to_string(&file_path)
+            .map_err(|e| format!("Failed to read {}: {}", file_path.display(), e))?;
+        let records: Vec<Value> = match serde_json::from_str(&contents) {
+            Ok(records) => records,
+            Err(err) => {
+                eprintln!("Skipping {}: {}", file_path.display(), err);
+                continue;
+            }
+        };
+
+        let tx = conn
+            .unchecked_transaction()
+            .map_err(|e| format!("Failed to start transaction: {}", e))?;
+        insert_records(&tx, &model_name, &records, &HashMap::new())?;
+        tx.execute(
+            "INSERT INTO json_migrations (model_name, source_path, record_count, migrated_at)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![
+                model_name,
+                file_path.to_string_lossy(),
+                records.len() as i64,
+                chrono::Utc::now().to_rfc3339()
+            ],
+        )
+        .map_err(|e| format!("Failed to record migration: {}", e))?;
+        tx.commit()
+            .map_err(|e| format!("Failed to commit migration: {}", e))?;
+
+        let mut migrated_path = file_path.clone().into_os_string();
+        migrated_path.push(".migrated");
+        if let Err(err) = fs::rename(&file_path, &migrated_path) {
+            eprintln!("Migrated {} but could not rename it: {}", file_path.display(), err);
+        }
+
+        println!("Migrated {} {} records from {}", records.len(), model_name, file_path.display());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn data_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("camc-db-test-{}-{}", name, new_record_id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn search_index_follows_records_when_rowids_change() {
+        let conn = test_connection();
+        let first = insert_record(&conn, "VenueObject", json!({ "name": "Assembly Hall" })).unwrap();
+        let second = insert_record(&conn, "VenueObject", json!({ "name": "Kingdom Hall" })).unwrap();
+        // Renumber the rowids of model_records behind the triggers' back, as VACUUM or an encrypted copy may
+        let triggers: Vec<(String, String)> = conn
+            .prepare("SELECT name, sql FROM sqlite_master WHERE type = 'trigger' AND tbl_name = 'model_records'")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<Result<_>>()
+            .unwrap();
+        for (name, _) in &triggers {
+            conn.execute_batch(&format!("DROP TRIGGER {}", name)).unwrap();
+        }
+        conn.execute_batch("UPDATE model_records SET rowid = rowid + 100").unwrap();
+        for (_, sql) in &triggers {
+            conn.execute_batch(sql).unwrap();
+        }
+        delete_record(&conn, "VenueObject", first["id"].as_str().unwrap()).unwrap();
+        update_record(&conn, "VenueObject", second["id"].as_str().unwrap(), json!({ "name": "Kingdom Hall North" }))
+            .unwrap();
+
+        let hits = crate::search::search_records(&conn, "hall", &[], 10).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].record_id, second["id"].as_str().unwrap());
+        assert_eq!(hits[0].title, "Kingdom Hall North");
+
+        crate::search::rebuild_index(&conn).unwrap();
+        assert_eq!(crate::search::search_records(&conn, "north", &[], 10).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn legacy_files_load_into_empty_models() {
+        let conn = test_connection();
+        let dir = data_dir("load");
+        fs::write(dir.join("VenueObject-data.json"), r#"[{"id":"v1","name":"Hall"}]"#).unwrap();
+
+        migrate_json_model_files(&conn, &dir).unwrap();
+        assert_eq!(get_record(&conn, "VenueObject", "v1").unwrap()["name"], "Hall");
+        assert!(dir.join("VenueObject-data.json.migrated").exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn legacy_files_never_replace_records() {
+        let conn = test_connection();
+        let venue = insert_record(&conn, "VenueObject", json!({ "name": "Kept" })).unwrap();
+        let dir = data_dir("keep");
+        fs::write(dir.join("VenueObject-data.json"), r#"[{"id":"v1","name":"Hall"}]"#).unwrap();
+
+        migrate_json_model_files(&conn, &dir).unwrap();
+        let records = read_records(&conn, "VenueObject").unwrap();
+        assert_eq!(records, vec![venue]);
+        assert!(!dir.join("VenueObject-data.json").exists());
+        assert!(dir.join("VenueObject-data.json.skipped").exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,206 @@
+use rusqlite::{params, DatabaseName};
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, State};
+use crate::commands::{get_data_directory, DbConnection};
+use crate::exports::get_export_directory;
+use crate::{archive, db, search};
+
+/// Lock state of the database, reported to the frontend
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DatabaseStatus {
+    pub encrypted: bool,
+    pub locked: bool,
+}
+
+fn check_passphrase(passphrase: &str) -> Result<(), String> {
+    if passphrase.trim().is_empty() {
+        return Err("Passphrase must not be empty".to_string());
+    }
+    Ok(())
+}
+
+/// Remove unencrypted copies of data that would otherwise outlive encryption:
+/// pre-migration database backups and migrated `*-data.json` files
+fn remove_plaintext_copies(db_path: &Path, data_dir: &Path) -> Result<(), String> {
+    let db_name = db_path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("camc.db")
+        .to_string();
+
+    let mut dirs = vec![data_dir.to_path_buf()];
+    if let Some(db_dir) = db_path.parent() {
+        if db_dir != data_dir {
+            dirs.push(db_dir.to_path_buf());
+        }
+    }
+
+    for dir in dirs {
+        let entries = fs::read_dir(&dir).map_err(|e| format!("Failed to read directory: {}", e))?;
+        for entry in entries {
+            let path = entry.map_err(|e| format!("Failed to read entry: {}", e))?.path();
+            let name = match path.file_name().and_then(|n| n.to_str()) {
+                Some(name) => name,
+                None => continue,
+            };
+            let is_db_backup = name.starts_with(&format!("{}.v", db_name)) && name.ends_with(".bak");
+            if path.is_file() && (is_db_backup || name.ends_with("-data.json.migrated")) {
+                fs::remove_file(&path)
+                    .map_err(|e| format!("Failed to remove {}: {}", path.display(), e))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Exports the app writes next to the database, which encrypting it does not cover
+const EXPORT_EXTENSIONS: &[&str] = &["csv", "xlsx", "ics", "pdf"];
+
+/// Files that still hold data in plain text once the database is encrypted: exports, backups of the
+/// unencrypted database and legacy data files that were never imported. They are the user's to keep
+/// or delete, so they are only listed.
+fn plaintext_files_left(dirs: &[PathBuf]) -> Result<Vec<String>, String> {
+    let mut left = Vec::new();
+    for dir in dirs {
+        let entries = fs::read_dir(dir).map_err(|e| format!("Failed to read directory: {}", e))?;
+        for entry in entries {
+            let path = entry.map_err(|e| format!("Failed to read entry: {}", e))?.path();
+            let Some(name) = path.file_name().and_then(|n| n.to_str()) else { continue };
+            let extension = path.extension().and_then(|e| e.to_str()).unwrap_or_default().to_lowercase();
+            let plaintext = EXPORT_EXTENSIONS.contains(&extension.as_str())
+                || name.ends_with("-data.json")
+                || name.ends_with("-data.json.skipped")
+                // A backup made after encrypting holds the encrypted database
+                || (extension == "zip" && !archive::is_encrypted_backup(&path).unwrap_or(false));
+            if path.is_file() && plaintext && !left.contains(&path) {
+                left.push(path);
+            }
+        }
+    }
+    left.sort();
+    Ok(left.into_iter().map(|path| path.to_string_lossy().to_string()).collect())
+}
+
+/// Report whether the database is encrypted and whether it is currently locked
+#[tauri::command(async)]
+pub fn get_database_status(app: AppHandle, db: State<'_, DbConnection>) -> Result<DatabaseStatus, String> {
+    let guard = db.lock().map_err(|e| format!("Failed to lock database: {}", e))?;
+    Ok(DatabaseStatus {
+        encrypted: db::is_encrypted(&db::get_db_path(&app)?)?,
+        locked: guard.is_none(),
+    })
+}
+
+/// Open an encrypted database with its passphrase
+#[tauri::command(async)]
+pub fn unlock_database(app: AppHandle, db: State<'_, DbConnection>, passphrase: String) -> Result<(), String> {
+    let mut guard = db.lock().map_err(|e| format!("Failed to lock database: {}", e))?;
+    if guard.is_some() {
+        return Ok(());
+    }
+
+    *guard = Some(db::open_db(&app, Some(&passphrase))?);
+    db.set_readers(db::open_readers(&app, Some(&passphrase))?)
+}
+
+/// Close an encrypted database so no data can be read until it is unlocked again
+#[tauri::command(async)]
+pub fn lock_database(app: AppHandle, db: State<'_, DbConnection>) -> Result<(), String> {
+    if !db::is_encrypted(&db::get_db_path(&app)?)? {
+        return Err("Database is not encrypted. Set a passphrase before locking it.".to_string());
+    }
+
+    let mut guard = db.lock().map_err(|e| format!("Failed to lock database: {}", e))?;
+    // Dropping the connections closes them and clears the key from memory
+    db.set_readers(Vec::new())?;
+    *guard = None;
+    Ok(())
+}
+
+/// Set or change the database passphrase.
+/// An unencrypted database is exported into an encrypted copy that replaces it;
+/// `current_passphrase` is only needed when the database is already encrypted.
+/// Returns the files that still hold data in plain text, so the user can be warned about them.
+#[tauri::command(async)]
+pub fn change_database_passphrase(
+    app: AppHandle,
+    db: State<'_, DbConnection>,
+    current_passphrase: Option<String>,
+    new_passphrase: String,
+) -> Result<Vec<String>, String> {
+    check_passphrase(&new_passphrase)?;
+
+    let db_path = db::get_db_path(&app)?;
+    let mut guard = db.lock().map_err(|e| format!("Failed to lock database: {}", e))?;
+    let conn = guard
+        .as_ref()
+        .ok_or_else(|| "Database is locked. Unlock it with your passphrase first.".to_string())?;
+
+    if db::is_encrypted(&db_path)? {
+        let current = current_passphrase
+            .ok_or_else(|| "Current passphrase is required".to_string())?;
+        // Prove the caller knows the current passphrase before rekeying
+        db::open_connection(&db_path, Some(&current))?;
+
+        // SQLCipher cannot rekey a database in WAL mode, and the readers still use the old key
+        db.set_readers(Vec::new())?;
+        let rekeyed = conn
+            .pragma_update(None, "journal_mode", "DELETE")
+            .and_then(|_| conn.pragma_update(None, "rekey", &new_passphrase))
+            .map_err(|e| format!("Failed to change passphrase: {}", e));
+        // Even if the rekey failed, go back to WAL and reopen the readers with the key now in use
+        let key = if rekeyed.is_ok() { &new_passphrase } else { &current };
+        let journal = conn
+            .pragma_update(None, "journal_mode", "WAL")
+            .map_err(|e| format!("Failed to set journal mode: {}", e));
+        let readers = db::open_readers(&app, Some(key)).and_then(|readers| db.set_readers(readers));
+        rekeyed.and(journal).and(readers)?;
+        return plaintext_files_left(&[get_data_directory(&app)?, get_export_directory(&app)?]);
+    }
+
+    let mut encrypted_path = db_path.as_os_str().to_owned();
+    encrypted_path.push(".encrypting");
+    let encrypted_path = Path::new(&encrypted_path).to_path_buf();
+    if encrypted_path.exists() {
+        fs::remove_file(&encrypted_path)
+            .map_err(|e| format!("Failed to remove stale encrypted copy: {}", e))?;
+    }
+
+    let version = db::schema_version(conn)?;
+    conn.execute(
+        "ATTACH DATABASE ?1 AS encrypted KEY ?2",
+        params![encrypted_path.to_string_lossy(), new_passphrase],
+    )
+    .map_err(|e| format!("Failed to create encrypted database: {}", e))?;
+    let exported = conn
+        .query_row("SELECT sqlcipher_export('encrypted')", [], |_| Ok(()))
+        .and_then(|_| conn.pragma_update(Some(DatabaseName::Attached("encrypted")), "user_version", version));
+    conn.execute("DETACH DATABASE encrypted", [])
+        .map_err(|e| format!("Failed to detach encrypted database: {}", e))?;
+    exported.map_err(|e| format!("Failed to encrypt database: {}", e))?;
+
+    // Close the plaintext connections before swapping the files
+    db.set_readers(Vec::new())?;
+    *guard = None;
+    let swapped = db::remove_wal_files(&db_path).and_then(|_| {
+        fs::rename(&encrypted_path, &db_path)
+            .map_err(|e| format!("Failed to replace database with encrypted copy: {}", e))
+    });
+    if let Err(e) = swapped {
+        // The plaintext database is still in place, so keep using it
+        *guard = Some(db::open_connection(&db_path, None)?);
+        db.set_readers(db::open_readers(&app, None)?)?;
+        return Err(e);
+    }
+    let conn = guard.insert(db::open_connection(&db_path, Some(&new_passphrase))?);
+    // The copy may number rows differently, so the search index is built again from the records
+    search::rebuild_index(conn)?;
+    db.set_readers(db::open_readers(&app, Some(&new_passphrase))?)?;
+
+    let data_dir = get_data_directory(&app)?;
+    remove_plaintext_copies(&db_path, &data_dir)?;
+    plaintext_files_left(&[data_dir, get_export_directory(&app)?])
+}
@@ -1,25 +1,13 @@
 use std::fs;
 use std::path::PathBuf;
 use serde::Serialize;
-use tauri::{AppHandle, Manager};
-
-/// Creates sample ICS calendar content
-fn create_sample_ics_content() -> String {
-    "BEGIN:VCALENDAR\r\n\
-VERSION:2.0\r\n\
-PRODID:-//Circuit Assistant//EN\r\n\
-BEGIN:VEVENT\r\n\
-UID:sample-event-001@circuitassistant.org\r\n\
-DTSTAMP:20251109T120000Z\r\n\
-DTSTART:20251115T100000Z\r\n\
-DTEND:20251115T110000Z\r\n\
-SUMMARY:Circuit Overseer Visit\r\n\
-DESCRIPTION:Weekly meeting with Circuit Overseer talk\r\n\
-LOCATION:Kingdom Hall\r\n\
-STATUS:CONFIRMED\r\n\
-END:VEVENT\r\n\
-END:VCALENDAR\r\n".to_string()
-}
+use tauri::{AppHandle, Manager, State};
+use crate::commands::DbConnection;
+use crate::files::write_atomic;
+use crate::ics_exports::{self, IcsCalendar, IcsExportOptions};
+use crate::ics_imports::{self, IcsImport};
+use crate::ics_updates::{self, IcsUpdates, SentEvents};
+use crate::{profiles, sandbox};
 
 /// Creates sample vCard contact content
 fn create_sample_vcard_content() -> String {
@@ -36,10 +24,15 @@ NOTE:Circuit Overseer Contact\r\n\
 END:VCARD\r\n".to_string()
 }
 
-/// Get the appropriate export directory based on platform
+/// Get the export directory of the active profile
+pub fn get_export_directory(app: &AppHandle) -> Result<PathBuf, String> {
+    profiles::active_directory(app, &export_root(app)?)
+}
+
+/// Get the base export directory based on platform, shared by all profiles
 /// On iOS/Android: Returns app's Documents directory (accessible via Files app)
 /// On Desktop: Returns app's data directory
-pub fn get_export_directory(app: &AppHandle) -> Result<PathBuf, String> {
+pub fn export_root(app: &AppHandle) -> Result<PathBuf, String> {
     // On iOS/Android, use app's document directory
     // This is accessible via Files app -> "On My iPhone/iPad" -> App Name
     #[cfg(any(target_os = "ios", target_os = "android"))]
@@ -70,22 +63,33 @@ pub fn get_export_directory(app: &AppHandle) -> Result<PathBuf, String> {
     }
 }
 
+/// Export events from the stored records as an ICS file in the export directory.
+/// `options` picks the date range and sources; everything is exported without it.
+/// Returns the file path.
 #[tauri::command]
-pub fn export_ics(app: AppHandle) -> Result<String, String> {
-    // Get ICS content
-    let ics_content = create_sample_ics_content();
-    
-    // Get the appropriate export directory
-    let export_dir = get_export_directory(&app)?;
-    
-    // Create ICS file path
-    let file_path = export_dir.join("CircuitOverseerVisit.ics");
-    
-    // Write to file
-    fs::write(&file_path, ics_content).map_err(|e| e.to_string())?;
-    
-    // Return the file path as a string
-    Ok(file_path.to_string_lossy().to_string())
+pub async fn export_ics(
+    app: AppHandle,
+    db: State<'_, DbConnection>,
+    options: Option<IcsExportOptions>,
+) -> Result<String, String> {
+    db.write(move |conn| {
+        let calendar = ics_exports::build_calendar(conn, &options.unwrap_or_default())?;
+
+        // Get the appropriate export directory
+        let export_dir = get_export_directory(&app)?;
+
+        // Create ICS file path
+        let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S");
+        let file_path = export_dir.join(format!("Calendar-{}.ics", stamp));
+
+        // Write to file, then remember its events as sent
+        write_atomic(&file_path, calendar.content.as_bytes())?;
+        ics_updates::mark_sent(conn, &calendar.sent)?;
+
+        // Return the file path as a string
+        Ok(file_path.to_string_lossy().to_string())
+    })
+    .await
 }
 
 #[tauri::command]
@@ -100,16 +104,75 @@ pub fn export_vcard(app: AppHandle) -> Result<String, String> {
     let file_path = export_dir.join("JohnSmith.vcf");
     
     // Write to file
-    fs::write(&file_path, vcard_content).map_err(|e| e.to_string())?;
+    write_atomic(&file_path, vcard_content.as_bytes())?;
     
     // Return the file path as a string
     Ok(file_path.to_string_lossy().to_string())
 }
 
 /// Get ICS content - exposed for frontend to handle save on mobile
+/// Takes the same `options` as `export_ics`. Nothing is remembered as sent until the frontend
+/// passes `sent` to `mark_ics_sent` after saving the file.
 #[tauri::command]
-pub fn get_ics_content() -> String {
-    create_sample_ics_content()
+pub async fn get_ics_content(db: State<'_, DbConnection>, options: Option<IcsExportOptions>) -> Result<IcsCalendar, String> {
+    db.read(move |conn| ics_exports::build_calendar(conn, &options.unwrap_or_default())).await
+}
+
+/// Remember the events of content from `get_ics_content` or `get_ics_updates` as sent,
+/// once the frontend has saved or shared it
+#[tauri::command]
+pub async fn mark_ics_sent(db: State<'_, DbConnection>, sent: SentEvents) -> Result<(), String> {
+    db.write(move |conn| ics_updates::mark_sent(conn, &sent)).await
+}
+
+/// Files written by `export_ics_updates`
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IcsUpdateFiles {
+    /// METHOD:REQUEST file of the new and changed events, when there are any
+    pub updates_path: Option<String>,
+    /// METHOD:CANCEL file of the deleted events, when there are any
+    pub cancellations_path: Option<String>,
+    pub updated: Vec<String>,
+    pub cancelled: Vec<String>,
+}
+
+/// Export the changes since the last export as ICS files in the export directory: the new and
+/// changed events, and the cancellations of deleted ones. Calendars that imported the earlier
+/// export update their events from them. Takes the same `options` as `export_ics`.
+#[tauri::command]
+pub async fn export_ics_updates(
+    app: AppHandle,
+    db: State<'_, DbConnection>,
+    options: Option<IcsExportOptions>,
+) -> Result<IcsUpdateFiles, String> {
+    db.write(move |conn| {
+        let result = ics_updates::build_updates(conn, &options.unwrap_or_default())?;
+        let export_dir = get_export_directory(&app)?;
+        let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S");
+        let write = |name: &str, content: Option<&String>| -> Result<Option<String>, String> {
+            let Some(content) = content else { return Ok(None) };
+            let file_path = export_dir.join(format!("{}-{}.ics", name, stamp));
+            write_atomic(&file_path, content.as_bytes())?;
+            Ok(Some(file_path.to_string_lossy().to_string()))
+        };
+        let files = IcsUpdateFiles {
+            updates_path: write("Calendar-Updates", result.updates.as_ref())?,
+            cancellations_path: write("Calendar-Cancellations", result.cancellations.as_ref())?,
+            updated: result.updated,
+            cancelled: result.cancelled,
+        };
+        ics_updates::mark_sent(conn, &result.sent)?;
+        Ok(files)
+    })
+    .await
+}
+
+/// Get the update and cancellation content - exposed for frontend to handle save on mobile.
+/// Nothing is remembered as sent until the frontend passes `sent` to `mark_ics_sent`.
+#[tauri::command]
+pub async fn get_ics_updates(db: State<'_, DbConnection>, options: Option<IcsExportOptions>) -> Result<IcsUpdates, String> {
+    db.read(move |conn| ics_updates::build_updates(conn, &options.unwrap_or_default())).await
 }
 
 /// Get vCard content - exposed for frontend to handle save on mobile
@@ -126,12 +189,11 @@ pub struct FileInfo {
     pub size: u64,
 }
 
-/// List all JSON files in the app's Documents directory
-#[tauri::command]
-pub fn list_json_files(app: AppHandle) -> Result<Vec<FileInfo>, String> {
-    let export_dir = get_export_directory(&app)?;
+/// List the files with an extension in the app's Documents directory, sorted by name
+fn list_files(app: &AppHandle, extension: &str) -> Result<Vec<FileInfo>, String> {
+    let export_dir = get_export_directory(app)?;
     
-    let mut json_files = Vec::new();
+    let mut files = Vec::new();
     
     // Read directory entries
     let entries = fs::read_dir(&export_dir).map_err(|e| e.to_string())?;
@@ -140,17 +202,17 @@ pub fn list_json_files(app: AppHandle) -> Result<Vec<FileInfo>, String> {
         let entry = entry.map_err(|e| e.to_string())?;
         let path = entry.path();
         
-        // Check if it's a file and has .json extension
+        // Check if it's a file and has the extension
         if path.is_file() {
-            if let Some(extension) = path.extension() {
-                if extension == "json" {
+            if let Some(file_extension) = path.extension() {
+                if file_extension.eq_ignore_ascii_case(extension) {
                     let metadata = fs::metadata(&path).map_err(|e| e.to_string())?;
                     let name = path.file_name()
                         .and_then(|n| n.to_str())
                         .ok_or("Invalid filename")?
                         .to_string();
                     
-                    json_files.push(FileInfo {
+                    files.push(FileInfo {
                         name,
                         path: path.to_string_lossy().to_string(),
                         size: metadata.len(),
@@ -161,19 +223,41 @@ pub fn list_json_files(app: AppHandle) -> Result<Vec<FileInfo>, String> {
     }
     
     // Sort by name
-    json_files.sort_by(|a, b| a.name.cmp(&b.name));
+    files.sort_by(|a, b| a.name.cmp(&b.name));
     
-    Ok(json_files)
+    Ok(files)
+}
+
+/// List all JSON files in the app's Documents directory
+#[tauri::command]
+pub fn list_json_files(app: AppHandle) -> Result<Vec<FileInfo>, String> {
+    list_files(&app, "json")
+}
+
+/// List all ICS files in the app's Documents directory, e.g. schedules received as attachments
+#[tauri::command]
+pub fn list_ics_files(app: AppHandle) -> Result<Vec<FileInfo>, String> {
+    list_files(&app, "ics")
 }
 
 /// Read a JSON file from the app's Documents directory
+/// Paths outside the export and data directories are refused; see `sandbox::resolve_file`
 #[tauri::command]
-pub fn read_json_file(file_path: String) -> Result<String, String> {
-    // Read the file
-    let contents = fs::read_to_string(&file_path).map_err(|e| e.to_string())?;
+pub fn read_json_file(app: AppHandle, file_path: String) -> Result<String, String> {
+    let path = sandbox::resolve_file(&app, &file_path)?;
+    let contents = fs::read_to_string(&path).map_err(|e| e.to_string())?;
     Ok(contents)
 }
 
+/// Read the events of an ICS file in the format of the calendar page, with what could not be imported
+/// Paths outside the export and data directories are refused; see `sandbox::resolve_file`
+#[tauri::command(async)]
+pub fn read_ics_file(app: AppHandle, file_path: String) -> Result<IcsImport, String> {
+    let path = sandbox::resolve_file(&app, &file_path)?;
+    let bytes = fs::read(&path).map_err(|e| e.to_string())?;
+    ics_imports::parse_calendar(&String::from_utf8_lossy(&bytes))
+}
+
 /// Create a sample events JSON file in the Documents directory for testing
 #[tauri::command]
 pub fn create_sample_events(app: AppHandle) -> Result<String, String> {
@@ -227,7 +311,7 @@ pub fn create_sample_events(app: AppHandle) -> Result<String, String> {
     let file_path = export_dir.join("events-sample.json");
     
     // Write to file
-    fs::write(&file_path, sample_events).map_err(|e| e.to_string())?;
+    write_atomic(&file_path, sample_events.as_bytes())?;
     
     // Return the file path as a string
     Ok(file_path.to_string_lossy().to_string())
@@ -237,12 +321,12 @@ pub fn create_sample_events(app: AppHandle) -> Result<String, String> {
 /// the Files app will display the app folder on device installs (TestFlight/App Store).
 #[tauri::command]
 pub fn ensure_documents_placeholder(app: AppHandle) -> Result<String, String> {
-    let export_dir = get_export_directory(&app)?;
+    let export_dir = export_root(&app)?;
     let placeholder = export_dir.join("CircuitAssistant-README.txt");
 
     if !placeholder.exists() {
         let content = "Circuit Assistant app files go in this folder.\n";
-        fs::write(&placeholder, content).map_err(|e| e.to_string())?;
+        write_atomic(&placeholder, content.as_bytes())?;
     }
 
     Ok(placeholder.to_string_lossy().to_string())
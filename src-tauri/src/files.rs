@@ -0,0 +1,58 @@
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Temporary sibling of `path` used while a new version is being written.
+/// It lives in the same directory so the final rename never crosses file systems.
+pub fn temp_path_for(path: &Path) -> PathBuf {
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(format!(".tmp-{}", std::process::id()));
+    PathBuf::from(temp)
+}
+
+/// Flush a fully written temp file to disk and move it over `path`.
+/// Readers see either the old file or the new one, never a partial write.
+pub fn commit_temp_file(temp: &Path, path: &Path) -> Result<(), String> {
+    let result = File::open(temp)
+        .and_then(|file| file.sync_all())
+        .and_then(|_| fs::rename(temp, path));
+    if let Err(err) = result {
+        let _ = fs::remove_file(temp);
+        return Err(format!("Failed to save {}: {}", path.display(), err));
+    }
+
+    // Persist the rename itself; directories cannot be opened for syncing on Windows
+    #[cfg(unix)]
+    if let Some(dir) = path.parent() {
+        if let Ok(dir) = File::open(dir) {
+            let _ = dir.sync_all();
+        }
+    }
+
+    Ok(())
+}
+
+/// Write a file crash-safely: temp file, fsync, then rename over the target
+pub fn write_atomic(path: &Path, contents: &[u8]) -> Result<(), String> {
+    write_atomic_with(path, |file| {
+        file.write_all(contents)
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    })
+}
+
+/// Like `write_atomic`, for writers that stream into a file
+pub fn write_atomic_with<F>(path: &Path, write: F) -> Result<(), String>
+where
+    F: FnOnce(&mut File) -> Result<(), String>,
+{
+    let temp = temp_path_for(path);
+    let written = File::create(&temp)
+        .map_err(|e| format!("Failed to create {}: {}", temp.display(), e))
+        .and_then(|mut file| write(&mut file));
+    if let Err(err) = written {
+        let _ = fs::remove_file(&temp);
+        return Err(err);
+    }
+
+    commit_temp_file(&temp, path)
+}
@@ -0,0 +1,362 @@
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::{json, Map, Value};
+use tauri::State;
+use crate::commands::{get_platform, DbConnection};
+use crate::{db, sandbox};
+
+/// Setting holding this installation's id, created on first use
+const DEVICE_ID_SETTING: &str = "device.id";
+
+/// Setting holding the user-chosen name of this device
+const DEVICE_NAME_SETTING: &str = "device.name";
+
+/// Fields that change on every write and say nothing about what was edited
+const UNTRACKED_FIELDS: &[&str] = &["id", "updatedAt", "revision"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Operation {
+    Insert,
+    Update,
+    Delete,
+}
+
+impl Operation {
+    fn as_str(self) -> &'static str {
+        match self {
+            Operation::Insert => "insert",
+            Operation::Update => "update",
+            Operation::Delete => "delete",
+        }
+    }
+
+    fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "insert" => Ok(Operation::Insert),
+            "update" => Ok(Operation::Update),
+            "delete" => Ok(Operation::Delete),
+            other => Err(format!("Unknown change operation: {}", other)),
+        }
+    }
+}
+
+/// One field's value before and after a change; left out while the record does not have the field
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FieldChange {
+    pub field: String,
+    #[serde(default, skip_serializing_if = "Option::is_none", deserialize_with = "present")]
+    pub before: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none", deserialize_with = "present")]
+    pub after: Option<Value>,
+}
+
+/// A value that is there, even if it is null; a missing one is None through `serde(default)`
+fn present<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Value>, D::Error> {
+    Value::deserialize(deserializer).map(Some)
+}
+
+/// One entry of the change log
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordChange {
+    pub id: i64,
+    pub model_name: String,
+    pub record_id: String,
+    pub operation: Operation,
+    pub changed_at: String,
+    pub device_id: String,
+    pub device_name: String,
+    pub changes: Vec<FieldChange>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceInfo {
+    pub id: String,
+    pub name: String,
+}
+
+fn device_info(conn: &Connection) -> Result<DeviceInfo, String> {
+    let id = match db::get_setting(conn, DEVICE_ID_SETTING)? {
+        Some(id) => id,
+        None => {
+            let id = db::new_record_id();
+            db::set_setting(conn, DEVICE_ID_SETTING, &id)?;
+            id
+        }
+    };
+    let name = db::get_setting(conn, DEVICE_NAME_SETTING)?.unwrap_or_else(get_platform);
+    Ok(DeviceInfo { id, name })
+}
+
+/// Field-level differences between two versions of a record, in field name order
+fn diff_fields(before: Option<&Value>, after: Option<&Value>) -> Vec<FieldChange> {
+    let empty = Map::new();
+    let before = before.and_then(Value::as_object).unwrap_or(&empty);
+    let after = after.and_then(Value::as_object).unwrap_or(&empty);
+
+    let mut fields: Vec<&String> = before.keys().chain(after.keys()).collect();
+    fields.sort();
+    fields.dedup();
+
+    fields
+        .into_iter()
+        .filter(|field| !UNTRACKED_FIELDS.contains(&field.as_str()))
+        .filter_map(|field| {
+            let old = before.get(field).cloned();
+            let new = after.get(field).cloned();
+            (old != new).then(|| FieldChange { field: field.clone(), before: old, after: new })
+        })
+        .collect()
+}
+
+/// Every field of a deleted record, including empty and untracked ones, so reverting the delete
+/// brings back the whole record
+fn snapshot_fields(before: Option<&Value>) -> Vec<FieldChange> {
+    let mut fields: Vec<FieldChange> = before
+        .and_then(Value::as_object)
+        .into_iter()
+        .flatten()
+        .filter(|(field, _)| field.as_str() != "id")
+        .map(|(field, value)| FieldChange { field: field.clone(), before: Some(value.clone()), after: None })
+        .collect();
+    fields.sort_by(|a, b| a.field.cmp(&b.field));
+    fields
+}
+
+/// Append a change to the log. `before` is None for inserts, `after` is None for deletes.
+/// Updates that change nothing are not logged.
+pub fn record_change(
+    conn: &Connection,
+    model_name: &str,
+    record_id: &str,
+    before: Option<&Value>,
+    after: Option<&Value>,
+) -> Result<(), String> {
+    let operation = match (before, after) {
+        (None, _) => Operation::Insert,
+        (Some(_), None) => Operation::Delete,
+        (Some(_), Some(_)) => Operation::Update,
+    };
+    let changes = match after {
+        Some(_) => diff_fields(before, after),
+        None => snapshot_fields(before),
+    };
+    if operation == Operation::Update && changes.is_empty() {
+        return Ok(());
+    }
+
+    let device = device_info(conn)?;
+    let changes = serde_json::to_string(&changes)
+        .map_err(|e| format!("Failed to serialize change: {}", e))?;
+    conn.execute(
+        "INSERT INTO record_changes (model_name, record_id, operation, changed_at, device_id, device_name, changes)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![model_name, record_id, operation.as_str(), db::now_timestamp(), device.id, device.name, changes],
+    )
+    .map(|_| ())
+    .map_err(|e| format!("Failed to record change: {}", e))
+}
+
+/// Map a `record_changes` row selected with `CHANGE_COLUMNS`
+fn read_change(row: &rusqlite::Row) -> rusqlite::Result<RecordChange> {
+    let invalid = |index, e: String| {
+        rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, e.into())
+    };
+    let operation: String = row.get(3)?;
+    let changes: String = row.get(7)?;
+    Ok(RecordChange {
+        id: row.get(0)?,
+        model_name: row.get(1)?,
+        record_id: row.get(2)?,
+        operation: Operation::parse(&operation).map_err(|e| invalid(3, e))?,
+        changed_at: row.get(4)?,
+        device_id: row.get(5)?,
+        device_name: row.get(6)?,
+        changes: serde_json::from_str(&changes).map_err(|e| invalid(7, e.to_string()))?,
+    })
+}
+
+const CHANGE_COLUMNS: &str =
+    "id, model_name, record_id, operation, changed_at, device_id, device_name, changes";
+
+fn get_change(conn: &Connection, change_id: i64) -> Result<RecordChange, String> {
+    conn.query_row(
+        &format!("SELECT {} FROM record_changes WHERE id = ?1", CHANGE_COLUMNS),
+        params![change_id],
+        read_change,
+    )
+    .map_err(|e| format!("Change {} not found: {}", change_id, e))
+}
+
+/// Fields whose current value no longer matches what `change` left behind.
+/// Changes logged before missing fields were told apart have null for them, so null and missing match.
+fn changed_since(change: &RecordChange, current: Option<&Value>) -> Vec<String> {
+    change
+        .changes
+        .iter()
+        .filter(|c| {
+            current.and_then(|record| record.get(&c.field)).unwrap_or(&Value::Null) != c.after.as_ref().unwrap_or(&Value::Null)
+        })
+        .map(|c| c.field.clone())
+        .collect()
+}
+
+/// Undo one change. Fails if the record has been edited since, unless `force` is set.
+/// The revert is itself logged, so it can be undone too.
+pub fn revert(conn: &Connection, change_id: i64, force: bool) -> Result<Option<Value>, String> {
+    let change = get_change(conn, change_id)?;
+    let current = db::find_record(conn, &change.model_name, &change.record_id)?;
+
+    if change.operation == Operation::Delete {
+        if current.is_some() {
+            return Err(format!(
+                "Cannot revert change {}: {} record {} exists again",
+                change.id, change.model_name, change.record_id
+            ));
+        }
+    } else {
+        if current.is_none() {
+            return Err(format!(
+                "Cannot revert change {}: {} record {} no longer exists",
+                change.id, change.model_name, change.record_id
+            ));
+        }
+        let conflicts = changed_since(&change, current.as_ref());
+        if !conflicts.is_empty() && !force {
+            return Err(format!(
+                "Cannot revert change {}: {} record {} has been edited since ({}). Revert with force to overwrite.",
+                change.id,
+                change.model_name,
+                change.record_id,
+                conflicts.join(", ")
+            ));
+        }
+    }
+
+    match change.operation {
+        Operation::Insert => {
+            db::delete_record_in_tx(conn, &change.model_name, &change.record_id)?;
+            Ok(None)
+        }
+        Operation::Update => {
+            // Fields the update added are removed again rather than set to null
+            let mut record = current.unwrap_or_default();
+            if let Some(fields) = record.as_object_mut() {
+                for c in change.changes {
+                    match c.before {
+                        Some(before) => fields.insert(c.field, before),
+                        None => fields.remove(&c.field),
+                    };
+                }
+            }
+            db::replace_record_in_tx(conn, &change.model_name, &change.record_id, record).map(Some)
+        }
+        Operation::Delete => {
+            let mut record: Map<String, Value> = change
+                .changes
+                .into_iter()
+                .filter_map(|c| Some((c.field, c.before?)))
+                .collect();
+            record.insert("id".to_string(), json!(change.record_id));
+            db::reinsert_record(conn, &change.model_name, Value::Object(record)).map(Some)
+        }
+    }
+}
+
+/// The change log of one record, oldest first
+#[tauri::command]
+pub async fn get_record_history(
+    db: State<'_, DbConnection>,
+    model_name: String,
+    record_id: String,
+) -> Result<Vec<RecordChange>, String> {
+    sandbox::model_name(&model_name)?;
+    db.read(move |conn| {
+        let mut stmt = conn
+            .prepare(&format!(
+                "SELECT {} FROM record_changes WHERE model_name = ?1 AND record_id = ?2 ORDER BY id",
+                CHANGE_COLUMNS
+            ))
+            .map_err(|e| format!("Failed to prepare query: {}", e))?;
+        let rows = stmt
+            .query_map(params![model_name, record_id], read_change)
+            .map_err(|e| format!("Failed to query history: {}", e))?;
+
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to read change: {}", e))
+    })
+    .await
+}
+
+/// Undo a change from the log.
+/// Returns the record as it is after the revert, or null if the revert deleted it.
+#[tauri::command]
+pub async fn revert_change(
+    db: State<'_, DbConnection>,
+    change_id: i64,
+    force: Option<bool>,
+) -> Result<Option<Value>, String> {
+    db.write(move |conn| {
+        let tx = conn
+            .unchecked_transaction()
+            .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+        let record = revert(&tx, change_id, force.unwrap_or(false))?;
+        tx.commit().map_err(|e| format!("Failed to commit revert: {}", e))?;
+        Ok(record)
+    })
+    .await
+}
+
+/// The id and name recorded with changes made on this device
+#[tauri::command]
+pub async fn get_device_info(db: State<'_, DbConnection>) -> Result<DeviceInfo, String> {
+    db.write(|conn| device_info(conn)).await
+}
+
+#[tauri::command]
+pub async fn set_device_name(db: State<'_, DbConnection>, name: String) -> Result<(), String> {
+    db.write(move |conn| {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err("Device name cannot be empty".to_string());
+        }
+        db::set_setting(conn, DEVICE_NAME_SETTING, name)
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn last_change(conn: &Connection) -> i64 {
+        conn.query_row("SELECT MAX(id) FROM record_changes", [], |row| row.get(0)).unwrap()
+    }
+
+    #[test]
+    fn reverting_an_update_removes_the_fields_it_added() {
+        let conn = db::test_connection();
+        let person = db::insert_record(&conn, "PersonObject", json!({"nameFirst": "Ann", "nameLast": "Lee"})).unwrap();
+        let id = person["id"].as_str().unwrap();
+        db::update_record(&conn, "PersonObject", id, json!({"nameFirst": "Anne", "dateBirth": "1970-01-01"})).unwrap();
+
+        let change = get_change(&conn, last_change(&conn)).unwrap();
+        let added = change.changes.iter().find(|c| c.field == "dateBirth").unwrap();
+        assert_eq!((added.before.as_ref(), added.after.as_ref()), (None, Some(&json!("1970-01-01"))));
+
+        let reverted = revert(&conn, change.id, false).unwrap().unwrap();
+        assert_eq!(reverted["nameFirst"], "Ann");
+        assert!(reverted.get("dateBirth").is_none(), "{}", reverted);
+        assert_eq!(db::get_record(&conn, "PersonObject", id).unwrap(), reverted);
+    }
+
+    #[test]
+    fn missing_and_null_values_are_told_apart() {
+        let logged: FieldChange = serde_json::from_str(r#"{"field": "a", "before": null}"#).unwrap();
+        assert_eq!((logged.before, logged.after), (Some(Value::Null), None));
+        let change = FieldChange { field: "a".to_string(), before: None, after: Some(json!(1)) };
+        assert_eq!(serde_json::to_value(&change).unwrap(), json!({"field": "a", "after": 1}));
+    }
+}
@@ -0,0 +1,942 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use chrono_tz::Tz;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use crate::ics_imports::Recurrence;
+use crate::ics_updates::{self, SentEvents};
+use crate::models::{self, CircuitEvent, Congregation, Model, Pss, PssClassWorkshop, Routing, User, Venue, Visit};
+use crate::recurrence::{self, Frequency, Rule, Series, Until};
+use crate::time_zones::{self, Zone};
+
+/// Domain part of the UIDs of events whose record has no calendar UID yet
+const UID_DOMAIN: &str = "circuitassistant.org";
+
+/// Days from the first day of a visit (Tuesday) through its last (Sunday)
+const VISIT_DAYS: i64 = 6;
+
+/// Length of timed events whose end time is not stored
+const DEFAULT_EVENT_MINUTES: i64 = 60;
+
+/// Length of congregation meetings
+const MEETING_MINUTES: i64 = 105;
+
+/// Longest line allowed by RFC 5545, in octets, not counting the line break
+const MAX_LINE_OCTETS: usize = 75;
+
+/// Kinds of records an ICS export can include
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum EventSource {
+    /// Circuit overseer visits, from the first to the last day of the visit week
+    Visits,
+    /// Circuit assemblies and other circuit events, with their rehearsals
+    CircuitEvents,
+    /// Pioneer service school classes, with book pickups and workshop rehearsals
+    PssClasses,
+    /// Routing weeks with a special event time or venue
+    RoutingEvents,
+    /// Weekly midweek and weekend meetings of the congregations, moved as arranged for visit weeks
+    Meetings,
+    /// Events of the calendar page passed in `events`
+    CalendarEvents,
+}
+
+/// An event of the calendar page (the format of its JSON import files)
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CalendarEvent {
+    pub title: String,
+    pub date: String,
+    pub time: Option<String>,
+    /// Last day of an event that lasts several days
+    pub end_date: Option<String>,
+    pub end_time: Option<String>,
+    pub location: Option<String>,
+    pub description: Option<String>,
+    /// Keeps the event's UID stable when it is exported again after a change
+    pub uid: Option<String>,
+    /// How the event repeats, as `read_ics_file` returns it
+    pub recurrence: Option<Recurrence>,
+    /// Set on an event that changes one occurrence of a recurring event with the same UID
+    pub recurrence_id: Option<String>,
+    /// IANA time zone of `time` and `endTime`; the export's time zone when missing
+    pub time_zone: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct IcsExportOptions {
+    /// First day to include (`YYYY-MM-DD`); no lower limit when missing
+    pub from: Option<String>,
+    /// Last day to include; no upper limit when missing
+    pub to: Option<String>,
+    /// Kinds of records to include; all of them when empty
+    pub sources: Vec<EventSource>,
+    pub events: Vec<CalendarEvent>,
+    /// IANA time zone (e.g. `Europe/Berlin`) of stored times whose venue and congregation have none.
+    /// Without it, the user's time zone is used, and without that the times are written as floating local times.
+    pub time_zone: Option<String>,
+}
+
+impl IcsExportOptions {
+    pub fn includes(&self, source: EventSource) -> bool {
+        self.sources.is_empty() || self.sources.contains(&source)
+    }
+}
+
+/// When an event starts or ends: a whole day, or a wall-clock time in the event's time zone
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventTime {
+    Date(NaiveDate),
+    Local(NaiveDateTime),
+}
+
+impl EventTime {
+    pub fn date(self) -> NaiveDate {
+        match self {
+            EventTime::Date(date) => date,
+            EventTime::Local(time) => time.date(),
+        }
+    }
+
+    /// All-day events sort before the timed events of their day
+    pub fn sort_key(self) -> NaiveDateTime {
+        match self {
+            EventTime::Date(date) => date.and_time(NaiveTime::MIN),
+            EventTime::Local(time) => time,
+        }
+    }
+}
+
+/// A VEVENT before it is written
+pub struct IcsEvent {
+    pub source: EventSource,
+    pub uid: String,
+    pub sequence: i64,
+    pub summary: String,
+    pub description: Option<String>,
+    pub location: Option<String>,
+    /// Latitude and longitude of the location
+    pub geo: Option<(f64, f64)>,
+    pub url: Option<String>,
+    pub categories: Vec<String>,
+    pub start: EventTime,
+    /// Exclusive end: the day after the last day of all-day events
+    pub end: EventTime,
+    /// How the event repeats; `start` and `end` are those of the first occurrence
+    pub series: Option<Series>,
+    /// Set when the event replaces one occurrence of the recurring event with the same UID
+    pub recurrence_id: Option<EventTime>,
+    /// IANA time zone of the event's times; floating local times when missing
+    pub time_zone: Option<Tz>,
+    /// Model name and id of the record the event is made from
+    pub record: Option<(&'static str, String)>,
+    /// Name and email address of whoever sends updates of the event
+    pub organizer: Option<(String, String)>,
+    /// Written with STATUS:CANCELLED, for cancellations of events sent before
+    pub cancelled: bool,
+}
+
+impl IcsEvent {
+    pub fn new(source: EventSource, uid: String, sequence: i64, summary: String, start: EventTime, end: EventTime) -> Self {
+        IcsEvent {
+            source,
+            uid,
+            sequence,
+            summary,
+            description: None,
+            location: None,
+            geo: None,
+            url: None,
+            categories: Vec::new(),
+            start,
+            end,
+            series: None,
+            recurrence_id: None,
+            time_zone: None,
+            record: None,
+            organizer: None,
+            cancelled: false,
+        }
+    }
+
+    fn for_record<M: Model>(mut self, id: Option<&str>) -> Self {
+        self.record = id.map(|id| (M::NAME, id.to_string()));
+        self
+    }
+
+    /// The zone the event's times are read in on this device; floating times are the device's local times
+    pub fn zone(&self) -> Zone {
+        self.time_zone.map_or(Zone::Device, Zone::Named)
+    }
+
+    fn at(mut self, place: Place) -> Self {
+        self.location = Some(place.text).filter(|text| !text.is_empty());
+        self.geo = place.geo;
+        self.time_zone = self.time_zone.or(place.time_zone);
+        self
+    }
+
+    /// Use a time zone for the event's times unless its venue has one
+    fn in_zone(mut self, time_zone: Option<Tz>) -> Self {
+        self.time_zone = self.time_zone.or(time_zone);
+        self
+    }
+
+    fn categories(mut self, categories: &[Option<String>]) -> Self {
+        self.categories = categories.iter().flatten().cloned().collect();
+        self
+    }
+}
+
+/// Where an event takes place, as written to LOCATION and GEO
+#[derive(Clone)]
+struct Place {
+    text: String,
+    geo: Option<(f64, f64)>,
+    time_zone: Option<Tz>,
+}
+
+/// Drop the line breaks and other control characters of a value that is not TEXT (a URI or parameter),
+/// so it cannot end its line and start properties of its own
+fn sanitize_value(text: &str) -> String {
+    text.chars().filter(|c| !c.is_control()).collect()
+}
+
+/// Escape a TEXT value (RFC 5545 section 3.3.11)
+pub fn escape_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ';' => escaped.push_str("\\;"),
+            ',' => escaped.push_str("\\,"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Fold a content line into lines of at most 75 octets, continued with a leading space.
+/// Never splits a UTF-8 character. Returns the line with its CRLF.
+pub fn fold_line(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len() + line.len() / MAX_LINE_OCTETS * 3 + 2);
+    let mut octets = 0;
+    for c in line.chars() {
+        if octets + c.len_utf8() > MAX_LINE_OCTETS {
+            folded.push_str("\r\n ");
+            octets = 1;
+        }
+        folded.push(c);
+        octets += c.len_utf8();
+    }
+    folded.push_str("\r\n");
+    folded
+}
+
+/// Write a date or date-time property, e.g. `DTSTART;VALUE=DATE:20251115`.
+/// Times without a time zone are written as floating local times, so every device reads them
+/// as the same wall-clock time.
+fn time_property(name: &str, time: EventTime, time_zone: Option<Tz>) -> String {
+    match (time, time_zone) {
+        (EventTime::Date(date), _) => format!("{};VALUE=DATE:{}", name, date.format("%Y%m%d")),
+        (EventTime::Local(time), Some(zone)) => format!("{};TZID={}:{}", name, zone.name(), time.format("%Y%m%dT%H%M%S")),
+        (EventTime::Local(time), None) => format!("{}:{}", name, time.format("%Y%m%dT%H%M%S")),
+    }
+}
+
+/// An RRULE value. UNTIL has the type of DTSTART: a date, a floating time, or UTC when DTSTART has a TZID
+/// (RFC 5545 section 3.3.10).
+fn rule_value(rule: &Rule, start: EventTime, time_zone: Option<Tz>) -> String {
+    // Without a time zone, a UTC UNTIL keeps its UTC wall-clock time rather than the device's
+    let local = |until: Until| match (until, time_zone) {
+        (until, Some(zone)) => until.local(Zone::Named(zone)),
+        (Until::Local(until), None) => until,
+        (Until::Utc(until), None) => until.naive_utc(),
+    };
+    rule.to_ics(|until| match (start, time_zone, until) {
+        (EventTime::Date(_), _, until) => local(until).format("%Y%m%d").to_string(),
+        (EventTime::Local(_), Some(_), Until::Utc(until)) => utc_stamp(until),
+        (EventTime::Local(_), Some(zone), Until::Local(until)) => utc_stamp(Zone::Named(zone).to_utc(until)),
+        (EventTime::Local(_), None, until) => local(until).format("%Y%m%dT%H%M%S").to_string(),
+    })
+}
+
+fn utc_stamp(time: DateTime<Utc>) -> String {
+    time.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// The VTIMEZONE blocks of the time zones the events are written in, covering the years of their times
+fn time_zone_lines(events: &[IcsEvent], stamp: DateTime<Utc>) -> Vec<String> {
+    let mut years: BTreeMap<&str, (Tz, i32, i32)> = BTreeMap::new();
+    for event in events {
+        let (Some(zone), EventTime::Local(start)) = (event.time_zone, event.start) else { continue };
+        let mut last = event.end.date().year();
+        if let Some(series) = &event.series {
+            last = series.dates.iter().map(|date| date.date().year()).fold(last, i32::max);
+            match series.rule.as_ref().map(|rule| rule.until) {
+                // Open-ended series go on past the export
+                Some(None) => last = last.max(stamp.year()),
+                Some(Some(until)) => last = last.max(until.local(Zone::Named(zone)).year()),
+                None => {}
+            }
+        }
+        let entry = years.entry(zone.name()).or_insert((zone, start.year(), last));
+        entry.1 = entry.1.min(start.year());
+        entry.2 = entry.2.max(last);
+    }
+    years
+        .values()
+        .flat_map(|(zone, first, last)| time_zones::vtimezone(*zone, *first, *last))
+        .collect()
+}
+
+/// The VEVENT lines of an event, not yet folded
+fn event_lines(event: &IcsEvent, stamp: DateTime<Utc>) -> Vec<String> {
+    let mut lines = vec![
+        "BEGIN:VEVENT".to_string(),
+        format!("UID:{}", escape_text(&event.uid)),
+        format!("DTSTAMP:{}", utc_stamp(stamp)),
+        format!("SEQUENCE:{}", event.sequence),
+    ];
+    if let Some((name, email)) = &event.organizer {
+        lines.push(format!(
+            "ORGANIZER;CN=\"{}\":mailto:{}",
+            sanitize_value(name).replace('"', ""),
+            sanitize_value(email)
+        ));
+    }
+    let time_zone = event.time_zone;
+    lines.push(time_property("DTSTART", event.start, time_zone));
+    lines.push(time_property("DTEND", event.end, time_zone));
+    if let Some(occurrence) = event.recurrence_id {
+        lines.push(time_property("RECURRENCE-ID", occurrence, time_zone));
+    }
+    if let Some(series) = &event.series {
+        if let Some(rule) = &series.rule {
+            lines.push(format!("RRULE:{}", rule_value(rule, event.start, time_zone)));
+        }
+        lines.extend(series.dates.iter().map(|date| time_property("RDATE", *date, time_zone)));
+        lines.extend(series.exdates.iter().map(|date| time_property("EXDATE", *date, time_zone)));
+    }
+    lines.push(format!("SUMMARY:{}", escape_text(&event.summary)));
+    if let Some(description) = &event.description {
+        lines.push(format!("DESCRIPTION:{}", escape_text(description)));
+    }
+    if let Some(location) = &event.location {
+        lines.push(format!("LOCATION:{}", escape_text(location)));
+    }
+    if let Some((latitude, longitude)) = event.geo {
+        lines.push(format!("GEO:{};{}", latitude, longitude));
+    }
+    if let Some(url) = &event.url {
+        lines.push(format!("URL:{}", sanitize_value(url)));
+    }
+    if !event.categories.is_empty() {
+        let categories: Vec<String> = event.categories.iter().map(|category| escape_text(category)).collect();
+        lines.push(format!("CATEGORIES:{}", categories.join(",")));
+    }
+    lines.push(if event.cancelled { "STATUS:CANCELLED" } else { "STATUS:CONFIRMED" }.to_string());
+    lines.push("END:VEVENT".to_string());
+    lines
+}
+
+/// Write events as an iCalendar file. `method` is the iTIP method (RFC 5546) of files that update
+/// or cancel events sent before, e.g. `REQUEST` or `CANCEL`.
+pub fn write_calendar(events: &[IcsEvent], method: Option<&str>, stamp: DateTime<Utc>) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//Circuit Assistant//EN".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+    ];
+    if let Some(method) = method {
+        lines.push(format!("METHOD:{}", method));
+    }
+    lines.extend(time_zone_lines(events, stamp));
+    for event in events {
+        lines.extend(event_lines(event, stamp));
+    }
+    lines.push("END:VCALENDAR".to_string());
+    lines.iter().map(|line| fold_line(line)).collect()
+}
+
+fn parse_date(text: &str) -> Option<NaiveDate> {
+    let text = text.trim();
+    NaiveDate::parse_from_str(text, "%Y-%m-%d")
+        .or_else(|_| NaiveDate::parse_from_str(text, "%Y/%m/%d"))
+        .ok()
+        .or_else(|| NaiveDateTime::parse_from_str(text.get(..19)?, "%Y-%m-%dT%H:%M:%S").ok().map(|time| time.date()))
+}
+
+/// Read a stored time of day: `19:00`, `19:00:00`, `7:00 PM`, `7 pm` or an ISO date-time
+fn parse_time(value: &Value) -> Option<NaiveTime> {
+    let text = value.as_str()?.trim();
+    if let Some(time) = text.get(..19).and_then(|start| NaiveDateTime::parse_from_str(start, "%Y-%m-%dT%H:%M:%S").ok()) {
+        return Some(time.time());
+    }
+    let mut upper = text.to_uppercase().replace('.', ":");
+    if !upper.contains(':') {
+        // `7 PM` has no minutes, which chrono requires
+        let hour_end = upper.find(|c: char| !c.is_ascii_digit()).unwrap_or(upper.len());
+        upper.insert_str(hour_end, ":00");
+    }
+    ["%H:%M", "%H:%M:%S", "%I:%M %p", "%I:%M%p"]
+        .iter()
+        .find_map(|format| NaiveTime::parse_from_str(&upper, format).ok())
+}
+
+/// Text of a loosely typed field: a string, a number, or an object's name or label
+fn value_text(value: &Value) -> Option<String> {
+    let text = match value {
+        Value::String(text) => text.trim().to_string(),
+        Value::Number(number) => number.to_string(),
+        Value::Object(object) => return ["name", "label", "title"].iter().find_map(|key| value_text(object.get(*key)?)),
+        _ => return None,
+    };
+    Some(text).filter(|text| !text.is_empty())
+}
+
+/// A timed event from a date and optional start and end times; an all-day event without a start time
+fn event_times(date: NaiveDate, start: Option<&Value>, end: Option<&Value>) -> (EventTime, EventTime) {
+    match start.and_then(parse_time) {
+        Some(start) => {
+            let start = date.and_time(start);
+            let end = end
+                .and_then(parse_time)
+                .map(|end| date.and_time(end))
+                .filter(|end| *end > start)
+                .unwrap_or(start + Duration::minutes(DEFAULT_EVENT_MINUTES));
+            (EventTime::Local(start), EventTime::Local(end))
+        }
+        None => all_day(date, 1),
+    }
+}
+
+fn all_day(date: NaiveDate, days: i64) -> (EventTime, EventTime) {
+    (EventTime::Date(date), EventTime::Date(date + Duration::days(days)))
+}
+
+/// The UID of a record's event: the one stored on the record, or one made from the record's id
+fn event_uid(stored: Option<&str>, record_id: Option<&str>, suffix: &str) -> String {
+    match stored.map(str::trim).filter(|uid| !uid.is_empty()) {
+        Some(uid) => uid.to_string(),
+        None => format!("{}{}@{}", record_id.unwrap_or_default(), suffix, UID_DOMAIN),
+    }
+}
+
+/// Records the exported events refer to
+struct Lookup {
+    venues: HashMap<String, Venue>,
+    congregations: HashMap<String, Congregation>,
+    /// The user's time zone, for times whose venue and congregation have none
+    user_zone: Option<Tz>,
+}
+
+fn by_id<M: Model>(records: Vec<M>, id: fn(&M) -> Option<&String>) -> HashMap<String, M> {
+    records
+        .into_iter()
+        .filter_map(|record| Some((id(&record)?.clone(), record)))
+        .collect()
+}
+
+impl Lookup {
+    fn load(conn: &Connection) -> Result<Self, String> {
+        Ok(Lookup {
+            venues: by_id(models::read_all::<Venue>(conn)?, |venue| venue.id.as_ref()),
+            congregations: by_id(models::read_all::<Congregation>(conn)?, |congregation| congregation.id.as_ref()),
+            user_zone: models::read_all::<User>(conn)?
+                .iter()
+                .find_map(|user| time_zones::parse(&user.time_zone).ok()),
+        })
+    }
+
+    fn congregation_name(&self, id: Option<&String>) -> Option<String> {
+        let congregation = self.congregations.get(id?)?;
+        Some(congregation.name.clone()).filter(|name| !name.is_empty())
+    }
+
+    /// The time zone of a congregation, or else of its venue
+    fn congregation_zone(&self, id: Option<&String>) -> Option<Tz> {
+        let congregation = self.congregations.get(id?)?;
+        time_zones::parse(&congregation.time_zone)
+            .ok()
+            .or_else(|| self.venue_place(congregation.venue.as_ref()).time_zone)
+    }
+
+    /// The place of a venue field: the venue's name and address, or the field's own text
+    /// when it is not a venue id
+    fn place(&self, value: Option<&Value>) -> Place {
+        let text = value.and_then(value_text).unwrap_or_default();
+        let Some(venue) = self.venues.get(&text) else {
+            return Place { text, geo: None, time_zone: None };
+        };
+        let region = [venue.state_province.as_str(), venue.postal.as_str()]
+            .iter()
+            .filter(|part| !part.is_empty())
+            .copied()
+            .collect::<Vec<_>>()
+            .join(" ");
+        let text = [venue.name.as_str(), venue.street.as_str(), venue.city.as_str(), region.as_str()]
+            .iter()
+            .filter(|part| !part.trim().is_empty())
+            .copied()
+            .collect::<Vec<_>>()
+            .join(", ");
+        let geo = venue
+            .latitude
+            .trim()
+            .parse()
+            .ok()
+            .zip(venue.longitude.trim().parse().ok());
+        Place { text, geo, time_zone: time_zones::parse(&venue.time_zone).ok() }
+    }
+
+    fn venue_place(&self, id: Option<&String>) -> Place {
+        self.place(id.map(|id| Value::String(id.clone())).as_ref())
+    }
+}
+
+fn visit_events(conn: &Connection, lookup: &Lookup, events: &mut Vec<IcsEvent>) -> Result<(), String> {
+    for visit in models::read_all::<Visit>(conn)? {
+        let Some(date) = visit.date_start.as_deref().and_then(parse_date) else { continue };
+        let congregation = lookup.congregation_name(visit.congregation.as_ref());
+        let summary = match &congregation {
+            Some(name) => format!("Circuit Overseer Visit: {}", name),
+            None => "Circuit Overseer Visit".to_string(),
+        };
+        let venue = visit
+            .congregation
+            .as_ref()
+            .and_then(|id| lookup.congregations.get(id))
+            .and_then(|congregation| congregation.venue.as_ref());
+        let time_zone = lookup.congregation_zone(visit.congregation.as_ref());
+        let (start, end) = all_day(date, VISIT_DAYS);
+        events.push(
+            IcsEvent::new(EventSource::Visits, event_uid(visit.calendar_u_id.as_deref(), visit.id.as_deref(), ""), visit.calendar_sequence, summary, start, end)
+                .at(lookup.venue_place(venue))
+                .categories(&[Some("Visit".to_string()), congregation.clone()])
+                .for_record::<Visit>(visit.id.as_deref()),
+        );
+
+        // Field service arrangements of the visit week: weekday ones from Tuesday through Friday
+        let services = [
+            ("-service-morning", "Field Service (Morning)", &visit.weekday_morning_service_time, 0, Some(4)),
+            ("-service-afternoon", "Field Service (Afternoon)", &visit.weekday_afternoon_service_time, 0, Some(4)),
+            ("-service-saturday", "Field Service", &visit.saturday_service_time, 4, None),
+            ("-service-sunday", "Field Service", &visit.sunday_service_time, 5, None),
+        ];
+        for (suffix, title, time, day, count) in services {
+            let time = Value::String(time.clone());
+            if parse_time(&time).is_none() {
+                continue;
+            }
+            let (start, end) = event_times(date + Duration::days(day), Some(&time), None);
+            let summary = match &congregation {
+                Some(name) => format!("{}: {}", title, name),
+                None => title.to_string(),
+            };
+            let mut ics = IcsEvent::new(EventSource::Visits, event_uid(None, visit.id.as_deref(), suffix), visit.calendar_sequence, summary, start, end)
+                .at(lookup.venue_place(venue))
+                .in_zone(time_zone)
+                .categories(&[Some("Visit".to_string()), Some("Field Service".to_string())])
+                .for_record::<Visit>(visit.id.as_deref());
+            ics.series = count.map(|count| Series {
+                rule: Some(Rule { count: Some(count), ..Rule::every(Frequency::Daily) }),
+                ..Series::default()
+            });
+            events.push(ics);
+        }
+    }
+    Ok(())
+}
+
+fn circuit_event_events(conn: &Connection, lookup: &Lookup, events: &mut Vec<IcsEvent>) -> Result<(), String> {
+    for event in models::read_all::<CircuitEvent>(conn)? {
+        let event_type = event.event_type.as_ref().and_then(value_text);
+        let summary = event_type.clone().unwrap_or_else(|| "Circuit Event".to_string());
+        let url = event.meeting_url.clone().or_else(|| event.jwss_url.clone()).filter(|url| !url.trim().is_empty());
+
+        if let Some(date) = event.event_date.as_deref().and_then(parse_date) {
+            let (start, end) = all_day(date, 1);
+            let mut ics = IcsEvent::new(EventSource::CircuitEvents, event_uid(None, event.id.as_deref(), ""), 0, summary.clone(), start, end)
+                .at(lookup.venue_place(event.event_venue.as_ref()))
+                .categories(&[Some("Circuit Event".to_string()), event_type.clone()])
+                .for_record::<CircuitEvent>(event.id.as_deref());
+            ics.url = url;
+            events.push(ics);
+        }
+
+        let rehearsal_date = event.rehearsal_date.as_deref().and_then(parse_date);
+        if let (true, Some(date)) = (event.include_rehearsal_cal, rehearsal_date) {
+            let (start, end) = event_times(date, event.rehearsal_time_start.as_ref(), event.rehearsal_time_end.as_ref());
+            let uid = event_uid(event.rehearsal_calendar_u_id.as_deref(), event.id.as_deref(), "-rehearsal");
+            let venue = event.rehearsal_venue.as_ref().or(event.event_venue.as_ref());
+            events.push(
+                IcsEvent::new(EventSource::CircuitEvents, uid, event.rehearsal_calendar_sequence, format!("Rehearsal: {}", summary), start, end)
+                    .at(lookup.venue_place(venue))
+                    .categories(&[Some("Circuit Event".to_string()), Some("Rehearsal".to_string())])
+                    .for_record::<CircuitEvent>(event.id.as_deref()),
+            );
+        }
+    }
+    Ok(())
+}
+
+fn pss_events(conn: &Connection, lookup: &Lookup, events: &mut Vec<IcsEvent>) -> Result<(), String> {
+    let classes = models::read_all::<Pss>(conn)?;
+    for class in &classes {
+        if let Some(date) = class.class_date.as_deref().and_then(parse_date) {
+            let (start, end) = all_day(date, 1);
+            let mut ics = IcsEvent::new(EventSource::PssClasses, event_uid(None, class.id.as_deref(), ""), 0, "Pioneer Service School".to_string(), start, end)
+                .at(lookup.venue_place(class.class_venue.as_ref()))
+                .categories(&[Some("PSS".to_string())])
+                .for_record::<Pss>(class.id.as_deref());
+            ics.url = class.meeting_url.clone().filter(|url| !url.trim().is_empty());
+            events.push(ics);
+        }
+        if let Some(date) = class.book_pickup_date.as_deref().and_then(parse_date) {
+            let (start, end) = event_times(date, class.book_pickup_time.as_ref(), None);
+            let uid = event_uid(class.book_pickup_calendar_u_id.as_deref(), class.id.as_deref(), "-books");
+            events.push(
+                IcsEvent::new(EventSource::PssClasses, uid, class.book_pickup_calendar_sequence, "PSS Book Pickup".to_string(), start, end)
+                    .at(lookup.place(class.book_pickup_location.as_ref()))
+                    .categories(&[Some("PSS".to_string())])
+                    .for_record::<Pss>(class.id.as_deref()),
+            );
+        }
+    }
+
+    let with_workshops: Vec<&String> = classes
+        .iter()
+        .filter(|class| class.include_workshop_cal)
+        .filter_map(|class| class.id.as_ref())
+        .collect();
+    for workshop in models::read_all::<PssClassWorkshop>(conn)? {
+        if !with_workshops.contains(&&workshop.pss_class) {
+            continue;
+        }
+        let Some(date) = workshop.rehearsal_date.as_deref().and_then(parse_date) else { continue };
+        let (start, end) = event_times(date, workshop.rehearsal_time_start.as_ref(), workshop.rehearsal_time_end.as_ref());
+        let summary = match workshop.class_workshop_assignment_number.as_ref().and_then(value_text) {
+            Some(number) => format!("PSS Workshop {} Rehearsal", number),
+            None => "PSS Workshop Rehearsal".to_string(),
+        };
+        events.push(
+            IcsEvent::new(EventSource::PssClasses, event_uid(None, workshop.id.as_deref(), "-rehearsal"), workshop.calendar_sequence, summary, start, end)
+                .at(lookup.place(workshop.rehearsal_venue.as_ref()))
+                .categories(&[Some("PSS".to_string()), Some("Rehearsal".to_string())])
+                .for_record::<PssClassWorkshop>(workshop.id.as_deref()),
+        );
+    }
+    Ok(())
+}
+
+fn routing_events(conn: &Connection, lookup: &Lookup, events: &mut Vec<IcsEvent>) -> Result<(), String> {
+    for routing in models::read_all::<Routing>(conn)? {
+        let has_time = routing.special_event_start_time.as_ref().and_then(parse_time).is_some();
+        if !has_time && routing.special_event_venue.is_none() {
+            continue;
+        }
+        let Some(date) = routing.date_start.as_deref().and_then(parse_date) else { continue };
+        let event_type = routing.event_type.as_ref().and_then(value_text);
+        let congregation = lookup.congregation_name(routing.congregation.as_ref());
+        let summary = match (&event_type, &congregation) {
+            (Some(kind), Some(name)) => format!("{}: {}", kind, name),
+            (Some(text), None) | (None, Some(text)) => text.clone(),
+            (None, None) => "Special Event".to_string(),
+        };
+        let (start, end) = event_times(date, routing.special_event_start_time.as_ref(), None);
+        let mut ics = IcsEvent::new(EventSource::RoutingEvents, event_uid(routing.calendar_u_id.as_deref(), routing.id.as_deref(), ""), routing.calendar_sequence, summary, start, end)
+            .at(lookup.venue_place(routing.special_event_venue.as_ref()))
+            .in_zone(lookup.congregation_zone(routing.congregation.as_ref()))
+            .categories(&[Some("Routing".to_string()), event_type])
+            .for_record::<Routing>(routing.id.as_deref());
+        ics.description = Some(routing.notes.trim().to_string()).filter(|notes| !notes.is_empty());
+        events.push(ics);
+    }
+    Ok(())
+}
+
+/// A congregation's weekly meeting from its stored day and time
+struct Meeting {
+    weekday: chrono::Weekday,
+    time: NaiveTime,
+}
+
+impl Meeting {
+    fn read(day: Option<&Value>, time: Option<&Value>) -> Option<Meeting> {
+        Some(Meeting { weekday: recurrence::weekday_value(day?)?, time: parse_time(time?)? })
+    }
+
+    /// The meeting's day in the week (Monday through Sunday) of `date`
+    fn in_week_of(&self, date: NaiveDate) -> NaiveDate {
+        let monday = date - Duration::days(date.weekday().num_days_from_monday().into());
+        recurrence::next_weekday(monday, self.weekday)
+    }
+}
+
+/// Congregation meetings have no start date of their own; their series start in the week the congregation
+/// was created, so they are the same in every export whatever its range
+fn meeting_events(conn: &Connection, lookup: &Lookup, events: &mut Vec<IcsEvent>) -> Result<(), String> {
+    let visits = models::read_all::<Visit>(conn)?;
+    let length = Duration::minutes(MEETING_MINUTES);
+    for (id, congregation) in &lookup.congregations {
+        // Stored records always have a creation time; without one the meetings cannot be placed
+        let Some(since) = congregation.created_at.as_deref().and_then(parse_date) else { continue };
+        let name = Some(congregation.name.clone()).filter(|name| !name.is_empty());
+        let place = lookup.venue_place(congregation.venue.as_ref());
+        let time_zone = lookup.congregation_zone(Some(id));
+        let visit_weeks: Vec<&Visit> = visits
+            .iter()
+            .filter(|visit| visit.congregation.as_ref() == Some(id))
+            .collect();
+
+        let meetings = [
+            ("-midweek", "Midweek Meeting", &congregation.midweek_meeting_day, &congregation.midweek_meeting_time),
+            ("-weekend", "Weekend Meeting", &congregation.weekend_meeting_day, &congregation.weekend_meeting_time),
+        ];
+        for (suffix, title, day, time) in meetings {
+            let Some(meeting) = Meeting::read(day.as_ref(), time.as_ref()) else { continue };
+            let uid = event_uid(None, Some(id), suffix);
+            let summary = match &name {
+                Some(name) => format!("{}: {}", title, name),
+                None => title.to_string(),
+            };
+            let first = meeting.in_week_of(since).and_time(meeting.time);
+            let mut series = Series { rule: Some(Rule::weekly(meeting.weekday)), ..Series::default() };
+
+            // During a visit the midweek meeting is on Tuesday, and the weekend meeting may be moved
+            for visit in &visit_weeks {
+                let Some(visit_start) = visit.date_start.as_deref().and_then(parse_date) else { continue };
+                let moved = match suffix {
+                    "-midweek" => parse_time(&Value::String(visit.tuesday_meeting_time.clone()))
+                        .or(Some(meeting.time))
+                        .map(|time| visit_start.and_time(time)),
+                    _ => {
+                        let day = visit.weekend_meeting_day.as_ref().and_then(recurrence::weekday_value);
+                        let time = parse_time(&Value::String(visit.weekend_meeting_time.clone()));
+                        (day.is_some() || time.is_some()).then(|| {
+                            let day = recurrence::next_weekday(visit_start, day.unwrap_or(meeting.weekday));
+                            day.and_time(time.unwrap_or(meeting.time))
+                        })
+                    }
+                };
+                let regular = meeting.in_week_of(visit_start).and_time(meeting.time);
+                let Some(moved) = moved.filter(|moved| *moved != regular && regular >= first) else { continue };
+                series.exdates.push(EventTime::Local(regular));
+                let mut ics = IcsEvent::new(
+                    EventSource::Meetings,
+                    uid.clone(),
+                    visit.calendar_sequence,
+                    format!("{} (Circuit Overseer Visit)", summary),
+                    EventTime::Local(moved),
+                    EventTime::Local(moved + length),
+                )
+                .at(place.clone())
+                .in_zone(time_zone)
+                .categories(&[Some("Meeting".to_string()), Some("Visit".to_string())]);
+                ics.recurrence_id = Some(EventTime::Local(regular));
+                events.push(ics);
+            }
+
+            let mut ics = IcsEvent::new(EventSource::Meetings, uid, 0, summary, EventTime::Local(first), EventTime::Local(first + length))
+                .at(place.clone())
+                .in_zone(time_zone)
+                .categories(&[Some("Meeting".to_string()), name.clone()]);
+            ics.series = Some(series);
+            events.push(ics);
+        }
+    }
+    Ok(())
+}
+
+/// How a calendar page event repeats, read from the RRULE text and occurrence ids `read_ics_file` returns
+fn calendar_page_series(event: &CalendarEvent) -> Result<Option<Series>, String> {
+    let Some(recurrence) = &event.recurrence else {
+        return Ok(None);
+    };
+    let occurrences = |ids: &[String]| -> Result<Vec<EventTime>, String> {
+        ids.iter()
+            .map(|id| recurrence::parse_occurrence_id(id).ok_or_else(|| format!("{}: invalid occurrence {}", event.title, id)))
+            .collect()
+    };
+    let rule = match &recurrence.rule {
+        Some(rule) => Some(Rule::parse(rule).map_err(|e| format!("{}: {}", event.title, e))?),
+        None => None,
+    };
+    Ok(Some(Series {
+        rule,
+        dates: occurrences(&recurrence.dates)?,
+        exdates: occurrences(&recurrence.exdates)?,
+    }))
+}
+
+fn calendar_page_events(source: &[CalendarEvent], events: &mut Vec<IcsEvent>) -> Result<(), String> {
+    for event in source {
+        let Some(date) = parse_date(&event.date) else { continue };
+        let time = event.time.clone().map(Value::String);
+        let end_time = event.end_time.clone().map(Value::String);
+        let end_date = event.end_date.as_deref().and_then(parse_date).filter(|end| *end > date);
+        let (start, end) = match (event_times(date, time.as_ref(), end_time.as_ref()), end_date) {
+            ((EventTime::Date(_), _), Some(last)) => all_day(date, (last - date).num_days() + 1),
+            ((EventTime::Local(start), _), Some(last)) => {
+                let end = end_time.as_ref().and_then(parse_time).map(|time| last.and_time(time));
+                (EventTime::Local(start), EventTime::Local(end.unwrap_or(start + Duration::minutes(DEFAULT_EVENT_MINUTES))))
+            }
+            (times, None) => times,
+        };
+        let uid = match &event.uid {
+            Some(uid) => uid.clone(),
+            None => {
+                // Same event, same UID: made from what identifies the event
+                let digest = Sha256::digest(format!("{}\n{}\n{}", event.title, event.date, event.time.as_deref().unwrap_or_default()));
+                let hex: String = digest.iter().take(12).map(|byte| format!("{:02x}", byte)).collect();
+                format!("{}@{}", hex, UID_DOMAIN)
+            }
+        };
+        let mut ics = IcsEvent::new(EventSource::CalendarEvents, uid, 0, event.title.clone(), start, end);
+        ics.location = event.location.clone().filter(|location| !location.trim().is_empty());
+        ics.description = event.description.clone().filter(|description| !description.trim().is_empty());
+        ics.series = calendar_page_series(event)?;
+        ics.time_zone = time_zones::parse_optional(event.time_zone.as_deref()).map_err(|e| format!("{}: {}", event.title, e))?;
+        ics.recurrence_id = match &event.recurrence_id {
+            Some(id) => Some(recurrence::parse_occurrence_id(id).ok_or_else(|| format!("{}: invalid occurrence {}", event.title, id))?),
+            None => None,
+        };
+        events.push(ics);
+    }
+    Ok(())
+}
+
+/// Whether an event, or any occurrence of a recurring one, falls into the date range
+fn overlaps(event: &IcsEvent, from: Option<NaiveDate>, to: Option<NaiveDate>) -> bool {
+    // The exclusive end of an all-day event is the day after its last day
+    let last_day = match event.end {
+        EventTime::Date(end) => end.pred_opt().unwrap_or(end),
+        EventTime::Local(end) => end.date(),
+    };
+    let Some(series) = &event.series else {
+        return from.is_none_or(|from| last_day >= from) && to.is_none_or(|to| event.start.date() <= to);
+    };
+    let endless = series.rule.as_ref().is_some_and(|rule| rule.count.is_none() && rule.until.is_none());
+    let to = match to {
+        Some(to) => to,
+        None if endless => return true,
+        None => NaiveDate::MAX,
+    };
+    let days = last_day - event.start.date();
+    let from = from.map_or(event.start.date(), |from| from.checked_sub_signed(days).unwrap_or(NaiveDate::MIN));
+    !series.occurrences(event.start, from, to, event.zone()).is_empty()
+}
+
+/// The date range of `options`, widened by `margin` on both sides
+fn date_range(options: &IcsExportOptions, margin: Duration) -> Result<(Option<NaiveDate>, Option<NaiveDate>), String> {
+    let range_limit = |text: &Option<String>, name: &str| -> Result<Option<NaiveDate>, String> {
+        text.as_deref()
+            .map(|text| parse_date(text).ok_or_else(|| format!("Invalid {} date: {}", name, text)))
+            .transpose()
+    };
+    let out_of_range = |text: &Option<String>| format!("Date out of range: {}", text.as_deref().unwrap_or_default());
+    let from = range_limit(&options.from, "from")?
+        .map(|from| from.checked_sub_signed(margin).ok_or_else(|| out_of_range(&options.from)))
+        .transpose()?;
+    let to = range_limit(&options.to, "to")?
+        .map(|to| to.checked_add_signed(margin).ok_or_else(|| out_of_range(&options.to)))
+        .transpose()?;
+    Ok((from, to))
+}
+
+/// Collect the events of the chosen sources that overlap the date range, widened by `margin` on both sides,
+/// in start order
+pub fn collect_events(conn: &Connection, options: &IcsExportOptions, margin: Duration) -> Result<Vec<IcsEvent>, String> {
+    let (from, to) = date_range(options, margin)?;
+    let mut events = all_events(conn, options)?;
+    events.retain(|event| overlaps(event, from, to));
+    Ok(events)
+}
+
+/// Collect every event of the chosen sources, whatever the date range, in start order.
+/// A recurring event and the occurrences it changes are always complete here.
+pub fn all_events(conn: &Connection, options: &IcsExportOptions) -> Result<Vec<IcsEvent>, String> {
+    let time_zone = time_zones::parse_optional(options.time_zone.as_deref())?;
+
+    let lookup = Lookup::load(conn)?;
+    let mut events = Vec::new();
+    if options.includes(EventSource::Visits) {
+        visit_events(conn, &lookup, &mut events)?;
+    }
+    if options.includes(EventSource::CircuitEvents) {
+        circuit_event_events(conn, &lookup, &mut events)?;
+    }
+    if options.includes(EventSource::PssClasses) {
+        pss_events(conn, &lookup, &mut events)?;
+    }
+    if options.includes(EventSource::RoutingEvents) {
+        routing_events(conn, &lookup, &mut events)?;
+    }
+    if options.includes(EventSource::CalendarEvents) {
+        calendar_page_events(&options.events, &mut events)?;
+    }
+    if options.includes(EventSource::Meetings) {
+        meeting_events(conn, &lookup, &mut events)?;
+    }
+
+    // Times of no zone at all are written as floating local times
+    for event in &mut events {
+        event.time_zone = event.time_zone.or(time_zone).or(lookup.user_zone);
+    }
+    events.sort_by_key(|event| event.start.sort_key());
+    Ok(events)
+}
+
+/// An ICS export, with what it sends for `mark_ics_sent`
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IcsCalendar {
+    pub content: String,
+    pub sent: SentEvents,
+}
+
+/// Build the iCalendar file of an export. Its events are remembered as sent with `ics_updates::mark_sent`,
+/// once the file has been written or saved, so later changes can be sent as updates.
+pub fn build_calendar(conn: &Connection, options: &IcsExportOptions) -> Result<IcsCalendar, String> {
+    let (from, to) = date_range(options, Duration::zero())?;
+    // The sequence is given to whole events, so it does not depend on the range they are written for
+    let mut events = all_events(conn, options)?;
+    let stamp = Utc::now();
+    let sent = ics_updates::sequence(conn, &mut events)?;
+    events.retain(|event| overlaps(event, from, to));
+    let sent = sent.written_in(&events);
+    Ok(IcsCalendar { content: write_calendar(&events, None, stamp), sent })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn urls_and_organizers_cannot_add_properties() {
+        let start = EventTime::Date(NaiveDate::from_ymd_opt(2026, 3, 1).unwrap());
+        let mut event = IcsEvent::new(EventSource::CalendarEvents, "a".to_string(), 0, "A".to_string(), start, start);
+        event.url = Some("https://example.org/\r\nATTACH:https://evil.example".to_string());
+        event.organizer = Some(("Jo\nX-EVIL:1".to_string(), "jo@example.org\r\nX-EVIL:2".to_string()));
+
+        let lines = event_lines(&event, Utc::now());
+        assert!(lines.contains(&"URL:https://example.org/ATTACH:https://evil.example".to_string()));
+        assert!(lines.iter().all(|line| !line.starts_with("ATTACH") && !line.starts_with("X-EVIL")));
+    }
+
+    #[test]
+    fn ranges_past_the_last_date_are_refused() {
+        let options = IcsExportOptions { from: Some("2026-01-01".to_string()), ..IcsExportOptions::default() };
+        let margin = Duration::try_days(1_000_000_000).unwrap();
+        assert!(date_range(&options, margin).is_err());
+        assert!(date_range(&options, Duration::days(1)).is_ok());
+    }
+}
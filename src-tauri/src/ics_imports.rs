@@ -0,0 +1,810 @@
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use crate::ics_exports::EventTime;
+use crate::recurrence::{self, Frequency, Rule, Until};
+use crate::time_zones;
+
+/// Title of events that have no SUMMARY
+const UNTITLED: &str = "(No title)";
+
+/// A content line: `NAME;PARAM=value:value`
+struct Property {
+    name: String,
+    params: Vec<(String, String)>,
+    value: String,
+}
+
+impl Property {
+    fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(param, _)| param.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// A BEGIN/END block with its properties and nested blocks
+struct Component {
+    name: String,
+    properties: Vec<Property>,
+    children: Vec<Component>,
+}
+
+impl Component {
+    fn new(name: &str) -> Self {
+        Component {
+            name: name.to_ascii_uppercase(),
+            properties: Vec::new(),
+            children: Vec::new(),
+        }
+    }
+
+    fn property(&self, name: &str) -> Option<&Property> {
+        self.properties.iter().find(|property| property.name == name)
+    }
+
+    fn all<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Property> + 'a {
+        self.properties.iter().filter(move |property| property.name == name)
+    }
+
+    /// Unescaped TEXT value of a property, if it is not blank
+    fn text(&self, name: &str) -> Option<String> {
+        let text = unescape_text(&self.property(name)?.value);
+        Some(text.trim().to_string()).filter(|text| !text.is_empty())
+    }
+}
+
+/// An event as the calendar page shows it, read from a VEVENT
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportedEvent {
+    pub title: String,
+    /// First day, `YYYY-MM-DD`
+    pub date: String,
+    /// Start time, `HH:MM`, in `time_zone` or else on this device; missing for all-day events
+    pub time: Option<String>,
+    /// Last day, when the event ends on a later day than it starts
+    pub end_date: Option<String>,
+    pub end_time: Option<String>,
+    pub all_day: bool,
+    pub location: Option<String>,
+    pub description: Option<String>,
+    pub categories: Vec<String>,
+    pub uid: Option<String>,
+    pub recurrence: Option<Recurrence>,
+    /// Start of the occurrence this event replaces, when it changes one occurrence of a recurring event
+    pub recurrence_id: Option<String>,
+    /// IANA time zone of the times, when DTSTART has a TZID that names one
+    pub time_zone: Option<String>,
+}
+
+/// How an imported event repeats
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Recurrence {
+    /// The RRULE value, e.g. `FREQ=WEEKLY;BYDAY=TU;UNTIL=20260630`
+    pub rule: Option<String>,
+    /// Additional occurrences (RDATE)
+    pub dates: Vec<String>,
+    /// Occurrences that do not take place (EXDATE), or that a separate event replaces
+    pub exdates: Vec<String>,
+}
+
+/// A component, or part of one, that was not imported
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SkippedComponent {
+    pub component: String,
+    pub uid: Option<String>,
+    pub summary: Option<String>,
+    pub reason: String,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct IcsImport {
+    pub events: Vec<ImportedEvent>,
+    pub skipped: Vec<SkippedComponent>,
+}
+
+/// Undo the escaping of a TEXT value (RFC 5545 section 3.3.11)
+pub fn unescape_text(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => unescaped.push('\n'),
+            Some(other) => unescaped.push(other),
+            None => unescaped.push('\\'),
+        }
+    }
+    unescaped
+}
+
+/// Split a list of TEXT values at the commas that are not escaped
+fn split_text_list(value: &str) -> Vec<String> {
+    let mut items = vec![String::new()];
+    let mut escaped = false;
+    for c in value.chars() {
+        match c {
+            ',' if !escaped => items.push(String::new()),
+            c => {
+                escaped = c == '\\' && !escaped;
+                items.last_mut().expect("list has an item").push(c);
+            }
+        }
+    }
+    items
+        .iter()
+        .map(|item| unescape_text(item).trim().to_string())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+/// Join folded lines: a line starting with a space or tab continues the one before
+fn unfold(content: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in content.trim_start_matches('\u{feff}').split('\n') {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(previous)) => previous.push_str(rest),
+            _ if line.trim().is_empty() => {}
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+/// Split a content line into its name, parameters and value. Parameter values may be quoted.
+fn parse_line(line: &str) -> Option<Property> {
+    let mut quoted = false;
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut value_start = None;
+    for (index, c) in line.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            ';' if !quoted => {
+                parts.push(&line[start..index]);
+                start = index + 1;
+            }
+            ':' if !quoted => {
+                parts.push(&line[start..index]);
+                value_start = Some(index + 1);
+                break;
+            }
+            _ => {}
+        }
+    }
+    let value = line[value_start?..].to_string();
+    let (name, params) = parts.split_first()?;
+    let name = name.trim().to_ascii_uppercase();
+    if name.is_empty() {
+        return None;
+    }
+    let params = params
+        .iter()
+        .filter_map(|param| {
+            let (key, value) = param.split_once('=')?;
+            Some((key.trim().to_ascii_uppercase(), value.trim().trim_matches('"').to_string()))
+        })
+        .collect();
+    Some(Property { name, params, value })
+}
+
+/// Build the component tree of a file. Returns the top-level components.
+fn parse_components(content: &str) -> Result<Vec<Component>, String> {
+    let mut stack = vec![Component::new("")];
+    for (number, line) in unfold(content).iter().enumerate() {
+        let property = parse_line(line)
+            .ok_or_else(|| format!("Invalid ICS file: line {} is not a content line: {}", number + 1, line))?;
+        match property.name.as_str() {
+            "BEGIN" => stack.push(Component::new(property.value.trim())),
+            "END" => {
+                let name = property.value.trim().to_ascii_uppercase();
+                if stack.len() < 2 || stack.last().is_none_or(|open| open.name != name) {
+                    return Err(format!("Invalid ICS file: unexpected END:{} on line {}", name, number + 1));
+                }
+                let component = stack.pop().expect("component is open");
+                stack.last_mut().expect("root is open").children.push(component);
+            }
+            _ => stack.last_mut().expect("root is open").properties.push(property),
+        }
+    }
+    if stack.len() > 1 {
+        return Err(format!("Invalid ICS file: {} is not closed", stack[stack.len() - 1].name));
+    }
+    Ok(stack.pop().expect("root is open").children)
+}
+
+/// A UTC offset like `+0100` or `-053000`, in seconds
+fn parse_offset(text: &str) -> Option<i32> {
+    let text = text.trim();
+    let sign = match text.get(..1)? {
+        "+" => 1,
+        "-" => -1,
+        _ => return None,
+    };
+    let digits = &text[1..];
+    if !(digits.len() == 4 || digits.len() == 6) || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let hours: i32 = digits[..2].parse().ok()?;
+    let minutes: i32 = digits[2..4].parse().ok()?;
+    let seconds: i32 = digits.get(4..).filter(|s| !s.is_empty()).map_or(Some(0), |s| s.parse().ok())?;
+    Some(sign * (hours * 3600 + minutes * 60 + seconds))
+}
+
+/// A DURATION value like `PT1H30M`, `P1D` or `-P1W`; none when it is out of range
+fn parse_duration(text: &str) -> Option<Duration> {
+    let text = text.trim();
+    let (sign, text) = match text.strip_prefix('-') {
+        Some(rest) => (-1, rest),
+        None => (1, text.strip_prefix('+').unwrap_or(text)),
+    };
+    let mut rest = text.strip_prefix('P')?;
+    let mut total = Duration::zero();
+    let mut in_time = false;
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix('T') {
+            in_time = true;
+            rest = after;
+            continue;
+        }
+        let digits = rest.find(|c: char| !c.is_ascii_digit())?;
+        let amount: i64 = rest[..digits].parse().ok()?;
+        let part = match (rest[digits..].chars().next()?, in_time) {
+            ('W', false) => Duration::try_weeks(amount),
+            ('D', false) => Duration::try_days(amount),
+            ('H', true) => Duration::try_hours(amount),
+            ('M', true) => Duration::try_minutes(amount),
+            ('S', true) => Duration::try_seconds(amount),
+            _ => return None,
+        };
+        total = total.checked_add(&part?)?;
+        rest = &rest[digits + 1..];
+    }
+    total.checked_mul(sign)
+}
+
+/// A DATE or DATE-TIME value as written, before its time zone is applied
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum RawTime {
+    Date(NaiveDate),
+    Floating(NaiveDateTime),
+    Utc(NaiveDateTime),
+}
+
+fn parse_raw_time(value: &str, is_date: bool) -> Option<RawTime> {
+    let value = value.trim();
+    if is_date || value.len() == 8 {
+        return NaiveDate::parse_from_str(value, "%Y%m%d").ok().map(RawTime::Date);
+    }
+    let (text, utc) = match value.strip_suffix('Z').or_else(|| value.strip_suffix('z')) {
+        Some(text) => (text, true),
+        None => (value, false),
+    };
+    let time = NaiveDateTime::parse_from_str(text, "%Y%m%dT%H%M%S").ok()?;
+    Some(if utc { RawTime::Utc(time) } else { RawTime::Floating(time) })
+}
+
+/// One STANDARD or DAYLIGHT block of a VTIMEZONE
+struct Observance {
+    start: NaiveDateTime,
+    offset_to: i32,
+    offset_from: i32,
+    rule: Option<Rule>,
+    dates: Vec<NaiveDateTime>,
+}
+
+impl Observance {
+    fn parse(component: &Component) -> Option<Observance> {
+        let start = match parse_raw_time(&component.property("DTSTART")?.value, false)? {
+            RawTime::Date(date) => date.and_time(chrono::NaiveTime::MIN),
+            RawTime::Floating(time) | RawTime::Utc(time) => time,
+        };
+        let offset_to = parse_offset(&component.property("TZOFFSETTO")?.value)?;
+        let offset_from = component
+            .property("TZOFFSETFROM")
+            .and_then(|property| parse_offset(&property.value))
+            .unwrap_or(offset_to);
+        let rule = component
+            .property("RRULE")
+            .and_then(|property| Rule::parse(&property.value).ok())
+            .filter(|rule| rule.frequency == Frequency::Yearly);
+        let dates = component
+            .all("RDATE")
+            .flat_map(|property| property.value.split(','))
+            .filter_map(|value| match parse_raw_time(value, false)? {
+                RawTime::Date(date) => Some(date.and_time(start.time())),
+                RawTime::Floating(time) | RawTime::Utc(time) => Some(time),
+            })
+            .collect();
+        Some(Observance { start, offset_to, offset_from, rule, dates })
+    }
+
+    /// When the observance takes effect in a year, following a yearly rule such as "last Sunday of March"
+    fn onsets_in(&self, year: i32) -> Vec<NaiveDateTime> {
+        let Some(rule) = &self.rule else {
+            return Vec::new();
+        };
+        if year < self.start.year() || (year - self.start.year()) % rule.interval as i32 != 0 {
+            return Vec::new();
+        }
+        let months = if rule.by_month.is_empty() { vec![self.start.month()] } else { rule.by_month.clone() };
+        let mut onsets = Vec::new();
+        for month in months {
+            let mut days: Vec<NaiveDate> = Vec::new();
+            for weekday in &rule.by_day {
+                match weekday.ordinal {
+                    Some(ordinal) => days.extend(recurrence::nth_weekday(year, month, weekday.weekday, ordinal)),
+                    None => days.extend((1..=5).filter_map(|n| recurrence::nth_weekday(year, month, weekday.weekday, n))),
+                }
+            }
+            if rule.by_day.is_empty() {
+                let month_days = if rule.by_month_day.is_empty() { vec![self.start.day() as i32] } else { rule.by_month_day.clone() };
+                days.extend(month_days.iter().filter_map(|day| NaiveDate::from_ymd_opt(year, month, day.unsigned_abs())));
+            } else if !rule.by_month_day.is_empty() {
+                days.retain(|date| rule.by_month_day.contains(&(date.day() as i32)));
+            }
+            onsets.extend(days.into_iter().map(|date| date.and_time(self.start.time())));
+        }
+        let until = rule.until.map(|until| match until {
+            Until::Local(time) => time,
+            Until::Utc(time) => time.naive_utc() + Duration::seconds(self.offset_from.into()),
+        });
+        onsets.retain(|onset| *onset >= self.start && until.is_none_or(|until| *onset <= until));
+        onsets
+    }
+
+    /// The latest time this observance took effect at or before `time`
+    fn last_onset(&self, time: NaiveDateTime) -> Option<NaiveDateTime> {
+        let mut onsets = vec![self.start];
+        onsets.extend(self.dates.iter().copied());
+        for year in [time.year() - 1, time.year()] {
+            onsets.extend(self.onsets_in(year));
+        }
+        onsets.into_iter().filter(|onset| *onset <= time).max()
+    }
+}
+
+/// A VTIMEZONE definition
+struct Zone {
+    observances: Vec<Observance>,
+}
+
+impl Zone {
+    /// UTC offset in seconds of a wall-clock time in this zone
+    fn offset(&self, time: NaiveDateTime) -> Option<i32> {
+        let current = self
+            .observances
+            .iter()
+            .filter_map(|observance| Some((observance.last_onset(time)?, observance.offset_to)))
+            .max_by_key(|(onset, _)| *onset);
+        match current {
+            Some((_, offset)) => Some(offset),
+            None => self.observances.iter().min_by_key(|observance| observance.start).map(|first| first.offset_from),
+        }
+    }
+}
+
+/// Reads the times of one file: its time zones, and what could not be resolved
+struct Times {
+    zones: HashMap<String, Zone>,
+}
+
+impl Times {
+    fn new(calendar: &Component) -> Self {
+        let zones = calendar
+            .children
+            .iter()
+            .filter(|child| child.name == "VTIMEZONE")
+            .filter_map(|zone| {
+                let id = zone.property("TZID")?.value.trim().to_string();
+                let observances = zone.children.iter().filter_map(Observance::parse).collect::<Vec<_>>();
+                Some((id, Zone { observances })).filter(|(_, zone)| !zone.observances.is_empty())
+            })
+            .collect();
+        Times { zones }
+    }
+
+    /// A date, or a date-time as wall-clock time in `zone`, the time zone of the event.
+    /// A TZID that is an IANA name is read with the IANA rules, any other with the file's VTIMEZONE.
+    /// Times in a zone that is neither are kept as written; `unknown_zone` gets its name.
+    fn resolve(&self, value: &str, property: &Property, zone: time_zones::Zone, unknown_zone: &mut Option<String>) -> Option<EventTime> {
+        let is_date = property.param("VALUE").is_some_and(|kind| kind.eq_ignore_ascii_case("DATE"));
+        let utc = match parse_raw_time(value, is_date)? {
+            RawTime::Date(date) => return Some(EventTime::Date(date)),
+            RawTime::Utc(time) => Utc.from_utc_datetime(&time),
+            RawTime::Floating(time) => {
+                let Some(id) = property.param("TZID").map(str::trim) else {
+                    return Some(EventTime::Local(time));
+                };
+                match time_zones::parse(id) {
+                    Ok(named) if zone == time_zones::Zone::Named(named) => return Some(EventTime::Local(time)),
+                    Ok(named) => time_zones::Zone::Named(named).to_utc(time),
+                    Err(_) => match self.zones.get(id).and_then(|defined| defined.offset(time)) {
+                        Some(offset) => Utc.from_utc_datetime(&(time - Duration::seconds(offset.into()))),
+                        None => {
+                            *unknown_zone = Some(id.to_string());
+                            return Some(EventTime::Local(time));
+                        }
+                    },
+                }
+            }
+        };
+        Some(EventTime::Local(zone.wall_clock(utc)))
+    }
+
+    fn resolve_property(&self, property: &Property, zone: time_zones::Zone, unknown_zone: &mut Option<String>) -> Option<EventTime> {
+        self.resolve(&property.value, property, zone, unknown_zone)
+    }
+
+    /// All values of a list property such as EXDATE or RDATE
+    fn resolve_list<'a>(
+        &self,
+        properties: impl Iterator<Item = &'a Property>,
+        zone: time_zones::Zone,
+        unknown_zone: &mut Option<String>,
+    ) -> Result<Vec<EventTime>, String> {
+        let mut times = Vec::new();
+        for property in properties {
+            for value in property.value.split(',').filter(|value| !value.trim().is_empty()) {
+                let time = self
+                    .resolve(value, property, zone, unknown_zone)
+                    .ok_or_else(|| format!("Invalid {} value: {}", property.name, value))?;
+                times.push(time);
+            }
+        }
+        Ok(times)
+    }
+}
+
+fn date_text(date: NaiveDate) -> String {
+    date.format("%Y-%m-%d").to_string()
+}
+
+fn skip(component: &Component, reason: impl Into<String>) -> SkippedComponent {
+    SkippedComponent {
+        component: component.name.clone(),
+        uid: component.text("UID"),
+        summary: component.text("SUMMARY"),
+        reason: reason.into(),
+    }
+}
+
+/// Map a VEVENT to an event. Problems that only lose part of the event are added to `skipped`.
+fn read_event(event: &Component, times: &Times, skipped: &mut Vec<SkippedComponent>) -> Result<ImportedEvent, String> {
+    let mut unknown_zone = None;
+    let start_property = event.property("DTSTART").ok_or("Event has no start (DTSTART)")?;
+    // Times in an IANA time zone stay in it, so recurring events keep their time of day there
+    let time_zone = start_property.param("TZID").and_then(|id| time_zones::parse(id).ok());
+    let zone = time_zone.map_or(time_zones::Zone::Device, time_zones::Zone::Named);
+    let start = times
+        .resolve_property(start_property, zone, &mut unknown_zone)
+        .ok_or_else(|| format!("Invalid DTSTART value: {}", start_property.value))?;
+
+    let end = match (event.property("DTEND"), event.property("DURATION")) {
+        (Some(end), _) => Some(
+            times
+                .resolve_property(end, zone, &mut unknown_zone)
+                .ok_or_else(|| format!("Invalid DTEND value: {}", end.value))?,
+        ),
+        (None, Some(duration)) => {
+            let invalid = || format!("Invalid DURATION value: {}", duration.value);
+            let length = parse_duration(&duration.value).ok_or_else(invalid)?;
+            Some(match start {
+                EventTime::Date(date) => EventTime::Date(
+                    Duration::try_days(length.num_days().max(1))
+                        .and_then(|days| date.checked_add_signed(days))
+                        .ok_or_else(invalid)?,
+                ),
+                EventTime::Local(time) => EventTime::Local(time.checked_add_signed(length).ok_or_else(invalid)?),
+            })
+        }
+        (None, None) => None,
+    };
+
+    let (date, time, end_date, end_time, all_day) = match (start, end) {
+        (EventTime::Date(first), end) => {
+            // DTEND of an all-day event is the day after its last day
+            let last = match end {
+                Some(EventTime::Date(end)) => end.pred_opt().unwrap_or(end),
+                Some(EventTime::Local(end)) => end.date(),
+                None => first,
+            };
+            (first, None, Some(last).filter(|last| *last > first), None, true)
+        }
+        (EventTime::Local(start), end) => {
+            let end = match end {
+                Some(EventTime::Local(end)) => Some(end),
+                Some(EventTime::Date(end)) => end.and_hms_opt(0, 0, 0),
+                None => None,
+            }
+            .filter(|end| *end >= start);
+            (
+                start.date(),
+                Some(start.format("%H:%M").to_string()),
+                end.map(|end| end.date()).filter(|last| *last > start.date()),
+                end.map(|end| end.format("%H:%M").to_string()),
+                false,
+            )
+        }
+    };
+
+    let recurrence_id = match event.property("RECURRENCE-ID") {
+        Some(property) => Some(recurrence::occurrence_id(
+            times
+                .resolve_property(property, zone, &mut unknown_zone)
+                .ok_or_else(|| format!("Invalid RECURRENCE-ID value: {}", property.value))?,
+        )),
+        None => None,
+    };
+
+    let mut rule = None;
+    if let Some(property) = event.property("RRULE") {
+        match Rule::parse(&property.value) {
+            Ok(_) => rule = Some(property.value.trim().to_ascii_uppercase()),
+            Err(e) => skipped.push(skip(event, format!("{}; only the first occurrence was imported", e))),
+        }
+    }
+    let dates = times.resolve_list(event.all("RDATE"), zone, &mut unknown_zone)?;
+    let exdates = times.resolve_list(event.all("EXDATE"), zone, &mut unknown_zone)?;
+    let recurrence = (rule.is_some() || !dates.is_empty()).then(|| Recurrence {
+        rule,
+        dates: dates.into_iter().map(recurrence::occurrence_id).collect(),
+        exdates: exdates.into_iter().map(recurrence::occurrence_id).collect(),
+    });
+
+    if let Some(zone) = unknown_zone {
+        skipped.push(skip(
+            event,
+            format!("Time zone {} is not defined in the file; its times were imported as written", zone),
+        ));
+    }
+
+    Ok(ImportedEvent {
+        title: event.text("SUMMARY").unwrap_or_else(|| UNTITLED.to_string()),
+        date: date_text(date),
+        time,
+        end_date: end_date.map(date_text),
+        end_time,
+        all_day,
+        location: event.text("LOCATION"),
+        description: event.text("DESCRIPTION"),
+        categories: event.all("CATEGORIES").flat_map(|property| split_text_list(&property.value)).collect(),
+        uid: event.text("UID"),
+        recurrence,
+        recurrence_id,
+        time_zone: time_zone.filter(|_| !all_day).map(|zone| zone.name().to_string()),
+    })
+}
+
+/// Read the events of an iCalendar file.
+/// Components other than events, cancelled events and events that cannot be read are listed in `skipped`.
+pub fn parse_calendar(content: &str) -> Result<IcsImport, String> {
+    let calendars: Vec<Component> = parse_components(content)?
+        .into_iter()
+        .filter(|component| component.name == "VCALENDAR")
+        .collect();
+    if calendars.is_empty() {
+        return Err("Not an iCalendar file: BEGIN:VCALENDAR is missing".to_string());
+    }
+
+    let mut events = Vec::new();
+    let mut skipped = Vec::new();
+    // Occurrences of recurring events that were cancelled or replaced, by UID
+    let mut replaced: HashMap<String, Vec<String>> = HashMap::new();
+    for calendar in &calendars {
+        let times = Times::new(calendar);
+        // A calendar sent to cancel events lists the events it cancels
+        let cancels = calendar.property("METHOD").is_some_and(|method| method.value.trim().eq_ignore_ascii_case("CANCEL"));
+        for component in &calendar.children {
+            match component.name.as_str() {
+                "VEVENT" => {}
+                "VTIMEZONE" => continue,
+                "VTODO" | "VJOURNAL" | "VFREEBUSY" => {
+                    skipped.push(skip(component, "Only events are imported"));
+                    continue;
+                }
+                _ => {
+                    skipped.push(skip(component, "Unknown component"));
+                    continue;
+                }
+            }
+            let cancelled = cancels
+                || component
+                    .property("STATUS")
+                    .is_some_and(|status| status.value.trim().eq_ignore_ascii_case("CANCELLED"));
+            match read_event(component, &times, &mut skipped) {
+                Ok(event) => {
+                    if let (Some(uid), Some(occurrence)) = (&event.uid, &event.recurrence_id) {
+                        replaced.entry(uid.clone()).or_default().push(occurrence.clone());
+                    }
+                    if cancelled {
+                        skipped.push(skip(component, "Event is cancelled"));
+                    } else {
+                        events.push(event);
+                    }
+                }
+                Err(reason) => skipped.push(skip(component, reason)),
+            }
+        }
+    }
+
+    // The main event of a recurring series skips the occurrences that were changed or cancelled
+    for event in events.iter_mut().filter(|event| event.recurrence_id.is_none()) {
+        let (Some(uid), Some(recurrence)) = (&event.uid, &mut event.recurrence) else {
+            continue;
+        };
+        for occurrence in replaced.get(uid).into_iter().flatten() {
+            if !recurrence.exdates.contains(occurrence) {
+                recurrence.exdates.push(occurrence.clone());
+            }
+        }
+    }
+    events.sort_by(|a, b| (&a.date, &a.time).cmp(&(&b.date, &b.time)));
+    Ok(IcsImport { events, skipped })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{db, ics_exports};
+    use serde_json::json;
+
+    fn calendar(body: &str) -> String {
+        format!("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n{}END:VCALENDAR\r\n", body)
+    }
+
+    fn time(text: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    const WINDOWS_ZONE: &str = "BEGIN:VTIMEZONE\r\nTZID:W. Europe Standard Time\r\n\
+        BEGIN:STANDARD\r\nDTSTART:16010101T030000\r\nTZOFFSETFROM:+0200\r\nTZOFFSETTO:+0100\r\n\
+        RRULE:FREQ=YEARLY;BYDAY=-1SU;BYMONTH=10\r\nEND:STANDARD\r\n\
+        BEGIN:DAYLIGHT\r\nDTSTART:16010101T020000\r\nTZOFFSETFROM:+0100\r\nTZOFFSETTO:+0200\r\n\
+        RRULE:FREQ=YEARLY;BYDAY=-1SU;BYMONTH=3\r\nEND:DAYLIGHT\r\nEND:VTIMEZONE\r\n";
+
+    #[test]
+    fn folded_lines_are_joined() {
+        let content = calendar(
+            "BEGIN:VEVENT\r\nUID:ca-1\r\nSUMMARY:Circuit Assembly\\, Hall\r\n  2\r\nDTSTART;VALUE=DATE:20260314\r\n\
+             DTEND;VALUE=DATE:20260316\r\nDESCRIPTION:Line 1\\nLine\r\n\t 2\\; more\r\nCATEGORIES:Assembly,A\\,B\r\nEND:VEVENT\r\n",
+        );
+        let event = &parse_calendar(&content).unwrap().events[0];
+        assert_eq!(event.title, "Circuit Assembly, Hall 2");
+        assert_eq!(event.description.as_deref(), Some("Line 1\nLine 2; more"));
+        assert_eq!(event.categories, ["Assembly", "A,B"]);
+        assert!(event.all_day);
+        assert_eq!(event.end_date.as_deref(), Some("2026-03-15"));
+    }
+
+    #[test]
+    fn vtimezone_offsets_follow_their_rules() {
+        let components = parse_components(&calendar(WINDOWS_ZONE)).unwrap();
+        let times = Times::new(&components[0]);
+        let zone = &times.zones["W. Europe Standard Time"];
+        assert_eq!(zone.offset(time("2026-01-05 19:00")), Some(3600));
+        assert_eq!(zone.offset(time("2026-03-29 01:59")), Some(3600));
+        assert_eq!(zone.offset(time("2026-03-29 02:00")), Some(7200));
+        assert_eq!(zone.offset(time("2026-07-07 19:00")), Some(7200));
+        assert_eq!(zone.offset(time("2026-10-25 03:00")), Some(3600));
+        assert_eq!(parse_offset("-0530"), Some(-19800));
+        assert_eq!(parse_offset("+053045"), Some(19845));
+        assert_eq!(parse_offset("0100"), None);
+    }
+
+    #[test]
+    fn times_are_read_in_the_zone_of_the_start() {
+        let content = calendar(&format!(
+            "{}BEGIN:VEVENT\r\nUID:m-1\r\nSUMMARY:Meeting\r\nDTSTART;TZID=Europe/Berlin:20260707T190000\r\nDURATION:PT1H45M\r\n\
+             RRULE:FREQ=WEEKLY;BYDAY=TU;COUNT=10\r\nEXDATE:20260714T170000Z\r\n\
+             EXDATE;TZID=\"W. Europe Standard Time\":20260728T190000\r\nRDATE;TZID=America/New_York:20260730T130000\r\nEND:VEVENT\r\n\
+             BEGIN:VEVENT\r\nUID:m-1\r\nRECURRENCE-ID;TZID=Europe/Berlin:20260721T190000\r\nSUMMARY:Meeting (moved)\r\n\
+             DTSTART;TZID=Europe/Berlin:20260722T190000\r\nEND:VEVENT\r\n\
+             BEGIN:VEVENT\r\nUID:x-1\r\nSUMMARY:Elsewhere\r\nDTSTART;TZID=Nowhere/Zone:20260105T190000\r\nEND:VEVENT\r\n",
+            WINDOWS_ZONE
+        ));
+        let import = parse_calendar(&content).unwrap();
+        let series = import.events.iter().find(|event| event.title == "Meeting").unwrap();
+        assert_eq!((series.date.as_str(), series.time.as_deref()), ("2026-07-07", Some("19:00")));
+        assert_eq!(series.end_time.as_deref(), Some("20:45"));
+        assert_eq!(series.time_zone.as_deref(), Some("Europe/Berlin"));
+        let recurrence = series.recurrence.as_ref().unwrap();
+        assert_eq!(recurrence.rule.as_deref(), Some("FREQ=WEEKLY;BYDAY=TU;COUNT=10"));
+        assert_eq!(recurrence.dates, ["2026-07-30T19:00"]);
+        assert_eq!(recurrence.exdates, ["2026-07-14T19:00", "2026-07-28T19:00", "2026-07-21T19:00"]);
+
+        let moved = import.events.iter().find(|event| event.recurrence_id.is_some()).unwrap();
+        assert_eq!(moved.recurrence_id.as_deref(), Some("2026-07-21T19:00"));
+        assert_eq!(moved.date, "2026-07-22");
+
+        let elsewhere = import.events.iter().find(|event| event.title == "Elsewhere").unwrap();
+        assert_eq!((elsewhere.date.as_str(), elsewhere.time.as_deref()), ("2026-01-05", Some("19:00")));
+        assert!(import.skipped.iter().any(|skipped| skipped.reason.contains("Nowhere/Zone")));
+    }
+
+    #[test]
+    fn events_that_cannot_be_read_are_skipped() {
+        let content = calendar(
+            "BEGIN:VEVENT\r\nUID:h-1\r\nSUMMARY:Hourly\r\nDTSTART:20260105T100000Z\r\nRRULE:FREQ=HOURLY\r\nEND:VEVENT\r\n\
+             BEGIN:VEVENT\r\nUID:c-1\r\nSTATUS:CANCELLED\r\nDTSTART:20260101T100000Z\r\nEND:VEVENT\r\n\
+             BEGIN:VEVENT\r\nUID:n-1\r\nSUMMARY:No start\r\nEND:VEVENT\r\n\
+             BEGIN:VEVENT\r\nUID:d-1\r\nDTSTART:20250101T100000Z\r\nDURATION:P9999999999999D\r\nEND:VEVENT\r\n\
+             BEGIN:VTODO\r\nSUMMARY:Todo\r\nEND:VTODO\r\n",
+        );
+        let import = parse_calendar(&content).unwrap();
+        assert_eq!(import.events.len(), 1);
+        assert!(import.events[0].recurrence.is_none());
+        let reasons: Vec<&str> = import.skipped.iter().map(|skipped| skipped.reason.as_str()).collect();
+        assert_eq!(reasons.len(), 5, "{:?}", reasons);
+        assert!(reasons[0].contains("HOURLY"));
+        assert!(reasons.contains(&"Event is cancelled"));
+        assert!(reasons.contains(&"Event has no start (DTSTART)"));
+        assert!(reasons.iter().any(|reason| reason.starts_with("Invalid DURATION")));
+        assert!(reasons.contains(&"Only events are imported"));
+        assert!(parse_calendar("BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nEND:VCALENDAR\r\n").is_err());
+    }
+
+    #[test]
+    fn exported_events_import_unchanged() {
+        let conn = db::test_connection();
+        let options: ics_exports::IcsExportOptions = serde_json::from_value(json!({
+            "sources": ["calendarEvents"],
+            "events": [
+                {
+                    "title": "Service group, Hall 2; upstairs",
+                    "date": "2026-03-03",
+                    "time": "19:00",
+                    "endTime": "20:30",
+                    "location": "Kingdom Hall\nNorth",
+                    "uid": "group-1",
+                    "timeZone": "Europe/Berlin",
+                    "recurrence": {
+                        "rule": "FREQ=MONTHLY;BYDAY=TU;BYSETPOS=1;UNTIL=20261231T225959Z",
+                        "dates": ["2026-03-19T19:00"],
+                        "exdates": ["2026-05-05T19:00"]
+                    }
+                },
+                {
+                    "title": "Service group (moved)",
+                    "date": "2026-04-08",
+                    "time": "19:00",
+                    "uid": "group-1",
+                    "recurrenceId": "2026-04-07T19:00",
+                    "timeZone": "Europe/Berlin"
+                },
+                { "title": "Assembly", "date": "2026-06-13", "endDate": "2026-06-14", "uid": "assembly-1" }
+            ]
+        }))
+        .unwrap();
+        let content = ics_exports::build_calendar(&conn, &options).unwrap().content;
+        let import = parse_calendar(&content).unwrap();
+        assert!(import.skipped.is_empty(), "{:?}", import.skipped);
+
+        let series = import.events.iter().find(|event| event.title.starts_with("Service group,")).unwrap();
+        assert_eq!(series.title, "Service group, Hall 2; upstairs");
+        assert_eq!((series.date.as_str(), series.time.as_deref(), series.end_time.as_deref()), ("2026-03-03", Some("19:00"), Some("20:30")));
+        assert_eq!(series.location.as_deref(), Some("Kingdom Hall\nNorth"));
+        assert_eq!(series.time_zone.as_deref(), Some("Europe/Berlin"));
+        let recurrence = series.recurrence.as_ref().unwrap();
+        assert_eq!(recurrence.rule.as_deref(), Some("FREQ=MONTHLY;UNTIL=20261231T225959Z;BYDAY=TU;BYSETPOS=1"));
+        assert_eq!(recurrence.dates, ["2026-03-19T19:00"]);
+        assert!(recurrence.exdates.contains(&"2026-05-05T19:00".to_string()));
+        assert!(recurrence.exdates.contains(&"2026-04-07T19:00".to_string()));
+
+        let moved = import.events.iter().find(|event| event.recurrence_id.is_some()).unwrap();
+        assert_eq!((moved.uid.as_deref(), moved.date.as_str()), (Some("group-1"), "2026-04-08"));
+
+        let assembly = import.events.iter().find(|event| event.title == "Assembly").unwrap();
+        assert!(assembly.all_day);
+        assert_eq!((assembly.date.as_str(), assembly.end_date.as_deref()), ("2026-06-13", Some("2026-06-14")));
+    }
+}
@@ -0,0 +1,386 @@
+use chrono::{Duration, NaiveDate, NaiveDateTime, Utc};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use crate::db;
+use crate::ics_exports::{self, EventSource, EventTime, IcsEvent, IcsExportOptions};
+use crate::models::{self, User};
+use crate::recurrence::Until;
+use crate::time_zones;
+
+/// An event as an export sends it, or as the last export sent it
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SentEvent {
+    uid: String,
+    source: EventSource,
+    model_name: Option<String>,
+    record_id: Option<String>,
+    sequence: i64,
+    fingerprint: String,
+    summary: String,
+    start_time: String,
+    end_time: String,
+    time_zone: Option<String>,
+    cancelled: bool,
+}
+
+/// What an export sends, remembered with `mark_sent` once its file has been written or saved
+#[derive(Serialize, Deserialize, Default)]
+pub struct SentEvents(Vec<SentEvent>);
+
+impl SentEvents {
+    /// Only the events written to a file, out of those given a sequence
+    pub fn written_in(self, events: &[IcsEvent]) -> SentEvents {
+        let written: HashSet<&String> = events.iter().map(|event| &event.uid).collect();
+        SentEvents(self.0.into_iter().filter(|event| written.contains(&event.uid)).collect())
+    }
+}
+
+/// Update and cancellation files for the calendars events were exported to
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IcsUpdates {
+    /// METHOD:REQUEST calendar of the new and changed events; none when nothing changed
+    pub updates: Option<String>,
+    /// METHOD:CANCEL calendar of the events that were sent before and no longer exist
+    pub cancellations: Option<String>,
+    /// Summaries of the updated events
+    pub updated: Vec<String>,
+    /// Summaries of the cancelled events
+    pub cancelled: Vec<String>,
+    /// What the files send, for `mark_ics_sent`
+    pub sent: SentEvents,
+}
+
+/// Events of the calendar page have no record to compare against later, so they are not tracked
+fn tracked(source: EventSource) -> bool {
+    source != EventSource::CalendarEvents
+}
+
+fn source_name(source: EventSource) -> String {
+    serde_json::to_value(source)
+        .ok()
+        .and_then(|value| value.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+fn time_text(time: EventTime) -> String {
+    match time {
+        EventTime::Date(date) => date.format("%Y-%m-%d").to_string(),
+        EventTime::Local(time) => time.format("%Y-%m-%dT%H:%M:%S").to_string(),
+    }
+}
+
+fn parse_time_text(text: &str) -> Option<EventTime> {
+    NaiveDateTime::parse_from_str(text, "%Y-%m-%dT%H:%M:%S")
+        .map(EventTime::Local)
+        .or_else(|_| NaiveDate::parse_from_str(text, "%Y-%m-%d").map(EventTime::Date))
+        .ok()
+}
+
+fn load_sent(conn: &Connection) -> Result<HashMap<String, SentEvent>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT uid, source, model_name, record_id, sequence, fingerprint, summary, start_time, end_time, time_zone,
+                    cancelled_at
+             FROM calendar_events",
+        )
+        .map_err(|e| format!("Failed to read exported events: {}", e))?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(1)?,
+                SentEvent {
+                    uid: row.get(0)?,
+                    source: EventSource::CalendarEvents,
+                    model_name: row.get(2)?,
+                    record_id: row.get(3)?,
+                    sequence: row.get(4)?,
+                    fingerprint: row.get(5)?,
+                    summary: row.get(6)?,
+                    start_time: row.get(7)?,
+                    end_time: row.get(8)?,
+                    time_zone: row.get(9)?,
+                    cancelled: row.get::<_, Option<String>>(10)?.is_some(),
+                },
+            ))
+        })
+        .map_err(|e| format!("Failed to read exported events: {}", e))?;
+
+    let mut sent = HashMap::new();
+    for row in rows {
+        let (source, mut event) = row.map_err(|e| format!("Failed to read exported events: {}", e))?;
+        // Rows of sources this build does not know are left alone
+        let Ok(source) = serde_json::from_value(Value::String(source)) else { continue };
+        event.source = source;
+        sent.insert(event.uid.clone(), event);
+    }
+    Ok(sent)
+}
+
+/// What a calendar shows of an event, from its stored data: wall-clock times with the name of their time zone,
+/// so the fingerprint does not change with the time zone of the device that exports it
+fn fingerprint(components: &[&IcsEvent]) -> String {
+    let times = |times: &[EventTime]| times.iter().map(|time| time_text(*time)).collect::<Vec<_>>().join(",");
+    let mut hasher = Sha256::new();
+    for event in components {
+        let mut parts = vec![
+            event.summary.clone(),
+            event.description.clone().unwrap_or_default(),
+            event.location.clone().unwrap_or_default(),
+            event.geo.map(|(latitude, longitude)| format!("{};{}", latitude, longitude)).unwrap_or_default(),
+            event.url.clone().unwrap_or_default(),
+            event.categories.join(","),
+            time_text(event.start),
+            time_text(event.end),
+            event.time_zone.map(|zone| zone.name().to_string()).unwrap_or_default(),
+            event.recurrence_id.map(time_text).unwrap_or_default(),
+        ];
+        if let Some(series) = &event.series {
+            parts.push(
+                series
+                    .rule
+                    .as_ref()
+                    .map(|rule| {
+                        rule.to_ics(|until| match until {
+                            Until::Local(time) => time.format("%Y-%m-%dT%H:%M:%S").to_string(),
+                            Until::Utc(time) => time.format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+                        })
+                    })
+                    .unwrap_or_default(),
+            );
+            parts.push(times(&series.dates));
+            parts.push(times(&series.exdates));
+        }
+        for part in parts {
+            hasher.update(part.as_bytes());
+            hasher.update(b"\0");
+        }
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+/// Give the events of an export their SEQUENCE: the one sent last, or the next one for events sent before
+/// with other content or cancelled since. Returns what the export sends of each event.
+fn assign(events: &mut [IcsEvent], sent: &HashMap<String, SentEvent>) -> Vec<SentEvent> {
+    // A recurring event and the occurrences it changes share a UID and are sent together
+    let mut groups: Vec<(String, Vec<usize>)> = Vec::new();
+    for (index, event) in events.iter().enumerate() {
+        if !tracked(event.source) {
+            continue;
+        }
+        match groups.iter_mut().find(|(uid, _)| *uid == event.uid) {
+            Some((_, members)) => members.push(index),
+            None => groups.push((event.uid.clone(), vec![index])),
+        }
+    }
+
+    let mut sending = Vec::new();
+    for (uid, members) in groups {
+        let components: Vec<&IcsEvent> = members.iter().map(|&index| &events[index]).collect();
+        let fingerprint = fingerprint(&components);
+        let stored = components.iter().map(|event| event.sequence).max().unwrap_or_default();
+        let sequence = match sent.get(&uid) {
+            None => stored,
+            Some(previous) if previous.fingerprint == fingerprint && !previous.cancelled => previous.sequence.max(stored),
+            Some(previous) => (previous.sequence + 1).max(stored),
+        };
+        let main = components.iter().find(|event| event.recurrence_id.is_none()).unwrap_or(&components[0]);
+        sending.push(SentEvent {
+            uid,
+            source: main.source,
+            model_name: main.record.as_ref().map(|(model_name, _)| model_name.to_string()),
+            record_id: main.record.as_ref().map(|(_, record_id)| record_id.clone()),
+            sequence,
+            fingerprint,
+            summary: main.summary.clone(),
+            start_time: time_text(main.start),
+            end_time: time_text(main.end),
+            time_zone: main.time_zone.map(|zone| zone.name().to_string()),
+            cancelled: false,
+        });
+        for &index in &members {
+            events[index].sequence = sequence;
+        }
+    }
+    sending
+}
+
+/// Whether an event is new or has changed since it was last sent
+fn changed(event: &SentEvent, sent: &HashMap<String, SentEvent>) -> bool {
+    sent.get(&event.uid)
+        .is_none_or(|previous| previous.fingerprint != event.fingerprint || previous.cancelled)
+}
+
+/// Give the events of an export their SEQUENCE. Nothing is remembered until `mark_sent`.
+pub fn sequence(conn: &Connection, events: &mut [IcsEvent]) -> Result<SentEvents, String> {
+    let sent = load_sent(conn)?;
+    Ok(SentEvents(assign(events, &sent)))
+}
+
+/// Remember the events of an export as sent, once its file has been written or saved
+pub fn mark_sent(conn: &Connection, sent: &SentEvents) -> Result<(), String> {
+    let tx = conn
+        .unchecked_transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    let now = db::now_timestamp();
+    for event in &sent.0 {
+        tx.execute(
+            "INSERT INTO calendar_events
+                 (uid, source, model_name, record_id, sequence, fingerprint, summary, start_time, end_time, time_zone,
+                  exported_at, cancelled_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)
+             ON CONFLICT (uid) DO UPDATE SET
+                 source = excluded.source, model_name = excluded.model_name, record_id = excluded.record_id,
+                 sequence = MAX(sequence, excluded.sequence), fingerprint = excluded.fingerprint,
+                 summary = excluded.summary, start_time = excluded.start_time, end_time = excluded.end_time,
+                 time_zone = excluded.time_zone, exported_at = excluded.exported_at, cancelled_at = excluded.cancelled_at",
+            params![
+                event.uid,
+                source_name(event.source),
+                event.model_name,
+                event.record_id,
+                event.sequence,
+                event.fingerprint,
+                event.summary,
+                event.start_time,
+                event.end_time,
+                event.time_zone,
+                now,
+                event.cancelled.then_some(&now),
+            ],
+        )
+        .map_err(|e| format!("Failed to record exported event: {}", e))?;
+    }
+    tx.commit().map_err(|e| format!("Failed to commit exported events: {}", e))
+}
+
+/// The user, who sends the updates, when an email address is stored for them
+fn organizer(conn: &Connection) -> Result<Option<(String, String)>, String> {
+    Ok(models::read_all::<User>(conn)?.into_iter().find_map(|user| {
+        let email = [user.email_main, user.email_jw]
+            .into_iter()
+            .flatten()
+            .map(|email| email.trim().to_string())
+            .find(|email| !email.is_empty())?;
+        let name = format!("{} {}", user.name_first.trim(), user.name_last.trim()).trim().to_string();
+        Some((name, email))
+    }))
+}
+
+/// Build the updates of events sent before. The range of `options` picks the new events to include;
+/// changes and cancellations of events sent before are included wherever they fall.
+/// Nothing is remembered until `mark_sent`.
+pub fn build_updates(conn: &Connection, options: &IcsExportOptions) -> Result<IcsUpdates, String> {
+    let stamp = Utc::now();
+    let in_range: HashSet<String> = ics_exports::collect_events(conn, options, Duration::zero())?
+        .into_iter()
+        .map(|event| event.uid)
+        .collect();
+    // Whole events, as `build_calendar` gives them their sequence
+    let mut events = ics_exports::all_events(conn, options)?;
+    let existing: HashSet<String> = events.iter().map(|event| event.uid.clone()).collect();
+
+    let sent = load_sent(conn)?;
+    events.retain(|event| in_range.contains(&event.uid) || sent.contains_key(&event.uid));
+    let mut sending: Vec<SentEvent> = assign(&mut events, &sent)
+        .into_iter()
+        .filter(|event| changed(event, &sent))
+        .collect();
+    let updated: HashSet<&String> = sending.iter().map(|event| &event.uid).collect();
+    events.retain(|event| updated.contains(&event.uid));
+
+    // Events sent before that are gone: their record was deleted, or no longer makes an event
+    let mut gone: Vec<&SentEvent> = sent
+        .values()
+        .filter(|event| !event.cancelled && options.includes(event.source) && !existing.contains(&event.uid))
+        .collect();
+    gone.sort_by(|a, b| a.start_time.cmp(&b.start_time));
+    let mut cancellations = Vec::new();
+    for event in gone {
+        let (Some(start), Some(end)) = (parse_time_text(&event.start_time), parse_time_text(&event.end_time)) else { continue };
+        let mut cancellation = IcsEvent::new(event.source, event.uid.clone(), event.sequence + 1, event.summary.clone(), start, end);
+        cancellation.time_zone = time_zones::parse_optional(event.time_zone.as_deref()).ok().flatten();
+        cancellation.cancelled = true;
+        cancellations.push(cancellation);
+        sending.push(SentEvent { sequence: event.sequence + 1, cancelled: true, ..event.clone() });
+    }
+
+    let organizer = organizer(conn)?;
+    for event in events.iter_mut().chain(cancellations.iter_mut()) {
+        event.organizer = organizer.clone();
+    }
+
+    Ok(IcsUpdates {
+        updates: (!events.is_empty()).then(|| ics_exports::write_calendar(&events, Some("REQUEST"), stamp)),
+        cancellations: (!cancellations.is_empty()).then(|| ics_exports::write_calendar(&cancellations, Some("CANCEL"), stamp)),
+        updated: events.iter().filter(|event| event.recurrence_id.is_none()).map(|event| event.summary.clone()).collect(),
+        cancelled: cancellations.iter().map(|event| event.summary.clone()).collect(),
+        sent: SentEvents(sending),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn options(value: Value) -> IcsExportOptions {
+        serde_json::from_value(value).expect("valid options")
+    }
+
+    #[test]
+    fn unchanged_events_are_not_sent_again() {
+        let conn = db::test_connection();
+        let congregation = db::insert_record(
+            &conn,
+            "CongregationObject",
+            json!({ "name": "North", "midweekMeetingDay": "Thursday", "midweekMeetingTime": "19:00" }),
+        )
+        .unwrap();
+        // A visit outside the range moves one of the meetings
+        db::insert_record(
+            &conn,
+            "VisitObject",
+            json!({ "dateStart": "2099-03-10", "congregation": congregation["id"], "tuesdayMeetingTime": "19:30" }),
+        )
+        .unwrap();
+        db::insert_record(
+            &conn,
+            "RoutingObject",
+            json!({ "dateStart": "2099-01-10", "eventType": "Special Event", "specialEventStartTime": "10:00" }),
+        )
+        .unwrap();
+
+        let ranged = options(json!({
+            "from": "2099-01-01", "to": "2099-01-31", "sources": ["meetings", "routingEvents"], "timeZone": "Europe/Berlin"
+        }));
+        let calendar = ics_exports::build_calendar(&conn, &ranged).unwrap();
+        assert!(!calendar.content.contains("RECURRENCE-ID"));
+        mark_sent(&conn, &calendar.sent).unwrap();
+
+        for options in [ranged, options(json!({ "sources": ["meetings", "routingEvents"], "timeZone": "Europe/Berlin" }))] {
+            let updates = build_updates(&conn, &options).unwrap();
+            assert!(updates.updates.is_none(), "{:?}", updates.updated);
+            assert!(updates.cancellations.is_none());
+        }
+    }
+
+    #[test]
+    fn building_remembers_nothing() {
+        let conn = db::test_connection();
+        db::insert_record(
+            &conn,
+            "RoutingObject",
+            json!({ "dateStart": "2099-01-10", "eventType": "Special Event", "specialEventStartTime": "10:00" }),
+        )
+        .unwrap();
+        let options = options(json!({ "sources": ["routingEvents"] }));
+        ics_exports::build_calendar(&conn, &options).unwrap();
+        build_updates(&conn, &options).unwrap();
+        assert!(load_sent(&conn).unwrap().is_empty());
+    }
+}
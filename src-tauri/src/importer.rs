@@ -0,0 +1,476 @@
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::fs;
+use tauri::{AppHandle, State};
+use crate::commands::DbConnection;
+use crate::history::FieldChange;
+use crate::schema::{self, FieldError};
+use crate::{db, model_backups, models, relations, revisions, sandbox};
+
+/// Fields the backend owns; they are never taken from imported data
+const BOOKKEEPING_FIELDS: &[&str] = &["id", "createdAt", "updatedAt", "revision", relations::EXPANDED_KEY];
+
+/// Where the records to import come from
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ImportSource {
+    /// Path of a JSON file holding an array of records, e.g. one listed by `list_json_files`
+    File(String),
+    Records(Vec<Value>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ImportStatus {
+    New,
+    Updated,
+    Unchanged,
+    /// Needs a decision: several possible matches, or only a similar name
+    Conflicting,
+}
+
+/// How an imported record was matched to a stored one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MatchKind {
+    Id,
+    SourceId,
+    VCardUId,
+    Name,
+    SimilarName,
+}
+
+/// The plan for one imported record
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportItem {
+    /// Position of the record in the imported array
+    pub index: usize,
+    pub status: ImportStatus,
+    pub matched_by: Option<MatchKind>,
+    /// The stored record that will be updated
+    pub record_id: Option<String>,
+    /// The stored records a conflicting record might be
+    pub candidates: Vec<String>,
+    /// Why a conflicting record needs a decision
+    pub reason: Option<String>,
+    /// Fields the import changes on the matched record
+    pub changes: Vec<FieldChange>,
+    /// Problems the record would have once saved; such records must be skipped
+    pub errors: Vec<FieldError>,
+    pub record: Value,
+}
+
+#[derive(Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportCounts {
+    pub new: usize,
+    pub updated: usize,
+    pub unchanged: usize,
+    pub conflicting: usize,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportPreview {
+    pub model_name: String,
+    /// Model revision the preview was made against; `apply_import` requires it
+    pub revision: i64,
+    pub counts: ImportCounts,
+    pub items: Vec<ImportItem>,
+}
+
+/// What to do with one imported record, overriding the preview's plan
+#[derive(Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Resolution {
+    Skip,
+    Create,
+    /// Update the stored record with this id
+    Update(String),
+}
+
+#[derive(Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportResult {
+    pub created: usize,
+    pub updated: usize,
+    pub unchanged: usize,
+    pub skipped: usize,
+    /// Model revision after the import
+    pub revision: i64,
+}
+
+pub fn load_source(app: &AppHandle, source: ImportSource) -> Result<Vec<Value>, String> {
+    match source {
+        ImportSource::Records(records) => Ok(records),
+        ImportSource::File(path) => {
+            let path = sandbox::resolve_file(app, &path)?;
+            let contents = fs::read_to_string(&path)
+                .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+            serde_json::from_str(&contents)
+                .map_err(|e| format!("{} is not a JSON array of records: {}", path.display(), e))
+        }
+    }
+}
+
+fn text_field<'a>(record: &'a Value, field: &str) -> Option<&'a str> {
+    record
+        .get(field)
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|text| !text.is_empty())
+}
+
+/// null and blank text are "no value": they never overwrite stored data
+fn is_blank(value: &Value) -> bool {
+    value.is_null() || value.as_str().is_some_and(|text| text.trim().is_empty())
+}
+
+/// Records from different data sources may reuse a `sourceId`; a missing `dataSource` matches any
+fn same_source(a: &Value, b: &Value) -> bool {
+    match (text_field(a, "dataSource"), text_field(b, "dataSource")) {
+        (Some(a), Some(b)) => a.eq_ignore_ascii_case(b),
+        _ => true,
+    }
+}
+
+/// Whether two records carry identifiers saying they are different records
+fn identifiers_differ(a: &Value, b: &Value) -> bool {
+    let differ = |field| matches!((text_field(a, field), text_field(b, field)), (Some(x), Some(y)) if x != y);
+    (differ("sourceId") && same_source(a, b)) || differ("vCardUId")
+}
+
+/// Stored records sharing an identifier with `record`, trying its id, then `sourceId`
+/// (within the same `dataSource`), then `vCardUId`
+fn identifier_matches(record: &Value, existing: &[Value]) -> Option<(MatchKind, Vec<usize>)> {
+    let find = |matches: &dyn Fn(&Value) -> bool| -> Vec<usize> {
+        existing
+            .iter()
+            .enumerate()
+            .filter(|(_, stored)| matches(stored))
+            .map(|(i, _)| i)
+            .collect()
+    };
+
+    let mut found = Vec::new();
+    if let Some(id) = db::record_id_of(record) {
+        found.push((MatchKind::Id, find(&|stored| db::record_id_of(stored).as_ref() == Some(&id))));
+    }
+    if let Some(source_id) = text_field(record, "sourceId") {
+        found.push((
+            MatchKind::SourceId,
+            find(&|stored| text_field(stored, "sourceId") == Some(source_id) && same_source(record, stored)),
+        ));
+    }
+    if let Some(uid) = text_field(record, "vCardUId") {
+        found.push((MatchKind::VCardUId, find(&|stored| text_field(stored, "vCardUId") == Some(uid))));
+    }
+    found.into_iter().find(|(_, matches)| !matches.is_empty())
+}
+
+/// Fields that name a record of a model, for matching records that share no identifier
+pub fn name_fields(model_name: &str) -> &'static [&'static str] {
+    let has = |name| {
+        schema::model_schema(model_name).is_some_and(|schema| schema.fields.iter().any(|field| field.name == name))
+    };
+    if has("nameLast") {
+        &["nameFirst", "nameLast"]
+    } else if has("name") {
+        &["name"]
+    } else {
+        &[]
+    }
+}
+
+/// A record's name in lower case, without punctuation or extra spaces.
+/// None if the last name field (`nameLast` or `name`) is empty.
+pub fn name_key(record: &Value, fields: &[&str]) -> Option<String> {
+    text_field(record, fields.last()?)?;
+    let words: Vec<String> = fields
+        .iter()
+        .filter_map(|field| text_field(record, field))
+        .flat_map(|text| text.split(|c: char| !c.is_alphanumeric()))
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    Some(words.join(" "))
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, b_char) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if a_char == *b_char {
+                diagonal
+            } else {
+                1 + diagonal.min(row[j]).min(above)
+            };
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
+
+/// Names at most two typos apart, and no more than one typo per five letters,
+/// e.g. "jon smith" and "john smith"
+fn is_similar(a: &str, b: &str) -> bool {
+    let distance = edit_distance(a, b);
+    distance <= 2 && distance * 5 <= a.chars().count().max(b.chars().count())
+}
+
+/// The fields an import writes over `stored`: everything in `incoming` except
+/// bookkeeping fields and blank values, so empty cells never erase stored data
+fn import_changes(incoming: &Value, stored: &Value) -> Vec<FieldChange> {
+    let fields = match incoming.as_object() {
+        Some(fields) => fields,
+        None => return Vec::new(),
+    };
+    fields
+        .iter()
+        .filter(|(field, value)| !BOOKKEEPING_FIELDS.contains(&field.as_str()) && !is_blank(value))
+        .filter_map(|(field, value)| {
+            let before = stored.get(field).cloned().unwrap_or(Value::Null);
+            (before != *value).then(|| FieldChange { field: field.clone(), before: Some(before), after: Some(value.clone()) })
+        })
+        .collect()
+}
+
+fn apply_changes(mut record: Value, changes: &[FieldChange]) -> Value {
+    if let Some(fields) = record.as_object_mut() {
+        for change in changes {
+            if let Some(after) = &change.after {
+                fields.insert(change.field.clone(), after.clone());
+            }
+        }
+    }
+    record
+}
+
+/// An imported record laid over the model's new object, without bookkeeping fields or blank values
+fn new_record(model_name: &str, incoming: &Value) -> Result<Value, String> {
+    if !incoming.is_object() {
+        // Left as it is so validation reports it
+        return Ok(incoming.clone());
+    }
+    let template = models::default_object(model_name)?;
+    let template: Value = serde_json::from_str(template.get())
+        .map_err(|e| format!("Failed to read default {}: {}", model_name, e))?;
+    Ok(apply_changes(template.clone(), &import_changes(incoming, &template)))
+}
+
+/// Match every incoming record against the stored records of a model
+pub fn plan(conn: &Connection, model_name: &str, incoming: Vec<Value>) -> Result<ImportPreview, String> {
+    let revision = revisions::model_revision(conn, model_name)?;
+    let existing = db::read_records(conn, model_name)?;
+    let names = name_fields(model_name);
+    let existing_names: Vec<Option<String>> = existing.iter().map(|stored| name_key(stored, names)).collect();
+
+    // Stored record index -> the incoming record that claimed it first
+    let mut claimed: HashMap<usize, usize> = HashMap::new();
+    let mut counts = ImportCounts::default();
+    let mut items = Vec::with_capacity(incoming.len());
+
+    for (index, record) in incoming.into_iter().enumerate() {
+        let (matched_by, candidates) = match identifier_matches(&record, &existing) {
+            Some((kind, candidates)) => (Some(kind), candidates),
+            None => match name_key(&record, names) {
+                Some(key) => {
+                    let eligible: Vec<usize> = (0..existing.len())
+                        .filter(|&i| !identifiers_differ(&record, &existing[i]))
+                        .collect();
+                    let exact: Vec<usize> = eligible
+                        .iter()
+                        .copied()
+                        .filter(|&i| existing_names[i].as_deref() == Some(key.as_str()))
+                        .collect();
+                    let similar: Vec<usize> = eligible
+                        .into_iter()
+                        .filter(|&i| existing_names[i].as_deref().is_some_and(|name| is_similar(name, &key)))
+                        .collect();
+                    if !exact.is_empty() {
+                        (Some(MatchKind::Name), exact)
+                    } else if !similar.is_empty() {
+                        (Some(MatchKind::SimilarName), similar)
+                    } else {
+                        (None, Vec::new())
+                    }
+                }
+                None => (None, Vec::new()),
+            },
+        };
+
+        let reason = if candidates.len() > 1 {
+            Some(format!("Matches {} stored records", candidates.len()))
+        } else if matched_by == Some(MatchKind::SimilarName) {
+            Some("Only the name is similar".to_string())
+        } else {
+            candidates
+                .first()
+                .and_then(|stored| claimed.get(stored))
+                .map(|other| format!("Entry {} of the import matches the same record", other))
+        };
+        let candidate_ids: Vec<String> = candidates.iter().filter_map(|&i| db::record_id_of(&existing[i])).collect();
+
+        let item = match (reason, candidates.as_slice()) {
+            (Some(reason), _) => {
+                counts.conflicting += 1;
+                let changes = match candidates.as_slice() {
+                    [stored] => import_changes(&record, &existing[*stored]),
+                    _ => Vec::new(),
+                };
+                ImportItem {
+                    index,
+                    status: ImportStatus::Conflicting,
+                    matched_by,
+                    record_id: None,
+                    candidates: candidate_ids,
+                    reason: Some(reason),
+                    changes,
+                    errors: Vec::new(),
+                    record,
+                }
+            }
+            (None, [stored]) => {
+                claimed.insert(*stored, index);
+                let changes = import_changes(&record, &existing[*stored]);
+                let status = if changes.is_empty() {
+                    counts.unchanged += 1;
+                    ImportStatus::Unchanged
+                } else {
+                    counts.updated += 1;
+                    ImportStatus::Updated
+                };
+                ImportItem {
+                    index,
+                    status,
+                    matched_by,
+                    record_id: db::record_id_of(&existing[*stored]),
+                    candidates: candidate_ids,
+                    reason: None,
+                    errors: schema::validate(model_name, &apply_changes(existing[*stored].clone(), &changes)),
+                    changes,
+                    record,
+                }
+            }
+            (None, _) => {
+                counts.new += 1;
+                ImportItem {
+                    index,
+                    status: ImportStatus::New,
+                    matched_by: None,
+                    record_id: None,
+                    candidates: Vec::new(),
+                    reason: None,
+                    changes: Vec::new(),
+                    errors: schema::validate(model_name, &new_record(model_name, &record)?),
+                    record,
+                }
+            }
+        };
+        items.push(item);
+    }
+
+    Ok(ImportPreview {
+        model_name: model_name.to_string(),
+        revision,
+        counts,
+        items,
+    })
+}
+
+/// Apply an import planned at `revision`. New records are created and matched ones updated
+/// unless `resolutions` says otherwise; conflicting records are skipped unless resolved.
+/// Fails if the model changed since the preview or a record to be saved is invalid.
+pub fn apply(
+    conn: &Connection,
+    model_name: &str,
+    incoming: Vec<Value>,
+    revision: i64,
+    resolutions: &HashMap<usize, Resolution>,
+) -> Result<ImportResult, String> {
+    revisions::check_model(conn, model_name, revision)?;
+    let preview = plan(conn, model_name, incoming)?;
+    model_backups::backup_before_write(conn, model_name, true)?;
+
+    let mut result = ImportResult::default();
+    for item in preview.items {
+        let resolution = match (resolutions.get(&item.index), item.status) {
+            (Some(resolution), _) => resolution.clone(),
+            (None, ImportStatus::New) => Resolution::Create,
+            (None, ImportStatus::Updated | ImportStatus::Unchanged) => {
+                Resolution::Update(item.record_id.clone().unwrap_or_default())
+            }
+            (None, ImportStatus::Conflicting) => Resolution::Skip,
+        };
+        let failed = |e: String| format!("Entry {}: {}", item.index, e);
+
+        match resolution {
+            Resolution::Skip => result.skipped += 1,
+            Resolution::Create => {
+                db::insert_record_in_tx(conn, model_name, new_record(model_name, &item.record)?).map_err(failed)?;
+                result.created += 1;
+            }
+            Resolution::Update(id) => {
+                let stored = db::get_record(conn, model_name, &id).map_err(failed)?;
+                let patch: Map<String, Value> = import_changes(&item.record, &stored)
+                    .into_iter()
+                    .filter_map(|change| Some((change.field, change.after?)))
+                    .collect();
+                if patch.is_empty() {
+                    result.unchanged += 1;
+                    continue;
+                }
+                db::update_record_in_tx(conn, model_name, &id, Value::Object(patch)).map_err(failed)?;
+                result.updated += 1;
+            }
+        }
+    }
+
+    result.revision = revisions::model_revision(conn, model_name)?;
+    Ok(result)
+}
+
+/// Preview what importing an array of records into a model would do, without saving anything
+#[tauri::command]
+pub async fn preview_import(
+    app: AppHandle,
+    db: State<'_, DbConnection>,
+    model_name: String,
+    source: ImportSource,
+) -> Result<ImportPreview, String> {
+    sandbox::model_name(&model_name)?;
+    let incoming = load_source(&app, source)?;
+    db.read(move |conn| plan(conn, &model_name, incoming)).await
+}
+
+/// Import an array of records into a model, as previewed by `preview_import`.
+/// `revision` is the preview's; `resolutions` maps entry indexes to what to do with them.
+/// Everything is saved in one transaction, so a failed import changes nothing.
+#[tauri::command]
+pub async fn apply_import(
+    app: AppHandle,
+    db: State<'_, DbConnection>,
+    model_name: String,
+    source: ImportSource,
+    revision: i64,
+    resolutions: Option<HashMap<usize, Resolution>>,
+) -> Result<ImportResult, String> {
+    sandbox::model_name(&model_name)?;
+    let incoming = load_source(&app, source)?;
+    db.write(move |conn| {
+        let tx = conn
+            .unchecked_transaction()
+            .map_err(|e| format!("Failed to start transaction: {}", e))?;
+        let result = apply(&tx, &model_name, incoming, revision, &resolutions.unwrap_or_default())?;
+        tx.commit().map_err(|e| format!("Failed to commit import: {}", e))?;
+        Ok(result)
+    })
+    .await
+}
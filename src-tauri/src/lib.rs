@@ -1,13 +1,34 @@
 // Mobile entry point for iOS/Android builds
 // This file is only used for mobile builds, desktop uses main.rs
 
+mod archive;
 mod commands;
 mod db;
+mod encryption;
 mod exports;
+mod files;
+mod history;
+mod ics_exports;
+mod ics_imports;
+mod ics_updates;
+mod importer;
+mod model_backups;
+mod models;
 mod pdf_exports;
 mod print_exports;
+mod profiles;
+mod query;
+mod recurrence;
+mod relations;
+mod revisions;
+mod sandbox;
+mod schema;
+mod search;
+mod spreadsheet_exports;
+mod spreadsheet_imports;
+mod time_zones;
+mod trash;
 
-use std::sync::Mutex;
 use tauri::Manager;
 
 // Mobile entry point (for iOS/Android builds only)
@@ -19,9 +40,12 @@ fn main() {
         .plugin(tauri_plugin_fs::init())
         .setup(|app| {
             // Initialize database with AppHandle
-            let conn = db::init_db(&app.handle())
+            // The active profile decides which database is opened
+            app.manage(profiles::ActiveProfile::default());
+            let db = commands::DbConnection::default();
+            db::init_db(&app.handle(), &db)
                 .expect("Failed to initialize database");
-            app.manage(Mutex::new(conn));
+            app.manage(db);
 
             // Ensure a small placeholder exists in Documents so the Files app will show the app folder
             match exports::ensure_documents_placeholder(app.handle().clone()) {
@@ -39,13 +63,65 @@ fn main() {
             commands::greet,
             commands::get_platform,
             commands::test_db_connection,
+            commands::get_schema_version,
+            encryption::get_database_status,
+            encryption::unlock_database,
+            encryption::lock_database,
+            encryption::change_database_passphrase,
+            // Model data CRUD commands
+            commands::read_model_data,
+            commands::write_model_data,
+            commands::get_record,
+            commands::insert_record,
+            commands::update_record,
+            commands::delete_record,
+            models::get_default_object,
+            models::get_default_objects,
+            schema::get_model_schema,
+            schema::validate_record,
+            history::get_record_history,
+            history::revert_change,
+            history::get_device_info,
+            history::set_device_name,
+            model_backups::list_model_backups,
+            model_backups::restore_model_backup,
+            model_backups::get_backup_settings,
+            model_backups::set_backup_settings,
+            trash::list_trash,
+            trash::restore_from_trash,
+            trash::purge_trash,
+            trash::get_trash_settings,
+            trash::set_trash_settings,
+            importer::preview_import,
+            importer::apply_import,
+            spreadsheet_imports::preview_spreadsheet_import,
+            spreadsheet_imports::apply_spreadsheet_import,
+            query::query_model_data,
+            search::search,
+            commands::list_model_data_files,
+            archive::create_backup,
+            archive::restore_backup,
+            archive::list_backup_archives,
+            profiles::list_profiles,
+            profiles::create_profile,
+            profiles::switch_profile,
+            profiles::rename_profile,
+            profiles::delete_profile,
             exports::export_ics,
             exports::export_vcard,
+            spreadsheet_exports::export_model_csv,
+            spreadsheet_exports::export_model_xlsx,
             exports::get_ics_content,
+            exports::export_ics_updates,
+            exports::get_ics_updates,
+            exports::mark_ics_sent,
+            recurrence::list_occurrences,
             exports::get_vcard_content,
             // Also expose file-related commands on mobile (import/export/list)
             exports::list_json_files,
             exports::read_json_file,
+            exports::list_ics_files,
+            exports::read_ics_file,
             exports::create_sample_events,
             // Ensure placeholder so Files app shows the folder on device installs
             exports::ensure_documents_placeholder,
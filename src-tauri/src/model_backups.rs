@@ -0,0 +1,170 @@
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::State;
+use crate::commands::DbConnection;
+use crate::{db, sandbox};
+
+/// Number of backups kept per model unless the user changes it
+pub const DEFAULT_KEEP_COUNT: u32 = 10;
+
+/// Setting that holds the number of backups kept per model (0 turns backups off)
+const KEEP_COUNT_SETTING: &str = "modelBackups.keepCount";
+
+/// Single-record edits back up a model at most this often
+const AUTO_BACKUP_INTERVAL_MINUTES: i64 = 60;
+
+/// A stored backup of one model, without its records
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelBackupInfo {
+    pub id: i64,
+    pub model_name: String,
+    pub created_at: String,
+    pub record_count: i64,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupSettings {
+    pub keep_count: u32,
+}
+
+fn keep_count(conn: &Connection) -> Result<u32, String> {
+    Ok(db::get_setting(conn, KEEP_COUNT_SETTING)?
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(DEFAULT_KEEP_COUNT))
+}
+
+/// Save the current records of a model before they are changed.
+/// `force` backs up unconditionally (whole-model writes, restores); otherwise a
+/// backup is only taken if the last one is older than the auto-backup interval.
+/// Empty models are not backed up, and only the newest `keepCount` backups are kept.
+pub fn backup_before_write(conn: &Connection, model_name: &str, force: bool) -> Result<(), String> {
+    let keep = keep_count(conn)?;
+    if keep == 0 {
+        return Ok(());
+    }
+
+    if !force {
+        let cutoff = (chrono::Utc::now() - chrono::Duration::minutes(AUTO_BACKUP_INTERVAL_MINUTES))
+            .to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+        let recent: bool = conn
+            .query_row(
+                "SELECT EXISTS(SELECT 1 FROM model_backups WHERE model_name = ?1 AND created_at > ?2)",
+                params![model_name, cutoff],
+                |row| row.get(0),
+            )
+            .map_err(|e| format!("Failed to check backups: {}", e))?;
+        if recent {
+            return Ok(());
+        }
+    }
+
+    let records = db::read_records(conn, model_name)?;
+    if records.is_empty() {
+        return Ok(());
+    }
+    let data = serde_json::to_string(&records)
+        .map_err(|e| format!("Failed to serialize backup: {}", e))?;
+
+    conn.execute(
+        "INSERT INTO model_backups (model_name, created_at, record_count, data) VALUES (?1, ?2, ?3, ?4)",
+        params![model_name, db::now_timestamp(), records.len() as i64, data],
+    )
+    .map_err(|e| format!("Failed to save backup: {}", e))?;
+
+    prune_backups(conn, model_name, keep)
+}
+
+/// Drop all but the newest `keep` backups of a model
+fn prune_backups(conn: &Connection, model_name: &str, keep: u32) -> Result<(), String> {
+    conn.execute(
+        "DELETE FROM model_backups WHERE model_name = ?1 AND id NOT IN (
+            SELECT id FROM model_backups WHERE model_name = ?1 ORDER BY id DESC LIMIT ?2
+        )",
+        params![model_name, keep],
+    )
+    .map(|_| ())
+    .map_err(|e| format!("Failed to prune backups: {}", e))
+}
+
+/// List backups, newest first, for one model or for all models
+#[tauri::command]
+pub async fn list_model_backups(
+    db: State<'_, DbConnection>,
+    model_name: Option<String>,
+) -> Result<Vec<ModelBackupInfo>, String> {
+    if let Some(name) = &model_name {
+        sandbox::model_name(name)?;
+    }
+    db.read(move |conn| {
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, model_name, created_at, record_count FROM model_backups
+                 WHERE ?1 IS NULL OR model_name = ?1
+                 ORDER BY id DESC",
+            )
+            .map_err(|e| format!("Failed to prepare query: {}", e))?;
+        let rows = stmt
+            .query_map(params![model_name], |row| {
+                Ok(ModelBackupInfo {
+                    id: row.get(0)?,
+                    model_name: row.get(1)?,
+                    created_at: row.get(2)?,
+                    record_count: row.get(3)?,
+                })
+            })
+            .map_err(|e| format!("Failed to query backups: {}", e))?;
+
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to read backup: {}", e))
+    })
+    .await
+}
+
+/// Replace a model's records with the contents of a backup.
+/// The current records are backed up first, so a restore can itself be undone.
+#[tauri::command]
+pub async fn restore_model_backup(db: State<'_, DbConnection>, backup_id: i64) -> Result<Vec<Value>, String> {
+    db.write(move |conn| {
+        let (model_name, data): (String, String) = conn
+            .query_row(
+                "SELECT model_name, data FROM model_backups WHERE id = ?1",
+                params![backup_id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .map_err(|e| format!("Backup {} not found: {}", backup_id, e))?;
+
+        let records: Vec<Value> = serde_json::from_str(&data)
+            .map_err(|e| format!("Failed to parse backup: {}", e))?;
+
+        db::replace_records(conn, &model_name, &records)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn get_backup_settings(db: State<'_, DbConnection>) -> Result<BackupSettings, String> {
+    db.read(|conn| Ok(BackupSettings { keep_count: keep_count(conn)? })).await
+}
+
+/// Change how many backups are kept per model; existing extras are removed right away
+#[tauri::command]
+pub async fn set_backup_settings(db: State<'_, DbConnection>, settings: BackupSettings) -> Result<(), String> {
+    db.write(move |conn| {
+        db::set_setting(conn, KEEP_COUNT_SETTING, &settings.keep_count.to_string())?;
+        conn.execute(
+            "DELETE FROM model_backups WHERE id IN (
+                SELECT id FROM (
+                    SELECT id, ROW_NUMBER() OVER (PARTITION BY model_name ORDER BY id DESC) AS newest
+                    FROM model_backups
+                ) WHERE newest > ?1
+            )",
+            params![settings.keep_count],
+        )
+        .map(|_| ())
+        .map_err(|e| format!("Failed to prune backups: {}", e))
+    })
+    .await
+}
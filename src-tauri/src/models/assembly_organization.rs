@@ -0,0 +1,13 @@
+use serde_json::Value;
+
+model! {
+    /// A person's assignment in the organization of a circuit assembly (`src/models/assemblyOrganizationObject.js`)
+    AssemblyOrganization = "AssemblyOrganizationObject" {
+        circuit: Option<Value> = None,
+        circuit_section: Option<Value> = None,
+        department: Option<Value> = None,
+        assignment_role: Option<Value> = None,
+        person: Option<String> = None,
+        deleted: bool = false,
+    }
+}
@@ -0,0 +1,16 @@
+model! {
+    /// A branch office and how to correspond with it (`src/models/branchOfficeObject.js`)
+    BranchOffice = "BranchOfficeObject" {
+        name: String = String::new(),
+        country: String = String::new(),
+        selected_country: String = String::new(),
+        address: String = String::new(),
+        city: String = String::new(),
+        state_or_code: String = String::new(),
+        postal_code: String = String::new(),
+        jw_email: String = String::new(),
+        email_code: String = String::new(),
+        correspondence_language: String = String::new(),
+        notes: String = String::new(),
+    }
+}
@@ -0,0 +1,14 @@
+use serde_json::Value;
+
+model! {
+    /// A checklist, optionally attached to another record (`src/models/checklistObject.js`)
+    Checklist = "ChecklistObject" {
+        checklist_name: String = String::new(),
+        checklist_description: String = String::new(),
+        c_parent_id: Option<Value> = None,
+        c_parent_type: Option<Value> = None,
+        archived: bool = false,
+        expanded: bool = false,
+        card_color: String = "#00A300".to_string(),
+    }
+}
@@ -0,0 +1,14 @@
+use serde_json::Value;
+
+model! {
+    /// One item of a checklist (`src/models/checklistItemObject.js`)
+    ChecklistItem = "ChecklistItemObject" {
+        checklist_id: String = String::new(),
+        item_completed: bool = false,
+        item_name: String = String::new(),
+        item_description: String = String::new(),
+        date_due: String = String::new(),
+        c_child_id: String = String::new(),
+        assigned_to: Vec<Value> = Vec::new(),
+    }
+}
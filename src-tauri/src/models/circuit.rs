@@ -0,0 +1,13 @@
+use serde_json::Value;
+
+model! {
+    /// A circuit (`src/models/circuitObject.js`)
+    Circuit = "CircuitObject" {
+        country: String = String::new(),
+        state_or_code: Option<Value> = None,
+        number: Option<Value> = None,
+        language: Option<Value> = None,
+        user_toggle: bool = false,
+        number_only: bool = false,
+    }
+}
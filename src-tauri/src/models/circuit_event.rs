@@ -0,0 +1,53 @@
+use serde_json::Value;
+
+model! {
+    /// A circuit assembly or other circuit event (`src/models/circuitEventObject.js`)
+    CircuitEvent = "CircuitEventObject" {
+        event_date: Option<String> = None,
+        event_type: Option<Value> = None,
+        event_venue: Option<String> = None,
+        rehearsal_date: Option<String> = None,
+        rehearsal_time_start: Option<Value> = None,
+        rehearsal_time_end: Option<Value> = None,
+        rehearsal_venue: Option<String> = None,
+        rehearsal_calendar_sequence: i64 = 0,
+        rehearsal_calendar_u_id: Option<String> = None,
+        walk_through_time: Option<Value> = None,
+        #[serde(rename = "walkThroughTimePM")]
+        walk_through_time_pm: Option<Value> = None,
+        include_rehearsal_cal: bool = false,
+        notes: Option<String> = None,
+        co_notes: Option<String> = None,
+        branch_rep: Option<Value> = None,
+        field_instructor: Option<Value> = None,
+        #[serde(rename = "sharedCO")]
+        shared_co: Option<Value> = None,
+        circuit: Option<String> = None,
+        circuit_section: Vec<Value> = Vec::new(),
+        event_language: Option<Value> = None,
+        delivery_medium: String = "co-located".to_string(),
+        #[serde(rename = "jwssURL")]
+        jwss_url: Option<String> = None,
+        jwss_overseer: Option<Value> = None,
+        jwss_operator1: Option<Value> = None,
+        jwss_operator2: Option<Value> = None,
+        video_conference_host: Option<Value> = None,
+        meeting_id: Option<Value> = None,
+        meeting_password: Option<String> = None,
+        #[serde(rename = "meetingURL")]
+        meeting_url: Option<String> = None,
+        event_outline_files: Vec<Value> = Vec::new(),
+        speaker_direction_files: Vec<Value> = Vec::new(),
+        notice317_created: bool = false,
+        notice317_date_created: Option<String> = None,
+        auditor: Option<Value> = None,
+        audit_assigned_date: Option<String> = None,
+        audit_completed: bool = false,
+        peak_attendance: Option<i64> = None,
+        number_baptized: Option<i64> = None,
+        report318_event_id: Option<Value> = None,
+        circuit_participants_only: bool = true,
+        chairmen_array: Vec<Value> = Vec::new(),
+        routing_event_id: Option<Value> = None,
+    }
+}
@@ -0,0 +1,30 @@
+use serde_json::Value;
+
+model! {
+    /// One program part of a circuit event (`src/models/circuitEventPartObject.js`)
+    CircuitEventPart = "CircuitEventPartObject" {
+        part_time: Option<Value> = None,
+        part_duration_in_minutes: Option<i64> = None,
+        part_theme: Option<Value> = None,
+        part_outline_number: Option<Value> = None,
+        part_branch_rep: bool = false,
+        part_field_rep: bool = false,
+        #[serde(rename = "partSharedCO")]
+        part_shared_co: bool = false,
+        #[serde(rename = "partCOTalk")]
+        part_co_talk: bool = false,
+        part_speaker: Option<Value> = None,
+        part_type: Option<Value> = None,
+        talk_includes: Vec<Value> = Vec::new(),
+        symposium: bool = false,
+        part_instructions: Option<Value> = None,
+        video_link: Option<Value> = None,
+        part_confirmed: bool = false,
+        email_sent: bool = false,
+        jw_email_sent: bool = false,
+        part_outline_files: Vec<Value> = Vec::new(),
+        speaker_direction_files: Vec<Value> = Vec::new(),
+        event: Option<String> = None,
+        participants: Vec<Value> = Vec::new(),
+    }
+}
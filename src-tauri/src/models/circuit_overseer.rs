@@ -0,0 +1,21 @@
+use serde_json::Value;
+
+model! {
+    /// A circuit overseer, e.g. a neighbouring or substitute CO (`src/models/circuitOverseerObject.js`)
+    CircuitOverseer = "CircuitOverseerObject" {
+        name_first: String = String::new(),
+        name_last: String = String::new(),
+        name_suffix: String = String::new(),
+        name_preferred: String = String::new(),
+        email_main: String = String::new(),
+        #[serde(rename = "emailJWOrg")]
+        email_jw_org: String = String::new(),
+        phone_mobile: String = String::new(),
+        phone_home: String = String::new(),
+        circuit: String = String::new(),
+        branch_rep: bool = false,
+        field_instructor: bool = false,
+        photo: Option<Value> = None,
+        notes: String = String::new(),
+    }
+}
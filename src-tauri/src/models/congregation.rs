@@ -0,0 +1,40 @@
+use serde_json::Value;
+
+model! {
+    /// A congregation or group (`src/models/congregationObject.js`)
+    Congregation = "CongregationObject" {
+        number: String = String::new(),
+        name: String = String::new(),
+        branch_office_congregation_name: String = String::new(),
+        city: String = String::new(),
+        city_none: bool = false,
+        state_province: String = String::new(),
+        state_province_none: bool = false,
+        country: String = String::new(),
+        venue: Option<String> = None,
+        time_zone: String = String::new(),
+        midweek_meeting_day: Option<Value> = None,
+        midweek_meeting_time: Option<Value> = None,
+        weekend_meeting_day: Option<Value> = None,
+        weekend_meeting_time: Option<Value> = None,
+        circuit: Option<String> = None,
+        circuit_section: Option<Value> = None,
+        language: Option<Value> = None,
+        language_forms: Option<Value> = None,
+        group: bool = false,
+        host_congregation: Option<String> = None,
+        sponsors_group: bool = false,
+        prison_contact: bool = false,
+        #[serde(rename = "participatesSMPW")]
+        participates_smpw: bool = false,
+        sponsors_pregroup: bool = false,
+        notes: String = String::new(),
+        branch: Option<Value> = None,
+        data_source: Option<String> = None,
+        source_id: Option<String> = None,
+        v_card_u_id: Option<String> = None,
+        kml_data: String = String::new(),
+        #[serde(rename = "jwOrgCongURL")]
+        jw_org_cong_url: String = String::new(),
+    }
+}
@@ -0,0 +1,33 @@
+use serde_json::Value;
+
+model! {
+    /// A convention (`src/models/conventionObject.js`)
+    Convention = "ConventionObject" {
+        convention_identifier: String = String::new(),
+        convention_start_date: String = String::new(),
+        venue: String = String::new(),
+        event_language: String = String::new(),
+        congregations_assigned_array: Vec<Value> = Vec::new(),
+        service_year: String = String::new(),
+        convention_committee_coordinator: String = String::new(),
+        #[serde(rename = "assitantToCCC")]
+        assitant_to_ccc: String = String::new(),
+        program_overseer: String = String::new(),
+        #[serde(rename = "assistantToPO")]
+        assistant_to_po: String = String::new(),
+        rooming_overseer: String = String::new(),
+        #[serde(rename = "assistantToRO")]
+        assistant_to_ro: String = String::new(),
+        convention_chairman: String = String::new(),
+        convention_committee_email: String = String::new(),
+        routing_event_id: String = String::new(),
+        walkthrough_time_note: String = String::new(),
+        office_assignments: Vec<Value> = Vec::new(),
+        office_personnel: Vec<Value> = Vec::new(),
+        report_q1: String = String::new(),
+        report_q2: String = String::new(),
+        report_q3: String = String::new(),
+        report_q4: String = String::new(),
+        notes: String = String::new(),
+    }
+}
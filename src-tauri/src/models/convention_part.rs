@@ -0,0 +1,24 @@
+use serde_json::Value;
+
+model! {
+    /// One program part of a convention (`src/models/conventionPartObject.js`)
+    ConventionPart = "ConventionPartObject" {
+        part_day: String = String::new(),
+        part_time: Option<Value> = None,
+        part_theme: String = String::new(),
+        part_number: Option<Value> = None,
+        part_speaker: String = String::new(),
+        part_via_video_or_stream: bool = false,
+        rehearsal_required: bool = false,
+        rehearsal_completed: bool = false,
+        rehearsal_details: String = String::new(),
+        date_assigned: String = String::new(),
+        part_confirmed: bool = false,
+        part_presentation_format: String = String::new(),
+        speaker_visitor: String = String::new(),
+        speaker_bethelite: String = String::new(),
+        convention: String = String::new(),
+        notes: String = String::new(),
+        next_part_note: String = "~".to_string(),
+    }
+}
@@ -0,0 +1,12 @@
+model! {
+    /// A reusable text template for letters and emails (`src/models/editorTemplateObject.js`)
+    EditorTemplate = "EditorTemplateObject" {
+        template_id: String = String::new(),
+        template_type: String = String::new(),
+        template_name: String = String::new(),
+        template_content: String = String::new(),
+        template_email: bool = false,
+        date_updated: String = String::new(),
+        date_created: String = String::new(),
+    }
+}
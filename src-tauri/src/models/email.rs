@@ -0,0 +1,17 @@
+use serde_json::Value;
+
+model! {
+    /// An email draft (`src/models/emailObject.js`)
+    Email = "EmailObject" {
+        email_user_name: String = String::new(),
+        email_password: String = String::new(),
+        email_to: String = String::new(),
+        email_cc: String = String::new(),
+        user_setting_word_for_cc: String = String::new(),
+        email_bcc: String = String::new(),
+        email_subject: String = String::new(),
+        email_body: String = String::new(),
+        email_attachments: Vec<Value> = Vec::new(),
+        user_setting_word_for_attach: String = String::new(),
+    }
+}
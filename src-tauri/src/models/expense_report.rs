@@ -0,0 +1,21 @@
+use serde_json::Value;
+
+model! {
+    /// An expense report (`src/models/expenseReportObject.js`)
+    ExpenseReport = "ExpenseReportObject" {
+        report_date: Option<String> = None,
+        submitted_date: Option<String> = None,
+        reimbursed_date: Option<String> = None,
+        report_title: Option<String> = None,
+        report_total: f64 = 0.0,
+        report_type: Option<Value> = None,
+        line_items: Vec<Value> = Vec::new(),
+        submit_to: Option<Value> = None,
+        submit_to_branch: bool = false,
+        report_currency: Option<Value> = None,
+        report_comments: Option<String> = None,
+        report_status: Option<Value> = None,
+        receipts_file_path: Vec<Value> = Vec::new(),
+        pdf_file_path: Option<String> = None,
+    }
+}
@@ -0,0 +1,32 @@
+use serde_json::Value;
+
+model! {
+    /// A letter (`src/models/letterObject.js`)
+    Letter = "LetterObject" {
+        letter_name: String = String::new(),
+        letter_date: String = String::new(),
+        letter_date_centered: bool = false,
+        letter_content: String = String::new(),
+        letter_regarding: String = String::new(),
+        #[serde(rename = "letterPathPDF")]
+        letter_path_pdf: String = String::new(),
+        archived: bool = false,
+        external_letter: bool = false,
+        include_letterhead: bool = true,
+        letter_head_template_content: String = String::new(),
+        letter_head_template_id: Option<Value> = None,
+        include_signature: bool = true,
+        sent_to: Vec<Value> = Vec::new(),
+        sent_cc: Vec<Value> = Vec::new(),
+        sent_from: Option<Value> = None,
+        attention: String = String::new(),
+        letter_summary: String = String::new(),
+        letter_postscript: String = String::new(),
+        submitted: bool = false,
+        date_submitted: String = String::new(),
+        connected_to_array: Vec<Value> = Vec::new(),
+        hide_to: bool = false,
+        hide_cc: bool = false,
+        attachments_paths: Vec<Value> = Vec::new(),
+    }
+}
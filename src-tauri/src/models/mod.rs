@@ -0,0 +1,210 @@
+//! Typed structs for the domain models defined in `src/models/*Object.js`.
+//!
+//! Each struct (de)serializes to the same camelCase JSON the frontend stores, and its
+//! `Default` is the frontend's `new*Object` template. Records read from the database
+//! convert with `serde_json::from_value`; fields missing from a record take their default.
+
+use rusqlite::Connection;
+use serde::de::{DeserializeOwned, Deserializer, Error as _};
+use serde::Deserialize;
+use serde_json::value::RawValue;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use crate::sandbox::AccessError;
+use crate::{db, schema};
+
+/// Declare a model struct with its default values, in the order of the frontend template.
+/// Every struct also gets `id`, `createdAt`, `updatedAt` and `revision`, and keeps fields
+/// it does not know in `extra` so nothing is lost when a record is read and written back.
+macro_rules! model {
+    (
+        $(#[$meta:meta])*
+        $name:ident = $model_name:literal {
+            $( $(#[$field_meta:meta])* $field:ident: $ty:ty = $default:expr, )*
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+        #[serde(rename_all = "camelCase", default)]
+        pub struct $name {
+            #[serde(skip_serializing_if = "Option::is_none")]
+            pub id: Option<String>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            pub created_at: Option<String>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            pub updated_at: Option<String>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            pub revision: Option<i64>,
+            $(
+                $(#[$field_meta])*
+                #[serde(deserialize_with = "crate::models::lenient")]
+                pub $field: $ty,
+            )*
+            #[serde(flatten)]
+            pub extra: serde_json::Map<String, serde_json::Value>,
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                Self {
+                    id: None,
+                    created_at: None,
+                    updated_at: None,
+                    revision: None,
+                    $( $field: $default, )*
+                    extra: serde_json::Map::new(),
+                }
+            }
+        }
+
+        impl crate::models::Model for $name {
+            const NAME: &'static str = $model_name;
+        }
+    };
+}
+
+mod assembly_organization;
+mod branch_office;
+mod checklist;
+mod checklist_item;
+mod circuit;
+mod circuit_event;
+mod circuit_event_part;
+mod circuit_overseer;
+mod congregation;
+mod convention;
+mod convention_part;
+mod editor_template;
+mod email;
+mod expense_report;
+mod letter;
+mod person;
+mod pqr;
+mod pss;
+mod pss_class_workshop;
+mod recommendation;
+mod reminder;
+mod routing;
+mod sce;
+mod sce_class_assignment;
+mod user;
+mod venue;
+mod visit;
+
+pub use assembly_organization::AssemblyOrganization;
+pub use branch_office::BranchOffice;
+pub use checklist::Checklist;
+pub use checklist_item::ChecklistItem;
+pub use circuit::Circuit;
+pub use circuit_event::CircuitEvent;
+pub use circuit_event_part::CircuitEventPart;
+pub use circuit_overseer::CircuitOverseer;
+pub use congregation::Congregation;
+pub use convention::Convention;
+pub use convention_part::ConventionPart;
+pub use editor_template::EditorTemplate;
+pub use email::Email;
+pub use expense_report::ExpenseReport;
+pub use letter::Letter;
+pub use person::Person;
+pub use pqr::Pqr;
+pub use pss::Pss;
+pub use pss_class_workshop::PssClassWorkshop;
+pub use recommendation::Recommendation;
+pub use reminder::Reminder;
+pub use routing::Routing;
+pub use sce::Sce;
+pub use sce_class_assignment::SceClassAssignment;
+pub use user::User;
+pub use venue::Venue;
+pub use visit::Visit;
+
+/// A domain model struct
+pub trait Model: serde::Serialize + DeserializeOwned + Default {
+    /// Name the model's records are stored under, e.g. `PersonObject`
+    const NAME: &'static str;
+}
+
+/// Read a field the way older records may hold it: null (and "" outside text fields)
+/// as the empty value of its type, and numbers where text is expected (ids, phone numbers)
+fn lenient<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: DeserializeOwned + Default,
+{
+    let value = Value::deserialize(deserializer)?;
+    if value.is_null() {
+        return Ok(T::default());
+    }
+    match serde_json::from_value(value.clone()) {
+        Ok(parsed) => Ok(parsed),
+        Err(_) if value.as_str() == Some("") => Ok(T::default()),
+        Err(_) if value.is_number() => {
+            serde_json::from_value(Value::String(value.to_string())).map_err(D::Error::custom)
+        }
+        Err(e) => Err(D::Error::custom(e)),
+    }
+}
+
+/// Read all records of a model as typed structs
+pub fn read_all<M: Model>(conn: &Connection) -> Result<Vec<M>, String> {
+    db::read_records(conn, M::NAME)?
+        .into_iter()
+        .map(|record| serde_json::from_value(record).map_err(|e| format!("Failed to read {} record: {}", M::NAME, e)))
+        .collect()
+}
+
+/// Serialize a model's default without going through `Value`, which would sort the fields
+fn raw_default<M: Model>() -> Result<Box<RawValue>, String> {
+    serde_json::value::to_raw_value(&M::default())
+        .map_err(|e| format!("Failed to serialize default {}: {}", M::NAME, e))
+}
+
+pub fn default_object(model_name: &str) -> Result<Box<RawValue>, String> {
+    match model_name {
+        AssemblyOrganization::NAME => raw_default::<AssemblyOrganization>(),
+        BranchOffice::NAME => raw_default::<BranchOffice>(),
+        ChecklistItem::NAME => raw_default::<ChecklistItem>(),
+        Checklist::NAME => raw_default::<Checklist>(),
+        CircuitEvent::NAME => raw_default::<CircuitEvent>(),
+        CircuitEventPart::NAME => raw_default::<CircuitEventPart>(),
+        Circuit::NAME => raw_default::<Circuit>(),
+        CircuitOverseer::NAME => raw_default::<CircuitOverseer>(),
+        Congregation::NAME => raw_default::<Congregation>(),
+        Convention::NAME => raw_default::<Convention>(),
+        ConventionPart::NAME => raw_default::<ConventionPart>(),
+        EditorTemplate::NAME => raw_default::<EditorTemplate>(),
+        Email::NAME => raw_default::<Email>(),
+        ExpenseReport::NAME => raw_default::<ExpenseReport>(),
+        Letter::NAME => raw_default::<Letter>(),
+        Person::NAME => raw_default::<Person>(),
+        Pqr::NAME => raw_default::<Pqr>(),
+        PssClassWorkshop::NAME => raw_default::<PssClassWorkshop>(),
+        Pss::NAME => raw_default::<Pss>(),
+        Recommendation::NAME => raw_default::<Recommendation>(),
+        Reminder::NAME => raw_default::<Reminder>(),
+        Routing::NAME => raw_default::<Routing>(),
+        SceClassAssignment::NAME => raw_default::<SceClassAssignment>(),
+        Sce::NAME => raw_default::<Sce>(),
+        User::NAME => raw_default::<User>(),
+        Venue::NAME => raw_default::<Venue>(),
+        Visit::NAME => raw_default::<Visit>(),
+        _ => Err(AccessError::UnknownModel(model_name.to_string()).into()),
+    }
+}
+
+/// The new object of a model, with the fields in the same order as the frontend template
+#[tauri::command]
+pub fn get_default_object(model_name: String) -> Result<Box<RawValue>, String> {
+    default_object(&model_name)
+}
+
+/// The new objects of every model, keyed by model name.
+/// The `new*Object` constants in `src/models/` can be generated from this.
+#[tauri::command]
+pub fn get_default_objects() -> Result<BTreeMap<&'static str, Box<RawValue>>, String> {
+    schema::MODELS
+        .iter()
+        .map(|model| Ok((model.name, default_object(model.name)?)))
+        .collect()
+}
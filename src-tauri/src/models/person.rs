@@ -0,0 +1,142 @@
+use serde_json::Value;
+
+model! {
+    /// A publisher or other person (`src/models/personObject.js`)
+    Person = "PersonObject" {
+        blind: bool = false,
+        deaf: bool = false,
+        name_first: String = String::new(),
+        name_middle: String = String::new(),
+        name_last: String = String::new(),
+        name_nickname: Option<String> = None,
+        name_suffix: Option<String> = None,
+        gender: Option<Value> = None,
+        hope: Option<Value> = None,
+        marital_status: Option<Value> = None,
+        spouse: Option<String> = None,
+        family_head_id: Option<String> = None,
+        date_birth: Option<String> = None,
+        date_baptism: Option<String> = None,
+        is_unbaptized: bool = false,
+        is_deceased: bool = false,
+        date_deceased: Option<String> = None,
+        congregation: Option<String> = None,
+        language_group: Option<Value> = None,
+        inactive: bool = false,
+        not_a_publisher: bool = false,
+        privilege_pioneer: bool = false,
+        privilege_pioneer_infirm: bool = false,
+        pioneer_number: Option<Value> = None,
+        pioneer_start_date: Option<String> = None,
+        pioneer_school_year: Option<Value> = None,
+        privilege_pioneer_special: bool = false,
+        privilege_pioneer_special_infirm: bool = false,
+        privilege_pioneer_special_temp: bool = false,
+        privilege_field_missionary: bool = false,
+        theo_schools_grad: Vec<Value> = Vec::new(),
+        schools_grad_class: Option<Value> = None,
+        schools_grad_year: Option<Value> = None,
+        privilege_bethel_regular: bool = false,
+        privilege_bethel_expatriate: bool = false,
+        privilege_bethel_temporary: bool = false,
+        privilege_bethel_commuter: bool = false,
+        #[serde(rename = "privilegeBethelCommuterFT")]
+        privilege_bethel_commuter_ft: bool = false,
+        privilege_bethel_remote: bool = false,
+        privilege_bethel_remote_servant: bool = false,
+        privilege_bethel_consultant: bool = false,
+        #[serde(rename = "privilegeLDCVolunteer")]
+        privilege_ldc_volunteer: bool = false,
+        #[serde(rename = "privilegeLDCServant")]
+        privilege_ldc_servant: bool = false,
+        #[serde(rename = "privilegeLDCServantExpatriate")]
+        privilege_ldc_servant_expatriate: bool = false,
+        privilege_schools_facility_servant: bool = false,
+        privilege_elder: bool = false,
+        #[serde(rename = "privilegeCO")]
+        privilege_co: bool = false,
+        #[serde(rename = "privilegeCOWife")]
+        privilege_co_wife: bool = false,
+        #[serde(rename = "privilegeSCOWife")]
+        privilege_sco_wife: bool = false,
+        sce_last_graduation_year: Option<Value> = None,
+        #[serde(rename = "privilegeMS")]
+        privilege_ms: bool = false,
+        #[serde(rename = "emailJW")]
+        email_jw: Option<String> = None,
+        #[serde(rename = "elderCBOE")]
+        elder_cboe: bool = false,
+        elder_sec: bool = false,
+        #[serde(rename = "elderSO")]
+        elder_so: bool = false,
+        #[serde(rename = "elderCLMO")]
+        elder_clmo: bool = false,
+        #[serde(rename = "elderWTSC")]
+        elder_wtsc: bool = false,
+        elder_auxiliary_counselor: bool = false,
+        #[serde(rename = "privilegeGA")]
+        privilege_ga: bool = false,
+        #[serde(rename = "privilegeGS")]
+        privilege_gs: bool = false,
+        privilege_lang_inst: bool = false,
+        #[serde(rename = "privilegePSSInst")]
+        privilege_pss_inst: bool = false,
+        privilege_accounts: bool = false,
+        privilege_audio_video: bool = false,
+        privilege_literature: bool = false,
+        #[serde(rename = "privilegePTC")]
+        privilege_ptc: bool = false,
+        privilege_territory: bool = false,
+        #[serde(rename = "privilegeKHOC")]
+        privilege_khoc: bool = false,
+        #[serde(rename = "elderSCO")]
+        elder_sco: bool = false,
+        #[serde(rename = "elderSPC")]
+        elder_spc: bool = false,
+        #[serde(rename = "elderGO")]
+        elder_go: bool = false,
+        spc_rank: i64 = 100,
+        special_committee_notes: String = String::new(),
+        convention_personnel_notes: String = String::new(),
+        assembly_speaker: bool = false,
+        assembly_speaker_notes: String = String::new(),
+        conv_committee: bool = false,
+        conv_personnel: bool = false,
+        #[serde(rename = "elderAO")]
+        elder_ao: bool = false,
+        #[serde(rename = "elderAAO")]
+        elder_aao: bool = false,
+        #[serde(rename = "elderAHOverseer")]
+        elder_ah_overseer: bool = false,
+        #[serde(rename = "elderAHServant")]
+        elder_ah_servant: bool = false,
+        #[serde(rename = "elderHLC")]
+        elder_hlc: bool = false,
+        #[serde(rename = "elderPVG")]
+        elder_pvg: bool = false,
+        elder_fld_inst: bool = false,
+        #[serde(rename = "elderBC")]
+        elder_bc: bool = false,
+        address_home_street: Option<String> = None,
+        address_home_street_line2: Option<String> = None,
+        #[serde(rename = "addressHomePOBox")]
+        address_home_po_box: Option<String> = None,
+        address_home_apt: Option<String> = None,
+        address_home_city: Option<String> = None,
+        address_home_state_province: Option<String> = None,
+        address_home_postal: Option<Value> = None,
+        address_home_country: String = String::new(),
+        phone_mobile_country_code: Option<Value> = None,
+        phone_mobile: Option<Value> = None,
+        phone_home: Option<Value> = None,
+        email_main: Option<String> = None,
+        notes: String = String::new(),
+        notes_privileges: String = String::new(),
+        photo: Option<Value> = None,
+        tags: Vec<Value> = Vec::new(),
+        data_source: Option<String> = None,
+        diseverated_from: Vec<Value> = Vec::new(),
+        source_id: Option<String> = None,
+        v_card_u_id: Option<String> = None,
+    }
+}
@@ -0,0 +1,37 @@
+use serde_json::Value;
+
+model! {
+    /// A personal qualifications report (S-326) for a person (`src/models/pqrObject.js`)
+    Pqr = "PQRObject" {
+        person_id: String = String::new(),
+        submitted: bool = false,
+        name_with_branch: String = String::new(),
+        #[serde(rename = "datePQR")]
+        date_pqr: String = String::new(),
+        age: String = String::new(),
+        assignment: String = String::new(),
+        address: String = String::new(),
+        email: String = String::new(),
+        phone: String = String::new(),
+        citizen: String = String::new(),
+        languages: String = String::new(),
+        skills: String = String::new(),
+        hope: String = String::new(),
+        theo_training: Vec<Value> = Vec::new(),
+        current_serving: Vec<Value> = Vec::new(),
+        current_serving_other: String = String::new(),
+        past_service_priv: Vec<Value> = Vec::new(),
+        past_service_other: String = String::new(),
+        q10: String = String::new(),
+        q11: String = String::new(),
+        q12: String = String::new(),
+        q13: String = String::new(),
+        q14: String = String::new(),
+        q15: String = String::new(),
+        q16: String = String::new(),
+        q17: Vec<Value> = Vec::new(),
+        q17other: String = String::new(),
+        q18: String = String::new(),
+        q19: String = String::new(),
+    }
+}
@@ -0,0 +1,42 @@
+use serde_json::Value;
+
+model! {
+    /// A pioneer service school class (`src/models/pssObject.js`)
+    Pss = "PssObject" {
+        students_array: Vec<Value> = Vec::new(),
+        students: Vec<Value> = Vec::new(),
+        class_date: Option<String> = None,
+        service_year: Option<Value> = None,
+        confirm_date: Option<String> = None,
+        language: Option<Value> = None,
+        class_venue: Option<String> = None,
+        other_co_is_instructor_b: bool = false,
+        instructor_b: Option<Value> = None,
+        alt_instructor_a: Option<Value> = None,
+        local_elder: Option<Value> = None,
+        video_conference_host: Option<Value> = None,
+        delivery_medium: String = "co-located".to_string(),
+        meeting_id: Option<Value> = None,
+        meeting_password: Option<String> = None,
+        #[serde(rename = "meetingURL")]
+        meeting_url: Option<String> = None,
+        host_congregation: Option<Value> = None,
+        book_pickup_location: Option<Value> = None,
+        book_pickup_date: Option<String> = None,
+        book_pickup_time: Option<Value> = None,
+        book_pickup_calendar_sequence: i64 = 0,
+        book_pickup_calendar_u_id: Option<String> = None,
+        class_notes: Option<String> = None,
+        include_workshop_cal: bool = false,
+        hide_confirmations: bool = false,
+        hide_books: bool = false,
+        prayer_schedule: Vec<Value> = Vec::new(),
+        prayer_schedule_path: Option<String> = None,
+        prayer_schedule_email_sent: bool = false,
+        last_grad_year_updated: bool = false,
+        rehearsal_date_default: Option<String> = None,
+        rehearsal_time_start_default: Option<Value> = None,
+        rehearsal_time_end_default: Option<Value> = None,
+        rehearsal_venue_default: Option<Value> = None,
+    }
+}
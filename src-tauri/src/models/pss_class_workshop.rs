@@ -0,0 +1,21 @@
+use serde_json::Value;
+
+model! {
+    /// A workshop assignment of a pioneer service school class (`src/models/pssClassWorkshopObject.js`)
+    PssClassWorkshop = "PssClassWorkshopObject" {
+        class_workshop_assignment_number: Option<Value> = None,
+        participants_array: Vec<Value> = Vec::new(),
+        pss_class: String = String::new(),
+        participants: Vec<Value> = Vec::new(),
+        confirmed: bool = false,
+        email_sent: bool = false,
+        pdf_file_path: Option<String> = None,
+        automation_send_result: Vec<Value> = Vec::new(),
+        calendar_sequence: i64 = 0,
+        rehearsal_date: Option<String> = None,
+        rehearsal_time_start: Option<Value> = None,
+        rehearsal_time_end: Option<Value> = None,
+        rehearsal_venue: Option<Value> = None,
+        rehearsal_contact_elder: Option<Value> = None,
+    }
+}
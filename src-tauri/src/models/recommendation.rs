@@ -0,0 +1,18 @@
+use serde_json::Value;
+
+model! {
+    /// A recommendation for an appointment or deletion (`src/models/recommendationObject.js`)
+    Recommendation = "RecommendationObject" {
+        action_type: Option<Value> = None,
+        delete_reason: Option<Value> = None,
+        appointment_type: Option<Value> = None,
+        appt_delete_completed: bool = false,
+        notes: String = String::new(),
+        notes_personal: String = String::new(),
+        person: Option<String> = None,
+        rejected: bool = false,
+        appoint_delete_date: Option<String> = None,
+        visit: Option<String> = None,
+        list_order: i64 = 99,
+    }
+}
@@ -0,0 +1,9 @@
+model! {
+    /// A reminder attached to another record (`src/models/reminderObject.js`)
+    Reminder = "ReminderObject" {
+        object_type: String = String::new(),
+        object_id: String = String::new(),
+        event_date: Option<String> = None,
+        reminder_type: String = String::new(),
+    }
+}
@@ -0,0 +1,33 @@
+use serde_json::Value;
+
+model! {
+    /// One week of the circuit overseer's routing schedule (`src/models/routingObject.js`)
+    Routing = "RoutingObject" {
+        date_start: Option<String> = None,
+        event_type: Option<Value> = None,
+        congregation: Option<String> = None,
+        circuit: Option<String> = None,
+        notes: String = String::new(),
+        submitted: bool = false,
+        shared_c_ocontacted: bool = false,
+        email_c_osent: bool = false,
+        no_conflict: bool = false,
+        visit_package_sent: bool = false,
+        s302sent: bool = false,
+        s302confirmed: bool = false,
+        #[serde(rename = "subCO")]
+        sub_co: Option<String> = None,
+        co_being_served: Option<Value> = None,
+        visiting_shepherd: Option<Value> = None,
+        visit_created: bool = false,
+        branch_notified: bool = false,
+        email_reminder_sent: bool = false,
+        jwss_reminder_sent: bool = false,
+        special_event_start_time: Option<Value> = None,
+        special_event_venue: Option<String> = None,
+        calendar_u_id: Option<String> = None,
+        calendar_sequence: i64 = 0,
+        pdf_file_path: Option<String> = None,
+        is_imported: bool = false,
+    }
+}
@@ -0,0 +1,30 @@
+use serde_json::Value;
+
+model! {
+    /// A school for congregation elders class (`src/models/sceObject.js`)
+    Sce = "SceObject" {
+        students_array: Vec<Value> = Vec::new(),
+        class_number: Option<Value> = None,
+        class_date: Option<String> = None,
+        class_venue: Option<String> = None,
+        alt_instructor_a: Option<Value> = None,
+        instructor_b: Option<Value> = None,
+        location_coordinator: Option<Value> = None,
+        student_source: String = "branch".to_string(),
+        manually_added_students: bool = false,
+        prayer_schedule: Vec<Value> = Vec::new(),
+        prayer_schedule_path: Option<String> = None,
+        prayer_schedule_email_sent: bool = false,
+        branch_text_student: String = String::new(),
+        #[serde(rename = "branchTextCBOE")]
+        branch_text_cboe: String = String::new(),
+        #[serde(rename = "branchTextStudentCBOE")]
+        branch_text_student_cboe: String = String::new(),
+        not_attending_array: Vec<Value> = Vec::new(),
+        attendance_report_path: Option<String> = None,
+        attendance_report_sent: bool = false,
+        class_notes: Option<String> = None,
+        calendar_u_id: Option<String> = None,
+        calendar_sequence: i64 = 0,
+    }
+}
@@ -0,0 +1,15 @@
+use serde_json::Value;
+
+model! {
+    /// An assignment in a school for congregation elders class (`src/models/sceClassAssignmentObject.js`)
+    SceClassAssignment = "SceClassAssignmentObject" {
+        class_assignment_number: Option<Value> = None,
+        participants: Vec<Value> = Vec::new(),
+        participants_array: Vec<Value> = Vec::new(),
+        email_sent: bool = false,
+        date_sent: String = String::new(),
+        sce_class: String = String::new(),
+        pdf_file_path: String = String::new(),
+        confirmed: bool = false,
+    }
+}
@@ -0,0 +1,47 @@
+use serde_json::{Map, Value};
+
+model! {
+    /// The user of the app (the circuit overseer) (`src/models/userObject.js`)
+    User = "UserObject" {
+        name_first: String = String::new(),
+        name_middle: String = String::new(),
+        name_last: String = String::new(),
+        name_suffix: Option<String> = None,
+        name_nickname: Option<String> = None,
+        wife_name: Option<String> = None,
+        wife_phone: Option<Value> = None,
+        wife_photo: Option<Value> = None,
+        address_home_street: Option<String> = None,
+        address_home_apt: Option<String> = None,
+        address_home_city: Option<String> = None,
+        address_home_state_province: Option<String> = None,
+        address_home_postal: Option<Value> = None,
+        phone_home: Option<Value> = None,
+        phone_mobile: Option<Value> = None,
+        email_main: Option<String> = None,
+        email_main_password: Option<String> = None,
+        #[serde(rename = "emailJW")]
+        email_jw: Option<String> = None,
+        #[serde(rename = "emailJWPubPassword")]
+        email_jw_pub_password: Option<String> = None,
+        date_birth: Option<String> = None,
+        date_baptism: Option<String> = None,
+        hope: Option<Value> = None,
+        theo_schools_grad: Option<Value> = None,
+        personnel_number: Option<Value> = None,
+        sce_instructor: bool = false,
+        sce_instructor_fulltime: bool = false,
+        circuit: Option<Value> = None,
+        circuit_next: Option<Value> = None,
+        circuit_next_effective_date: Option<String> = None,
+        time_zone: String = String::new(),
+        s307sent: bool = false,
+        pdf_file_path307: String = String::new(),
+        photo: Option<Value> = None,
+        pet_allergies: bool = false,
+        notes: String = String::new(),
+        brother_following_me: String = String::new(),
+        brother_followed: String = String::new(),
+        license_info: Map<String, Value> = Map::new(),
+    }
+}
@@ -0,0 +1,22 @@
+use serde_json::Value;
+
+model! {
+    /// A Kingdom Hall, Assembly Hall or other venue (`src/models/venueObject.js`)
+    Venue = "VenueObject" {
+        name: String = String::new(),
+        name_other: String = String::new(),
+        street: String = String::new(),
+        city: String = String::new(),
+        state_province: String = String::new(),
+        postal: String = String::new(),
+        phone: String = String::new(),
+        email_main: String = String::new(),
+        latitude: String = String::new(),
+        longitude: String = String::new(),
+        time_zone: String = String::new(),
+        alarm_code: String = String::new(),
+        wifi_network_name: String = String::new(),
+        wifi_network_password: String = String::new(),
+        overlapping_circuits: Vec<Value> = Vec::new(),
+    }
+}
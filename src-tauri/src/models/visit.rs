@@ -0,0 +1,209 @@
+use serde_json::Value;
+
+model! {
+    /// A circuit overseer's visit to a congregation (`src/models/visitObject.js`)
+    Visit = "VisitObject" {
+        date_start: Option<String> = None,
+        congregation: Option<String> = None,
+        package_sent: bool = false,
+        package_sent_date: Option<String> = None,
+        review_period_starting_month: Option<Value> = None,
+        package_received: bool = false,
+        count_territories: Option<i64> = None,
+        count_territories_not_worked: Option<i64> = None,
+        count_territories_business: Option<i64> = None,
+        count_territories_business_not_worked: Option<i64> = None,
+        territory_comments: Option<String> = None,
+        count_territory_contacts: Option<i64> = None,
+        accounts_audit_last_quarter: Option<Value> = None,
+        accounts_audit_first_quarter: Option<Value> = None,
+        accounts_audit_regular: bool = false,
+        accounts_audit_first_date: Option<String> = None,
+        accounts_audit_last_date: Option<String> = None,
+        accounts_audit_first_auditor: String = String::new(),
+        accounts_audit_last_auditor: String = String::new(),
+        accounts_audit_diff_brothers: bool = false,
+        accounts_audit_issues: bool = false,
+        accounts_audit_issues_note: String = String::new(),
+        accounts_audit_reconicliation: bool = false,
+        accounts_donations_monthly: bool = false,
+        accounts_average_funds_held: f64 = 0.0,
+        accounts_average_funds_held_month1: f64 = 0.0,
+        accounts_average_funds_held_month2: f64 = 0.0,
+        accounts_average_funds_held_month3: f64 = 0.0,
+        accounts_average_funds_held_month4: f64 = 0.0,
+        accounts_average_funds_held_month5: f64 = 0.0,
+        accounts_average_funds_held_month6: f64 = 0.0,
+        accounts_average_funds_needed: f64 = 0.0,
+        accounts_average_funds_needed_month1: f64 = 0.0,
+        accounts_average_funds_needed_month2: f64 = 0.0,
+        accounts_average_funds_needed_month3: f64 = 0.0,
+        accounts_average_funds_needed_month4: f64 = 0.0,
+        accounts_average_funds_needed_month5: f64 = 0.0,
+        accounts_average_funds_needed_month6: f64 = 0.0,
+        accounts_target_balance: f64 = 0.0,
+        accounts_target_balance_last_date: Option<String> = None,
+        accounts_funds_balance_acceptable: bool = false,
+        accounts_general_comments: String = String::new(),
+        accounts_monthly_approved_resolution: bool = false,
+        #[serde(rename = "accountsMonthlyWWW")]
+        accounts_monthly_www: f64 = 0.0,
+        #[serde(rename = "accountsMonthlyWWWResolutionDate")]
+        accounts_monthly_www_resolution_date: Option<String> = None,
+        accounts_monthly_approved_resolution_reasonable: bool = false,
+        title_holding_cong: bool = false,
+        visit_notes_private: String = String::new(),
+        pubs_baptized_count_total: i64 = 0,
+        pioneers_count_total: i64 = 0,
+        pubs_unaptized_count_total: i64 = 0,
+        inactive_total: i64 = 0,
+        irregular_total: i64 = 0,
+        reactivated_total: i64 = 0,
+        new_publishers_total: i64 = 0,
+        baptized_since_last_visit: i64 = 0,
+        aux_pioneers_count: i64 = 0,
+        aux_pioneers_this_visit_count: i64 = 0,
+        topics_elders: String = String::new(),
+        #[serde(rename = "topicsCO")]
+        topics_co: String = String::new(),
+        group_midweek_meeting_day: String = String::new(),
+        weekday_morning_service_time: String = String::new(),
+        weekday_afternoon_service_time: String = String::new(),
+        saturday_service_time: String = String::new(),
+        sunday_service_time: String = String::new(),
+        half_day_off: String = String::new(),
+        tuesday_meeting_time: String = String::new(),
+        weekend_meeting_day: Option<Value> = None,
+        weekend_meeting_time: String = String::new(),
+        #[serde(rename = "eldersMSMeetingDay")]
+        elders_ms_meeting_day: Option<Value> = None,
+        #[serde(rename = "eldersMSMeetingTime")]
+        elders_ms_meeting_time: String = String::new(),
+        pioneer_meeting_day: Option<Value> = None,
+        pioneer_meeting_time: String = String::new(),
+        pioneer_meeting_theme: String = String::new(),
+        pioneer_meeting_for_group: bool = false,
+        co_pioneer_outline: bool = false,
+        elders_meeting_theme: String = String::new(),
+        tuesday_service_talk_theme: String = String::new(),
+        tuesday_concluding_song: String = String::new(),
+        public_talk_theme: String = String::new(),
+        public_talk_song: String = String::new(),
+        weekend_service_talk_theme: String = String::new(),
+        weekend_concluding_song: String = String::new(),
+        #[serde(rename = "meetingCBOENotes")]
+        meeting_cboe_notes: String = String::new(),
+        service_talk_notes: String = String::new(),
+        report_body: String = String::new(),
+        s303sent_branch: bool = false,
+        s303sent_cong: bool = false,
+        s303printed: bool = false,
+        track_recomendation_printed: bool = false,
+        track_recomendation_sent: bool = false,
+        track_recomendation_jworg: bool = false,
+        calendar_u_id: Option<String> = None,
+        narrow_margin: bool = false,
+        calendar_sequence: i64 = 0,
+        pdf_file_path_s303: String = String::new(),
+        pdf_file_path_visit_package: String = String::new(),
+        pdf_file_path_appointment_letter: String = String::new(),
+        #[serde(rename = "pdfFilePathBOEAgenda")]
+        pdf_file_path_boe_agenda: String = String::new(),
+        #[serde(rename = "pdfFilePathBOEAgendaPersonal")]
+        pdf_file_path_boe_agenda_personal: String = String::new(),
+        external_attachments_custom_fill: Vec<Value> = Vec::new(),
+        agenda_prayer_open_brother: Option<Value> = None,
+        agenda_prayer_close_brother: Option<Value> = None,
+        pubs_b_ss_month1: Option<i64> = None,
+        pubs_b_ss_month2: Option<i64> = None,
+        pubs_b_ss_month3: Option<i64> = None,
+        pubs_b_ss_month4: Option<i64> = None,
+        pubs_b_ss_month5: Option<i64> = None,
+        pubs_b_ss_month6: Option<i64> = None,
+        pubs_reporting_month1: Option<i64> = None,
+        pubs_reporting_month2: Option<i64> = None,
+        pubs_reporting_month3: Option<i64> = None,
+        pubs_reporting_month4: Option<i64> = None,
+        pubs_reporting_month5: Option<i64> = None,
+        pubs_reporting_month6: Option<i64> = None,
+        aux_pios_hours_month1: Option<f64> = None,
+        aux_pios_hours_month2: Option<f64> = None,
+        aux_pios_hours_month3: Option<f64> = None,
+        aux_pios_hours_month4: Option<f64> = None,
+        aux_pios_hours_month5: Option<f64> = None,
+        aux_pios_hours_month6: Option<f64> = None,
+        aux_pios_b_ss_month1: Option<i64> = None,
+        aux_pios_b_ss_month2: Option<i64> = None,
+        aux_pios_b_ss_month3: Option<i64> = None,
+        aux_pios_b_ss_month4: Option<i64> = None,
+        aux_pios_b_ss_month5: Option<i64> = None,
+        aux_pios_b_ss_month6: Option<i64> = None,
+        aux_pios_reporting_month1: Option<i64> = None,
+        aux_pios_reporting_month2: Option<i64> = None,
+        aux_pios_reporting_month3: Option<i64> = None,
+        aux_pios_reporting_month4: Option<i64> = None,
+        aux_pios_reporting_month5: Option<i64> = None,
+        aux_pios_reporting_month6: Option<i64> = None,
+        pios_hours_month1: Option<f64> = None,
+        pios_hours_month2: Option<f64> = None,
+        pios_hours_month3: Option<f64> = None,
+        pios_hours_month4: Option<f64> = None,
+        pios_hours_month5: Option<f64> = None,
+        pios_hours_month6: Option<f64> = None,
+        pios_b_ss_month1: Option<i64> = None,
+        pios_b_ss_month2: Option<i64> = None,
+        pios_b_ss_month3: Option<i64> = None,
+        pios_b_ss_month4: Option<i64> = None,
+        pios_b_ss_month5: Option<i64> = None,
+        pios_b_ss_month6: Option<i64> = None,
+        pios_reporting_month1: Option<i64> = None,
+        pios_reporting_month2: Option<i64> = None,
+        pios_reporting_month3: Option<i64> = None,
+        pios_reporting_month4: Option<i64> = None,
+        pios_reporting_month5: Option<i64> = None,
+        pios_reporting_month6: Option<i64> = None,
+        active_publisher_count_month1: Option<i64> = None,
+        active_publisher_count_month2: Option<i64> = None,
+        active_publisher_count_month3: Option<i64> = None,
+        active_publisher_count_month4: Option<i64> = None,
+        active_publisher_count_month5: Option<i64> = None,
+        active_publisher_count_month6: Option<i64> = None,
+        alt_s88meeting_midweek_count_month1: Option<i64> = None,
+        alt_s88meeting_midweek_total_month1: Option<i64> = None,
+        alt_s88meeting_midweek_average_month1: Option<f64> = None,
+        alt_s88meeting_midweek_count_month2: Option<i64> = None,
+        alt_s88meeting_midweek_total_month2: Option<i64> = None,
+        alt_s88meeting_midweek_average_month2: Option<f64> = None,
+        alt_s88meeting_midweek_count_month3: Option<i64> = None,
+        alt_s88meeting_midweek_total_month3: Option<i64> = None,
+        alt_s88meeting_midweek_average_month3: Option<f64> = None,
+        alt_s88meeting_midweek_count_month4: Option<i64> = None,
+        alt_s88meeting_midweek_total_month4: Option<i64> = None,
+        alt_s88meeting_midweek_average_month4: Option<f64> = None,
+        alt_s88meeting_midweek_count_month5: Option<i64> = None,
+        alt_s88meeting_midweek_total_month5: Option<i64> = None,
+        alt_s88meeting_midweek_average_month5: Option<f64> = None,
+        alt_s88meeting_midweek_count_month6: Option<i64> = None,
+        alt_s88meeting_midweek_total_month6: Option<i64> = None,
+        alt_s88meeting_midweek_average_month6: Option<f64> = None,
+        alt_s88meeting_weekend_count_month1: Option<i64> = None,
+        alt_s88meeting_weekend_total_month1: Option<i64> = None,
+        alt_s88meeting_weekend_average_month1: Option<f64> = None,
+        alt_s88meeting_weekend_count_month2: Option<i64> = None,
+        alt_s88meeting_weekend_total_month2: Option<i64> = None,
+        alt_s88meeting_weekend_average_month2: Option<f64> = None,
+        alt_s88meeting_weekend_count_month3: Option<i64> = None,
+        alt_s88meeting_weekend_total_month3: Option<i64> = None,
+        alt_s88meeting_weekend_average_month3: Option<f64> = None,
+        alt_s88meeting_weekend_count_month4: Option<i64> = None,
+        alt_s88meeting_weekend_total_month4: Option<i64> = None,
+        alt_s88meeting_weekend_average_month4: Option<f64> = None,
+        alt_s88meeting_weekend_count_month5: Option<i64> = None,
+        alt_s88meeting_weekend_total_month5: Option<i64> = None,
+        alt_s88meeting_weekend_average_month5: Option<f64> = None,
+        alt_s88meeting_weekend_count_month6: Option<i64> = None,
+        alt_s88meeting_weekend_total_month6: Option<i64> = None,
+        alt_s88meeting_weekend_average_month6: Option<f64> = None,
+        routing_event_id: String = String::new(),
+    }
+}
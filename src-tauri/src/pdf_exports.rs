@@ -1,35 +1,9 @@
-use std::fs;
-use std::path::PathBuf;
-use tauri::{AppHandle, Manager};
-
-/// Get the appropriate export directory based on platform
-/// Reuses the logic from exports.rs for cross-platform compatibility
-fn get_export_directory(app: &AppHandle) -> Result<PathBuf, String> {
-    #[cfg(any(target_os = "ios", target_os = "android"))]
-    {
-        let doc_dir = app
-            .path()
-            .document_dir()
-            .map_err(|e| format!("Failed to get document directory: {}", e))?;
-
-        fs::create_dir_all(&doc_dir).map_err(|e| e.to_string())?;
-        Ok(doc_dir)
-    }
-    
-    #[cfg(not(any(target_os = "ios", target_os = "android")))]
-    {
-        let app_dir = app
-            .path()
-            .app_data_dir()
-            .map_err(|e| format!("Failed to get app data directory: {}", e))?;
-        
-        fs::create_dir_all(&app_dir).map_err(|e| e.to_string())?;
-        Ok(app_dir)
-    }
-}
+use tauri::AppHandle;
+use crate::exports::get_export_directory;
+use crate::files::write_atomic_with;
 
 /// Generate Meeting Schedule PDF using printpdf
-#[tauri::command]
+#[tauri::command(async)]
 pub fn generate_pdf_printpdf(app: AppHandle) -> Result<String, String> {
     use printpdf::*;
     
@@ -109,15 +83,15 @@ pub fn generate_pdf_printpdf(app: AppHandle) -> Result<String, String> {
     current_layer.use_text("Circuit Assistant Mobile Companion", 8.0, Mm(60.0), Mm(20.0), &font);
     
     // Save PDF
-    doc.save(&mut std::io::BufWriter::new(
-        std::fs::File::create(&file_path).map_err(|e| e.to_string())?
-    )).map_err(|e| e.to_string())?;
+    write_atomic_with(&file_path, |file| {
+        doc.save(&mut std::io::BufWriter::new(file)).map_err(|e| e.to_string())
+    })?;
     
     Ok(file_path.to_string_lossy().to_string())
 }
 
 /// Generate Territory Assignment PDF using lopdf
-#[tauri::command]
+#[tauri::command(async)]
 pub fn generate_pdf_lopdf(app: AppHandle) -> Result<String, String> {
     use lopdf::{Document, Object, Dictionary, Stream};
     use lopdf::content::{Content, Operation};
@@ -256,14 +230,16 @@ pub fn generate_pdf_lopdf(app: AppHandle) -> Result<String, String> {
     doc.compress();
     
     // Save PDF
-    doc.save(&file_path).map_err(|e| e.to_string())?;
+    write_atomic_with(&file_path, |file| {
+        doc.save_to(file).map(|_| ()).map_err(|e| e.to_string())
+    })?;
     
     Ok(file_path.to_string_lossy().to_string())
 }
 
 /// Generate Service Report PDF using genpdf
 /// Note: This implementation uses printpdf instead due to genpdf font complexity
-#[tauri::command]
+#[tauri::command(async)]
 pub fn generate_pdf_genpdf(app: AppHandle) -> Result<String, String> {
     use printpdf::*;
     
@@ -380,9 +356,9 @@ pub fn generate_pdf_genpdf(app: AppHandle) -> Result<String, String> {
     current_layer.use_text("Circuit Assistant Mobile Companion", 8.0, Mm(60.0), Mm(20.0), &font);
     
     // Save PDF
-    doc.save(&mut std::io::BufWriter::new(
-        std::fs::File::create(&file_path).map_err(|e| e.to_string())?
-    )).map_err(|e| e.to_string())?;
+    write_atomic_with(&file_path, |file| {
+        doc.save(&mut std::io::BufWriter::new(file)).map_err(|e| e.to_string())
+    })?;
     
     Ok(file_path.to_string_lossy().to_string())
 }
@@ -1,6 +1,6 @@
 use tauri::AppHandle;
 use crate::exports::get_export_directory;
-use std::fs;
+use crate::files::{commit_temp_file, temp_path_for, write_atomic};
 use base64::{Engine as _, engine::general_purpose};
 use serde::{Deserialize, Serialize};
 
@@ -49,7 +49,7 @@ pub struct GeneratePdfOxidizeRequest {
 
 /// Generate PDF from web content
 /// Receives PDF bytes (base64) from JavaScript library (html2pdf.js) and writes to file
-#[tauri::command]
+#[tauri::command(async)]
 pub fn generate_pdf_from_web_content(
     app: AppHandle,
     pdf_base64: String,
@@ -77,7 +77,7 @@ pub fn generate_pdf_from_web_content(
     println!("Decoded PDF bytes, size: {} bytes", pdf_bytes.len());
 
     // Write PDF bytes to file
-    fs::write(&file_path, &pdf_bytes)
+    write_atomic(&file_path, &pdf_bytes)
         .map_err(|e| format!("Failed to write PDF file: {} (tried to write {} bytes)", e, pdf_bytes.len()))?;
 
     println!("Successfully wrote PDF to: {}", file_path.display());
@@ -87,7 +87,7 @@ pub fn generate_pdf_from_web_content(
 
 /// Generate PDF using oxidize-pdf from list items data
 /// Creates a properly formatted PDF with table structure using oxidize-pdf crate
-#[tauri::command]
+#[tauri::command(async)]
 pub fn generate_pdf_oxidize(
     app: AppHandle,
     request: GeneratePdfOxidizeRequest,
@@ -388,9 +388,11 @@ pub fn generate_pdf_oxidize(
     // Add the last page
     doc.add_page(page);
     
-    // Save PDF
-    doc.save(&file_path)
+    // Save PDF to a temp file first so a failed save never leaves a truncated file behind
+    let temp_path = temp_path_for(&file_path);
+    doc.save(&temp_path)
         .map_err(|e| format!("Failed to save PDF: {}", e))?;
+    commit_temp_file(&temp_path, &file_path)?;
     
     println!("Successfully created PDF using oxidize-pdf at: {}", file_path.display());
     
@@ -0,0 +1,254 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, State};
+use crate::commands::{data_root, DbConnection};
+use crate::db;
+use crate::encryption::DatabaseStatus;
+use crate::exports::export_root;
+use crate::files::write_atomic;
+
+/// The profile that uses the app's top-level folders, where all data lived before profiles
+pub const DEFAULT_PROFILE_ID: &str = "default";
+
+const DEFAULT_PROFILE_NAME: &str = "My circuit";
+
+/// Folder inside each base directory that holds one subfolder per additional profile
+pub const PROFILES_DIR: &str = "profiles";
+
+/// Registry of profiles, kept next to the default profile's database
+const REGISTRY_FILE: &str = "profiles.json";
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Profile {
+    pub id: String,
+    pub name: String,
+    pub created_at: String,
+}
+
+/// All profiles and the id of the active one, as stored in `profiles.json`
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Profiles {
+    pub active: String,
+    pub profiles: Vec<Profile>,
+}
+
+impl Profiles {
+    fn find(&self, id: &str) -> Result<&Profile, String> {
+        self.profiles
+            .iter()
+            .find(|profile| profile.id == id)
+            .ok_or_else(|| format!("Unknown profile {}", id))
+    }
+
+    fn find_mut(&mut self, id: &str) -> Result<&mut Profile, String> {
+        self.profiles
+            .iter_mut()
+            .find(|profile| profile.id == id)
+            .ok_or_else(|| format!("Unknown profile {}", id))
+    }
+
+    /// Trim a new profile name and check that no other profile uses it
+    fn check_name(&self, name: &str, except_id: Option<&str>) -> Result<String, String> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err("Profile name cannot be empty".to_string());
+        }
+        let taken = self
+            .profiles
+            .iter()
+            .any(|profile| Some(profile.id.as_str()) != except_id && profile.name.to_lowercase() == name.to_lowercase());
+        if taken {
+            return Err(format!("A profile named {} already exists", name));
+        }
+        Ok(name.to_string())
+    }
+}
+
+/// The id of the active profile, read from `profiles.json` on first use and kept in memory,
+/// so finding a folder does not read the registry again.
+#[derive(Default)]
+pub struct ActiveProfile {
+    id: Mutex<Option<String>>,
+    /// Held while switching, so two switches do not interleave
+    switching: Mutex<()>,
+}
+
+fn active_state(app: &AppHandle) -> Result<State<'_, ActiveProfile>, String> {
+    app.try_state::<ActiveProfile>()
+        .ok_or_else(|| "Active profile is not available".to_string())
+}
+
+/// Id of the active profile
+fn active_id(app: &AppHandle) -> Result<String, String> {
+    let state = active_state(app)?;
+    let mut id = state.id.lock().map_err(|e| format!("Failed to lock active profile: {}", e))?;
+    if id.is_none() {
+        *id = Some(load(app)?.active);
+    }
+    Ok(id.clone().unwrap_or_default())
+}
+
+fn set_active_id(app: &AppHandle, new_id: &str) -> Result<(), String> {
+    let state = active_state(app)?;
+    let mut id = state.id.lock().map_err(|e| format!("Failed to lock active profile: {}", e))?;
+    *id = Some(new_id.to_string());
+    Ok(())
+}
+
+fn registry_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(db::db_root(app)?.join(REGISTRY_FILE))
+}
+
+/// Read the registry. Before the first profile is created there is no file,
+/// and the default profile is the only one.
+fn load(app: &AppHandle) -> Result<Profiles, String> {
+    let path = registry_path(app)?;
+    match fs::read_to_string(&path) {
+        Ok(contents) => serde_json::from_str(&contents)
+            .map_err(|e| format!("Failed to parse {}: {}", path.display(), e)),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Profiles {
+            active: DEFAULT_PROFILE_ID.to_string(),
+            profiles: vec![Profile {
+                id: DEFAULT_PROFILE_ID.to_string(),
+                name: DEFAULT_PROFILE_NAME.to_string(),
+                created_at: db::now_timestamp(),
+            }],
+        }),
+        Err(err) => Err(format!("Failed to read {}: {}", path.display(), err)),
+    }
+}
+
+fn save(app: &AppHandle, profiles: &Profiles) -> Result<(), String> {
+    let json = serde_json::to_vec_pretty(profiles)
+        .map_err(|e| format!("Failed to serialize profiles: {}", e))?;
+    write_atomic(&registry_path(app)?, &json)
+}
+
+/// The folder of a profile inside one of the base directories.
+/// The default profile uses the base directory itself.
+pub fn directory_in(base: &Path, id: &str) -> PathBuf {
+    if id == DEFAULT_PROFILE_ID {
+        base.to_path_buf()
+    } else {
+        base.join(PROFILES_DIR).join(id)
+    }
+}
+
+/// The active profile's folder inside `base`, created if it does not exist yet
+pub fn active_directory(app: &AppHandle, base: &Path) -> Result<PathBuf, String> {
+    let dir = directory_in(base, &active_id(app)?);
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    Ok(dir)
+}
+
+/// Close the open database and open the active profile's one.
+/// Leaves the connection empty (locked) when that database is encrypted.
+fn reopen_database(app: &AppHandle, db: &DbConnection) -> Result<DatabaseStatus, String> {
+    db::init_db(app, db)?;
+    let guard = db.lock().map_err(|e| format!("Failed to lock database: {}", e))?;
+    Ok(DatabaseStatus {
+        encrypted: db::is_encrypted(&db::get_db_path(app)?)?,
+        locked: guard.is_none(),
+    })
+}
+
+/// List all profiles and which one is active
+#[tauri::command]
+pub fn list_profiles(app: AppHandle) -> Result<Profiles, String> {
+    let mut profiles = load(&app)?;
+    profiles.active = active_id(&app)?;
+    Ok(profiles)
+}
+
+/// Add a profile with its own empty database and folders; it is not switched to
+#[tauri::command]
+pub fn create_profile(app: AppHandle, name: String) -> Result<Profile, String> {
+    let mut profiles = load(&app)?;
+    let profile = Profile {
+        id: db::new_record_id(),
+        name: profiles.check_name(&name, None)?,
+        created_at: db::now_timestamp(),
+    };
+    profiles.profiles.push(profile.clone());
+    save(&app, &profiles)?;
+    Ok(profile)
+}
+
+/// Make another profile the active one and open its database.
+/// Returns the new database's status; an encrypted one must be unlocked before use.
+#[tauri::command(async)]
+pub fn switch_profile(app: AppHandle, db: State<'_, DbConnection>, id: String) -> Result<DatabaseStatus, String> {
+    let active = active_state(&app)?;
+    let _switching = active.switching.lock().map_err(|e| format!("Failed to lock active profile: {}", e))?;
+    let mut profiles = load(&app)?;
+    profiles.find(&id)?;
+    let previous = active_id(&app)?;
+    if previous == id {
+        let guard = db.lock().map_err(|e| format!("Failed to lock database: {}", e))?;
+        return Ok(DatabaseStatus {
+            encrypted: db::is_encrypted(&db::get_db_path(&app)?)?,
+            locked: guard.is_none(),
+        });
+    }
+
+    // The new profile is only saved as active once its database is open
+    set_active_id(&app, &id)?;
+    profiles.active = id;
+    let switched = reopen_database(&app, &db)
+        .map_err(|e| format!("Failed to open profile: {}", e))
+        .and_then(|status| save(&app, &profiles).map(|_| status));
+    switched.or_else(|err| {
+        // Go back to the profile that was working
+        set_active_id(&app, &previous)?;
+        reopen_database(&app, &db).map_err(|e| format!("{}; reopening the previous profile failed too: {}", err, e))?;
+        Err(err)
+    })
+}
+
+#[tauri::command]
+pub fn rename_profile(app: AppHandle, id: String, name: String) -> Result<Profile, String> {
+    let mut profiles = load(&app)?;
+    let name = profiles.check_name(&name, Some(&id))?;
+    let profile = profiles.find_mut(&id)?;
+    profile.name = name;
+    let profile = profile.clone();
+    save(&app, &profiles)?;
+    Ok(profile)
+}
+
+/// Delete a profile together with its database, data files and exports.
+/// The active profile and the default profile cannot be deleted.
+#[tauri::command]
+pub fn delete_profile(app: AppHandle, id: String) -> Result<(), String> {
+    let mut profiles = load(&app)?;
+    profiles.find(&id)?;
+    if id == DEFAULT_PROFILE_ID {
+        return Err("The default profile cannot be deleted".to_string());
+    }
+    if id == active_id(&app)? {
+        return Err("Switch to another profile before deleting this one".to_string());
+    }
+    // Ids are generated UUIDs; anything else must not be turned into a path to delete
+    if !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        return Err(format!("Invalid profile id {}", id));
+    }
+
+    // Remove the files first, so a failure leaves the profile listed and the delete can be retried
+    let mut dirs: Vec<PathBuf> = Vec::new();
+    for base in [db::db_root(&app)?, data_root(&app)?, export_root(&app)?] {
+        let dir = directory_in(&base, &id);
+        if !dirs.contains(&dir) {
+            dirs.push(dir);
+        }
+    }
+    for dir in dirs.iter().filter(|dir| dir.exists()) {
+        fs::remove_dir_all(dir).map_err(|e| format!("Failed to delete {}: {}", dir.display(), e))?;
+    }
+
+    profiles.profiles.retain(|profile| profile.id != id);
+    save(&app, &profiles)
+}
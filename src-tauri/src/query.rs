@@ -0,0 +1,236 @@
+use regex::Regex;
+use rusqlite::{params_from_iter, types::Value as SqlValue, Connection};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::OnceLock;
+use tauri::State;
+use crate::commands::DbConnection;
+use crate::{relations, sandbox};
+
+/// A query over one model's records.
+/// All `filter` conditions must match (logical AND); use an `or` condition for alternatives.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelQuery {
+    #[serde(default)]
+    pub filter: Vec<Condition>,
+    #[serde(default)]
+    pub sort: Vec<SortKey>,
+    pub limit: Option<u32>,
+    #[serde(default)]
+    pub offset: u32,
+    /// Reference fields whose target records are embedded under `_expanded`
+    #[serde(default)]
+    pub expand: Vec<String>,
+}
+
+/// A single filter condition on a record field.
+/// Fields may address nested objects with dots, e.g. `licenseInfo.number`.
+/// Dates are compared as ISO-8601 strings, so `between` works for date ranges.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "op", rename_all = "camelCase")]
+pub enum Condition {
+    Eq { field: String, value: Value },
+    Ne { field: String, value: Value },
+    Gt { field: String, value: Value },
+    Gte { field: String, value: Value },
+    Lt { field: String, value: Value },
+    Lte { field: String, value: Value },
+    Between { field: String, from: Option<Value>, to: Option<Value> },
+    In { field: String, values: Vec<Value> },
+    /// Array field (e.g. `tags`) contains the value
+    Contains { field: String, value: Value },
+    IsNull { field: String },
+    NotNull { field: String },
+    Or { any: Vec<Condition> },
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SortKey {
+    pub field: String,
+    #[serde(default)]
+    pub descending: bool,
+}
+
+/// One page of query results plus the total number of matching records
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryResult {
+    pub records: Vec<Value>,
+    pub total: i64,
+}
+
+fn field_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"^[A-Za-z_][A-Za-z0-9_]*(\.[A-Za-z_][A-Za-z0-9_]*)*$").unwrap())
+}
+
+/// Turn a field name into a JSON path, rejecting anything that is not a plain identifier path
+fn json_path(field: &str) -> Result<SqlValue, String> {
+    if !field_pattern().is_match(field) {
+        return Err(format!("Invalid field name in query: {}", field));
+    }
+    Ok(SqlValue::Text(format!("$.{}", field)))
+}
+
+/// Convert a JSON scalar to a SQLite value the way `json_extract` would return it
+fn sql_value(value: &Value) -> Result<SqlValue, String> {
+    match value {
+        Value::Null => Ok(SqlValue::Null),
+        Value::Bool(b) => Ok(SqlValue::Integer(*b as i64)),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => Ok(SqlValue::Integer(i)),
+            None => Ok(SqlValue::Real(n.as_f64().unwrap_or_default())),
+        },
+        Value::String(s) => Ok(SqlValue::Text(s.clone())),
+        _ => Err("Query values must be strings, numbers, booleans or null".to_string()),
+    }
+}
+
+fn compare(field: &str, operator: &str, value: &Value, params: &mut Vec<SqlValue>) -> Result<String, String> {
+    params.push(json_path(field)?);
+    params.push(sql_value(value)?);
+    Ok(format!("json_extract(data, ?) {} ?", operator))
+}
+
+/// Build the SQL for one condition, pushing its parameters in order
+fn condition_sql(condition: &Condition, params: &mut Vec<SqlValue>) -> Result<String, String> {
+    match condition {
+        // Boolean flags that were never set count as false
+        Condition::Eq { field, value: Value::Bool(false) } => {
+            params.push(json_path(field)?);
+            Ok("IFNULL(json_extract(data, ?), 0) = 0".to_string())
+        }
+        Condition::Eq { field, value: Value::Null } | Condition::IsNull { field } => {
+            params.push(json_path(field)?);
+            Ok("json_extract(data, ?) IS NULL".to_string())
+        }
+        Condition::Ne { field, value: Value::Null } | Condition::NotNull { field } => {
+            params.push(json_path(field)?);
+            Ok("json_extract(data, ?) IS NOT NULL".to_string())
+        }
+        Condition::Eq { field, value } => compare(field, "=", value, params),
+        Condition::Ne { field, value } => {
+            params.push(json_path(field)?);
+            params.push(sql_value(value)?);
+            Ok("json_extract(data, ?) IS NOT ?".to_string())
+        }
+        Condition::Gt { field, value } => compare(field, ">", value, params),
+        Condition::Gte { field, value } => compare(field, ">=", value, params),
+        Condition::Lt { field, value } => compare(field, "<", value, params),
+        Condition::Lte { field, value } => compare(field, "<=", value, params),
+        Condition::Between { field, from, to } => {
+            let mut parts = Vec::new();
+            if let Some(from) = from {
+                parts.push(compare(field, ">=", from, params)?);
+            }
+            if let Some(to) = to {
+                parts.push(compare(field, "<=", to, params)?);
+            }
+            if parts.is_empty() {
+                return Err(format!("Range on {} needs a from or to value", field));
+            }
+            Ok(format!("({})", parts.join(" AND ")))
+        }
+        Condition::In { field, values } => {
+            if values.is_empty() {
+                return Ok("0".to_string());
+            }
+            params.push(json_path(field)?);
+            for value in values {
+                params.push(sql_value(value)?);
+            }
+            let placeholders = vec!["?"; values.len()].join(", ");
+            Ok(format!("json_extract(data, ?) IN ({})", placeholders))
+        }
+        Condition::Contains { field, value } => {
+            params.push(json_path(field)?);
+            params.push(sql_value(value)?);
+            Ok("EXISTS (SELECT 1 FROM json_each(data, ?) WHERE json_each.value = ?)".to_string())
+        }
+        Condition::Or { any } => {
+            if any.is_empty() {
+                return Ok("0".to_string());
+            }
+            let parts = any
+                .iter()
+                .map(|c| condition_sql(c, params))
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(format!("({})", parts.join(" OR ")))
+        }
+    }
+}
+
+/// Run a query against one model.
+/// Sorting puts missing values last and breaks ties by saved order.
+pub fn run_query(conn: &Connection, model_name: &str, query: &ModelQuery) -> Result<QueryResult, String> {
+    let mut where_params = vec![SqlValue::Text(model_name.to_string())];
+    let mut where_sql = "model_name = ?".to_string();
+    for condition in &query.filter {
+        where_sql.push_str(" AND ");
+        where_sql.push_str(&condition_sql(condition, &mut where_params)?);
+    }
+
+    let total: i64 = conn
+        .query_row(
+            &format!("SELECT COUNT(*) FROM model_records WHERE {}", where_sql),
+            params_from_iter(where_params.iter()),
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("Failed to count records: {}", e))?;
+
+    let mut params = where_params;
+    let mut order_by = Vec::new();
+    for key in &query.sort {
+        let direction = if key.descending { "DESC" } else { "ASC" };
+        params.push(json_path(&key.field)?);
+        params.push(json_path(&key.field)?);
+        order_by.push(format!(
+            "json_extract(data, ?) IS NULL, json_extract(data, ?) COLLATE NOCASE {}",
+            direction
+        ));
+    }
+    order_by.push("position".to_string());
+
+    // SQLite treats a negative LIMIT as "no limit"
+    params.push(SqlValue::Integer(query.limit.map(i64::from).unwrap_or(-1)));
+    params.push(SqlValue::Integer(i64::from(query.offset)));
+
+    let sql = format!(
+        "SELECT data FROM model_records WHERE {} ORDER BY {} LIMIT ? OFFSET ?",
+        where_sql,
+        order_by.join(", ")
+    );
+    let mut stmt = conn
+        .prepare(&sql)
+        .map_err(|e| format!("Failed to prepare query: {}", e))?;
+    let rows = stmt
+        .query_map(params_from_iter(params.iter()), |row| row.get::<_, String>(0))
+        .map_err(|e| format!("Failed to query records: {}", e))?;
+
+    let mut records = Vec::new();
+    for row in rows {
+        let data = row.map_err(|e| format!("Failed to read record: {}", e))?;
+        records.push(
+            serde_json::from_str(&data).map_err(|e| format!("Failed to parse stored record: {}", e))?,
+        );
+    }
+
+    if !query.expand.is_empty() {
+        relations::expand_records(conn, model_name, &mut records, &query.expand)?;
+    }
+
+    Ok(QueryResult { records, total })
+}
+
+/// Filter, sort and page through a model's records
+#[tauri::command]
+pub async fn query_model_data(
+    db: State<'_, DbConnection>,
+    model_name: String,
+    query: ModelQuery,
+) -> Result<QueryResult, String> {
+    sandbox::model_name(&model_name)?;
+    db.read(move |conn| run_query(conn, &model_name, &query)).await
+}
@@ -0,0 +1,736 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, SecondsFormat, TimeZone, Utc, Weekday};
+use serde::Serialize;
+use serde_json::Value;
+use tauri::State;
+use crate::commands::DbConnection;
+use crate::ics_exports::{self, EventSource, EventTime, IcsExportOptions};
+use crate::time_zones::Zone;
+
+/// Periods in a row without an occurrence after which a rule is taken to have no more,
+/// e.g. `FREQ=YEARLY;BYMONTH=2;BYMONTHDAY=30`
+const MAX_EMPTY_PERIODS: u32 = 1000;
+
+/// How often a rule repeats
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+/// A weekday in BYDAY, with an optional position: `MO`, `2SU` (second Sunday), `-1FR` (last Friday)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WeekdayNum {
+    pub ordinal: Option<i32>,
+    pub weekday: Weekday,
+}
+
+/// Last possible start of a rule (UNTIL): a wall-clock time in the event's time zone, or an instant
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Until {
+    Local(NaiveDateTime),
+    Utc(DateTime<Utc>),
+}
+
+impl Until {
+    /// The wall-clock time in the zone of the event's times
+    pub fn local(self, zone: Zone) -> NaiveDateTime {
+        match self {
+            Until::Local(time) => time,
+            Until::Utc(time) => zone.wall_clock(time),
+        }
+    }
+}
+
+/// A recurrence rule (RFC 5545 RRULE), limited to the parts calendars use for meetings and events
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rule {
+    pub frequency: Frequency,
+    pub interval: u32,
+    pub count: Option<u32>,
+    /// Last possible start (a date means the end of that day)
+    pub until: Option<Until>,
+    pub by_day: Vec<WeekdayNum>,
+    pub by_month_day: Vec<i32>,
+    pub by_month: Vec<u32>,
+    pub by_set_pos: Vec<i32>,
+    pub week_start: Weekday,
+}
+
+pub fn parse_weekday(text: &str) -> Option<Weekday> {
+    Some(match text {
+        "MO" => Weekday::Mon,
+        "TU" => Weekday::Tue,
+        "WE" => Weekday::Wed,
+        "TH" => Weekday::Thu,
+        "FR" => Weekday::Fri,
+        "SA" => Weekday::Sat,
+        "SU" => Weekday::Sun,
+        _ => return None,
+    })
+}
+
+fn parse_weekday_num(text: &str) -> Option<WeekdayNum> {
+    let split = text.len().checked_sub(2)?;
+    let weekday = parse_weekday(text.get(split..)?)?;
+    let ordinal = match &text[..split] {
+        "" => None,
+        number => Some(number.trim_start_matches('+').parse().ok().filter(|n: &i32| (1..=53).contains(&n.abs()))?),
+    };
+    Some(WeekdayNum { ordinal, weekday })
+}
+
+/// Parse an UNTIL value: a date, a floating date-time, or a UTC date-time
+fn parse_until(text: &str) -> Option<Until> {
+    if let Ok(date) = NaiveDate::parse_from_str(text, "%Y%m%d") {
+        return date.and_hms_opt(23, 59, 59).map(Until::Local);
+    }
+    let time = NaiveDateTime::parse_from_str(text.trim_end_matches('Z'), "%Y%m%dT%H%M%S").ok()?;
+    if text.ends_with('Z') {
+        return Some(Until::Utc(Utc.from_utc_datetime(&time)));
+    }
+    Some(Until::Local(time))
+}
+
+fn parse_list<T>(value: &str, name: &str, parse: impl Fn(&str) -> Option<T>) -> Result<Vec<T>, String> {
+    value
+        .split(',')
+        .map(|item| parse(item.trim()).ok_or_else(|| format!("Invalid {} value: {}", name, item)))
+        .collect()
+}
+
+impl Rule {
+    /// Parse the value of an RRULE property, e.g. `FREQ=MONTHLY;BYDAY=2TU;COUNT=6`
+    pub fn parse(text: &str) -> Result<Rule, String> {
+        let mut rule = Rule::every(Frequency::Weekly);
+        let mut frequency = None;
+        for part in text.trim().split(';').filter(|part| !part.is_empty()) {
+            let (name, value) = part
+                .split_once('=')
+                .ok_or_else(|| format!("Invalid recurrence rule part: {}", part))?;
+            let value = value.trim();
+            match name.trim().to_ascii_uppercase().as_str() {
+                "FREQ" => {
+                    frequency = Some(match value.to_ascii_uppercase().as_str() {
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        "MONTHLY" => Frequency::Monthly,
+                        "YEARLY" => Frequency::Yearly,
+                        other => return Err(format!("Unsupported recurrence frequency: {}", other)),
+                    })
+                }
+                "INTERVAL" => {
+                    rule.interval = value
+                        .parse()
+                        .ok()
+                        .filter(|interval| *interval > 0)
+                        .ok_or_else(|| format!("Invalid INTERVAL value: {}", value))?
+                }
+                "COUNT" => rule.count = Some(value.parse().map_err(|_| format!("Invalid COUNT value: {}", value))?),
+                "UNTIL" => rule.until = Some(parse_until(value).ok_or_else(|| format!("Invalid UNTIL value: {}", value))?),
+                "BYDAY" => rule.by_day = parse_list(&value.to_ascii_uppercase(), "BYDAY", parse_weekday_num)?,
+                "BYMONTHDAY" => {
+                    rule.by_month_day = parse_list(value, "BYMONTHDAY", |item| {
+                        item.parse().ok().filter(|day: &i32| (1..=31).contains(&day.abs()))
+                    })?
+                }
+                "BYMONTH" => {
+                    rule.by_month = parse_list(value, "BYMONTH", |item| item.parse().ok().filter(|month| (1..=12).contains(month)))?
+                }
+                "BYSETPOS" => {
+                    rule.by_set_pos = parse_list(value, "BYSETPOS", |item| {
+                        item.parse().ok().filter(|pos: &i32| (1..=366).contains(&pos.abs()))
+                    })?
+                }
+                "WKST" => {
+                    rule.week_start = parse_weekday(&value.to_ascii_uppercase())
+                        .ok_or_else(|| format!("Invalid WKST value: {}", value))?
+                }
+                other => return Err(format!("Unsupported recurrence rule part: {}", other)),
+            }
+        }
+        rule.frequency = frequency.ok_or_else(|| "Recurrence rule has no FREQ".to_string())?;
+        if rule.count.is_some() && rule.until.is_some() {
+            return Err("Recurrence rule has both COUNT and UNTIL".to_string());
+        }
+        Ok(rule)
+    }
+}
+
+/// Read a stored meeting day: a weekday name or abbreviation in English, German, Spanish or French,
+/// a number as JavaScript counts them (0 is Sunday, 6 Saturday; 7 is also Sunday),
+/// or an object with one of those as its name, label or value
+pub fn weekday_value(value: &Value) -> Option<Weekday> {
+    match value {
+        Value::Number(number) => Some(match number.as_u64()? {
+            0 | 7 => Weekday::Sun,
+            day @ 1..=6 => Weekday::try_from(day as u8 - 1).ok()?,
+            _ => return None,
+        }),
+        Value::String(text) => {
+            let text = text.trim().to_lowercase();
+            if let Ok(number) = text.parse::<u64>() {
+                return weekday_value(&Value::from(number));
+            }
+            const NAMES: [(Weekday, &[&str]); 7] = [
+                (Weekday::Mon, &["monday", "montag", "lunes", "lundi"]),
+                (Weekday::Tue, &["tuesday", "dienstag", "martes", "mardi"]),
+                (Weekday::Wed, &["wednesday", "mittwoch", "miércoles", "miercoles", "mercredi"]),
+                (Weekday::Thu, &["thursday", "donnerstag", "jueves", "jeudi"]),
+                (Weekday::Fri, &["friday", "freitag", "viernes", "vendredi"]),
+                (Weekday::Sat, &["saturday", "samstag", "sábado", "sabado", "samedi"]),
+                (Weekday::Sun, &["sunday", "sonntag", "domingo", "dimanche"]),
+            ];
+            let text = text.trim_end_matches('.');
+            // Abbreviations need at least two letters: `sa` is Saturday, `s` could be Sunday too
+            NAMES
+                .iter()
+                .find(|(_, names)| text.chars().count() >= 2 && names.iter().any(|name| name.starts_with(text)))
+                .map(|(weekday, _)| *weekday)
+        }
+        Value::Object(object) => ["name", "label", "value"].iter().find_map(|key| weekday_value(object.get(*key)?)),
+        _ => None,
+    }
+}
+
+/// The first `weekday` on or after `date`
+pub fn next_weekday(date: NaiveDate, weekday: Weekday) -> NaiveDate {
+    let ahead = (weekday.num_days_from_monday() + 7 - date.weekday().num_days_from_monday()) % 7;
+    date + Duration::days(ahead.into())
+}
+
+/// A day of a month; negative days count from the end (-1 is the last day)
+fn month_day(year: i32, month: u32, day: i32) -> Option<NaiveDate> {
+    if day > 0 {
+        return NaiveDate::from_ymd_opt(year, month, day as u32);
+    }
+    let first = NaiveDate::from_ymd_opt(year, month, 1)?;
+    let days = first.checked_add_months(chrono::Months::new(1))?.signed_duration_since(first).num_days();
+    let day = days + 1 + i64::from(day);
+    (day >= 1).then(|| first + Duration::days(day - 1))
+}
+
+/// A month counted from January of year 0, so months can be stepped through by adding
+fn month_from_index(index: i64) -> Option<(i32, u32)> {
+    Some((i32::try_from(index.div_euclid(12)).ok()?, index.rem_euclid(12) as u32 + 1))
+}
+
+impl Rule {
+    fn weekday_matches(&self, date: NaiveDate) -> bool {
+        self.by_day.is_empty() || self.by_day.iter().any(|day| day.weekday == date.weekday())
+    }
+
+    fn month_day_matches(&self, date: NaiveDate) -> bool {
+        self.by_month_day.is_empty()
+            || self
+                .by_month_day
+                .iter()
+                .any(|day| month_day(date.year(), date.month(), *day) == Some(date))
+    }
+
+    fn month_matches(&self, date: NaiveDate) -> bool {
+        self.by_month.is_empty() || self.by_month.contains(&date.month())
+    }
+
+    /// Days of a month the rule picks, before BYSETPOS
+    fn days_of_month(&self, year: i32, month: u32, first: NaiveDate) -> Vec<NaiveDate> {
+        if !self.by_day.is_empty() {
+            let mut days = Vec::new();
+            for day in &self.by_day {
+                match day.ordinal {
+                    Some(ordinal) => days.extend(nth_weekday(year, month, day.weekday, ordinal)),
+                    None => days.extend((1..=5).filter_map(|n| nth_weekday(year, month, day.weekday, n))),
+                }
+            }
+            days.retain(|date| self.month_day_matches(*date));
+            return days;
+        }
+        if !self.by_month_day.is_empty() {
+            return self.by_month_day.iter().filter_map(|day| month_day(year, month, *day)).collect();
+        }
+        NaiveDate::from_ymd_opt(year, month, first.day()).into_iter().collect()
+    }
+
+    /// Days the rule picks in the `period`-th period (day, week, month or year) after the one of `first`;
+    /// none when the period is past the dates chrono can represent
+    fn days_of_period(&self, first: NaiveDate, period: i64) -> Option<(NaiveDate, Vec<NaiveDate>)> {
+        let (start, mut days) = match self.frequency {
+            Frequency::Daily => {
+                let day = first.checked_add_signed(Duration::try_days(period)?)?;
+                let matches = self.weekday_matches(day) && self.month_day_matches(day) && self.month_matches(day);
+                (day, if matches { vec![day] } else { Vec::new() })
+            }
+            Frequency::Weekly => {
+                let back = (first.weekday().num_days_from_monday() + 7 - self.week_start.num_days_from_monday()) % 7;
+                let week = (first - Duration::days(back.into())).checked_add_signed(Duration::try_weeks(period)?)?;
+                let days = (0..7)
+                    .filter_map(|offset| week.checked_add_signed(Duration::days(offset)))
+                    .filter(|day| match self.by_day.is_empty() {
+                        true => day.weekday() == first.weekday(),
+                        false => self.weekday_matches(*day),
+                    })
+                    .filter(|day| self.month_matches(*day))
+                    .collect();
+                (week, days)
+            }
+            Frequency::Monthly => {
+                let (year, month) = month_from_index(i64::from(first.year()) * 12 + i64::from(first.month0()) + period)?;
+                let start = NaiveDate::from_ymd_opt(year, month, 1)?;
+                let days = match self.month_matches(start) {
+                    true => self.days_of_month(year, month, first),
+                    false => Vec::new(),
+                };
+                (start, days)
+            }
+            Frequency::Yearly => {
+                let year = first.year().checked_add(i32::try_from(period).ok()?)?;
+                let start = NaiveDate::from_ymd_opt(year, 1, 1)?;
+                let days = if self.by_month.is_empty() && !self.by_day.is_empty() && self.by_month_day.is_empty() {
+                    // `FREQ=YEARLY;BYDAY=20MO`: positions count through the whole year
+                    let all: Vec<NaiveDate> = start.iter_days().take_while(|day| day.year() == year).collect();
+                    let mut days = Vec::new();
+                    for day in &self.by_day {
+                        let matching: Vec<NaiveDate> = all.iter().copied().filter(|date| date.weekday() == day.weekday).collect();
+                        match day.ordinal {
+                            Some(ordinal) => days.extend(pick(&matching, ordinal)),
+                            None => days.extend(matching),
+                        }
+                    }
+                    days
+                } else {
+                    let months = if self.by_month.is_empty() { vec![first.month()] } else { self.by_month.clone() };
+                    months.iter().flat_map(|month| self.days_of_month(year, *month, first)).collect()
+                };
+                (start, days)
+            }
+        };
+        days.sort();
+        days.dedup();
+        if !self.by_set_pos.is_empty() {
+            let all = std::mem::take(&mut days);
+            days = self.by_set_pos.iter().filter_map(|pos| pick(&all, *pos)).collect();
+            days.sort();
+            days.dedup();
+        }
+        Some((start, days))
+    }
+
+    /// Start times of the occurrences from `first` (which is always the first one) through `to`,
+    /// as wall-clock times in `zone`
+    pub fn starts(&self, first: NaiveDateTime, to: NaiveDateTime, zone: Zone) -> Vec<NaiveDateTime> {
+        if self.count == Some(0) || first > to {
+            return Vec::new();
+        }
+        let last = self.until.map_or(to, |until| until.local(zone).min(to));
+        let mut starts = vec![first];
+        let mut empty = 0;
+        let mut period = 0;
+        while let Some((period_start, days)) = self.days_of_period(first.date(), period) {
+            if period_start > last.date() {
+                break;
+            }
+            let times: Vec<NaiveDateTime> = days
+                .into_iter()
+                .map(|day| day.and_time(first.time()))
+                .filter(|time| *time > first && *time <= last)
+                .collect();
+            empty = if times.is_empty() { empty + 1 } else { 0 };
+            if empty > MAX_EMPTY_PERIODS {
+                break;
+            }
+            for time in times {
+                if self.count.is_some_and(|count| starts.len() >= count as usize) {
+                    return starts;
+                }
+                starts.push(time);
+            }
+            period += i64::from(self.interval);
+        }
+        starts
+    }
+
+    /// The rule as an RRULE value; `until` writes the UNTIL time
+    pub fn to_ics(&self, until: impl Fn(Until) -> String) -> String {
+        let frequency = match self.frequency {
+            Frequency::Daily => "DAILY",
+            Frequency::Weekly => "WEEKLY",
+            Frequency::Monthly => "MONTHLY",
+            Frequency::Yearly => "YEARLY",
+        };
+        let join = |items: Vec<String>| items.join(",");
+        let mut parts = vec![format!("FREQ={}", frequency)];
+        if self.interval > 1 {
+            parts.push(format!("INTERVAL={}", self.interval));
+        }
+        if let Some(count) = self.count {
+            parts.push(format!("COUNT={}", count));
+        }
+        if let Some(time) = self.until {
+            parts.push(format!("UNTIL={}", until(time)));
+        }
+        if !self.by_day.is_empty() {
+            parts.push(format!(
+                "BYDAY={}",
+                join(self
+                    .by_day
+                    .iter()
+                    .map(|day| format!("{}{}", day.ordinal.map(|n| n.to_string()).unwrap_or_default(), weekday_code(day.weekday)))
+                    .collect())
+            ));
+        }
+        if !self.by_month_day.is_empty() {
+            parts.push(format!("BYMONTHDAY={}", join(self.by_month_day.iter().map(i32::to_string).collect())));
+        }
+        if !self.by_month.is_empty() {
+            parts.push(format!("BYMONTH={}", join(self.by_month.iter().map(u32::to_string).collect())));
+        }
+        if !self.by_set_pos.is_empty() {
+            parts.push(format!("BYSETPOS={}", join(self.by_set_pos.iter().map(i32::to_string).collect())));
+        }
+        if self.week_start != Weekday::Mon {
+            parts.push(format!("WKST={}", weekday_code(self.week_start)));
+        }
+        parts.join(";")
+    }
+
+    /// Every day, week, month or year, with nothing else set
+    pub fn every(frequency: Frequency) -> Rule {
+        Rule {
+            frequency,
+            interval: 1,
+            count: None,
+            until: None,
+            by_day: Vec::new(),
+            by_month_day: Vec::new(),
+            by_month: Vec::new(),
+            by_set_pos: Vec::new(),
+            week_start: Weekday::Mon,
+        }
+    }
+
+    /// Every week on a weekday
+    pub fn weekly(weekday: Weekday) -> Rule {
+        Rule { by_day: vec![WeekdayNum { ordinal: None, weekday }], ..Rule::every(Frequency::Weekly) }
+    }
+}
+
+/// The `position`-th item (1-based; negative counts from the end)
+fn pick<T: Copy>(items: &[T], position: i32) -> Option<T> {
+    let index = if position > 0 {
+        usize::try_from(position - 1).ok()?
+    } else {
+        items.len().checked_sub(usize::try_from(-position).ok()?)?
+    };
+    items.get(index).copied()
+}
+
+pub fn weekday_code(weekday: Weekday) -> &'static str {
+    match weekday {
+        Weekday::Mon => "MO",
+        Weekday::Tue => "TU",
+        Weekday::Wed => "WE",
+        Weekday::Thu => "TH",
+        Weekday::Fri => "FR",
+        Weekday::Sat => "SA",
+        Weekday::Sun => "SU",
+    }
+}
+
+/// How an event repeats: a rule, extra dates (RDATE), and occurrences taken out (EXDATE)
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Series {
+    pub rule: Option<Rule>,
+    pub dates: Vec<EventTime>,
+    pub exdates: Vec<EventTime>,
+}
+
+impl Series {
+    /// Starts of the occurrences of an event starting at `first` that fall on the days `from` through `to`,
+    /// all in the event's time zone. An exception date without a time takes out every occurrence of that day.
+    pub fn occurrences(&self, first: EventTime, from: NaiveDate, to: NaiveDate, zone: Zone) -> Vec<EventTime> {
+        let Some(to_time) = to.and_hms_opt(23, 59, 59) else {
+            return Vec::new();
+        };
+        let mut starts = match &self.rule {
+            Some(rule) => rule.starts(first.sort_key(), to_time, zone),
+            None => vec![first.sort_key()],
+        };
+        starts.extend(self.dates.iter().map(|date| match (first, date) {
+            // An RDATE without a time keeps the time of the first occurrence
+            (EventTime::Local(first), EventTime::Date(date)) => date.and_time(first.time()),
+            (_, date) => date.sort_key(),
+        }));
+        let mut occurrences: Vec<EventTime> = starts
+            .into_iter()
+            .map(|start| match first {
+                EventTime::Date(_) => EventTime::Date(start.date()),
+                EventTime::Local(_) => EventTime::Local(start),
+            })
+            .filter(|occurrence| (from..=to).contains(&occurrence.date()))
+            .filter(|occurrence| {
+                !self.exdates.iter().any(|exdate| match exdate {
+                    EventTime::Date(date) => occurrence.date() == *date,
+                    time => occurrence == time,
+                })
+            })
+            .collect();
+        occurrences.sort_by_key(|occurrence| occurrence.sort_key());
+        occurrences.dedup();
+        occurrences
+    }
+}
+
+/// How occurrences are identified: `YYYY-MM-DD`, or `YYYY-MM-DDTHH:MM` when timed
+pub fn occurrence_id(time: EventTime) -> String {
+    match time {
+        EventTime::Date(date) => date.format("%Y-%m-%d").to_string(),
+        EventTime::Local(time) => time.format("%Y-%m-%dT%H:%M").to_string(),
+    }
+}
+
+pub fn parse_occurrence_id(text: &str) -> Option<EventTime> {
+    let text = text.trim();
+    if let Ok(date) = NaiveDate::parse_from_str(text, "%Y-%m-%d") {
+        return Some(EventTime::Date(date));
+    }
+    NaiveDateTime::parse_from_str(text, "%Y-%m-%dT%H:%M")
+        .or_else(|_| NaiveDateTime::parse_from_str(text, "%Y-%m-%dT%H:%M:%S"))
+        .ok()
+        .map(EventTime::Local)
+}
+
+/// One occurrence of an event, as the calendar view shows it
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Occurrence {
+    pub uid: String,
+    /// Identifies the occurrence within its recurring event (see `occurrence_id`); missing for single events
+    pub recurrence_id: Option<String>,
+    pub source: EventSource,
+    pub title: String,
+    /// First day on this device
+    pub date: String,
+    /// `HH:MM` on this device; missing for all-day events
+    pub time: Option<String>,
+    /// Last day, when the occurrence ends on a later day than it starts
+    pub end_date: Option<String>,
+    pub end_time: Option<String>,
+    pub all_day: bool,
+    /// Start and end as UTC instants (RFC 3339); missing for all-day events
+    pub start: Option<String>,
+    pub end: Option<String>,
+    /// IANA time zone the event's times are kept in; missing when they are in the device's
+    pub time_zone: Option<String>,
+    pub location: Option<String>,
+    pub description: Option<String>,
+    pub categories: Vec<String>,
+}
+
+/// Expand the events of the chosen sources into their occurrences from `from` through `to`
+pub fn list(conn: &rusqlite::Connection, options: &IcsExportOptions) -> Result<Vec<Occurrence>, String> {
+    let limit = |text: &Option<String>, name: &str| -> Result<NaiveDate, String> {
+        let text = text.as_deref().ok_or_else(|| format!("A {} date is required", name))?;
+        NaiveDate::parse_from_str(text.trim(), "%Y-%m-%d").map_err(|_| format!("Invalid {} date: {}", name, text))
+    };
+    let (from, to) = (limit(&options.from, "from")?, limit(&options.to, "to")?);
+
+    // In another time zone an occurrence may fall on the day before or after on this device
+    let margin = Duration::days(1);
+    let (Some(earliest), Some(latest)) = (from.checked_sub_signed(margin), to.checked_add_signed(margin)) else {
+        return Err(format!("Dates out of range: {} to {}", from, to));
+    };
+    let mut occurrences = Vec::new();
+    for event in ics_exports::collect_events(conn, options, margin)? {
+        let zone = event.zone();
+        let length = event.end.sort_key() - event.start.sort_key();
+        // Also an occurrence that started before `from` but is still going on
+        let starts = match &event.series {
+            Some(series) => {
+                let since = earliest.checked_sub_signed(length.max(Duration::zero())).unwrap_or(NaiveDate::MIN);
+                series.occurrences(event.start, since, latest, zone)
+            }
+            None => vec![event.start],
+        };
+        for occurrence in starts {
+            let instant = |time: EventTime| match time {
+                EventTime::Local(time) => Some(zone.to_utc(time)),
+                EventTime::Date(_) => None,
+            };
+            let end = match occurrence {
+                EventTime::Date(date) => date.checked_add_signed(length).map(EventTime::Date),
+                EventTime::Local(time) => time.checked_add_signed(length).map(EventTime::Local),
+            };
+            let Some(end) = end else { continue };
+            let (start_instant, end_instant) = (instant(occurrence), instant(end));
+            // Timed occurrences are shown at the device's time
+            let on_device = |time: EventTime, instant: Option<DateTime<Utc>>| match instant {
+                Some(instant) => EventTime::Local(Zone::Device.wall_clock(instant)),
+                None => time,
+            };
+            let (start, end) = (on_device(occurrence, start_instant), on_device(end, end_instant));
+            let last_day = match end {
+                EventTime::Date(end) => end.pred_opt().unwrap_or(end),
+                EventTime::Local(end) => end.date(),
+            };
+            if last_day < from || start.date() > to {
+                continue;
+            }
+            let time_text = |time: EventTime| match time {
+                EventTime::Local(time) => Some(time.format("%H:%M").to_string()),
+                EventTime::Date(_) => None,
+            };
+            occurrences.push(Occurrence {
+                uid: event.uid.clone(),
+                recurrence_id: event
+                    .recurrence_id
+                    .or(event.series.as_ref().map(|_| occurrence))
+                    .map(occurrence_id),
+                source: event.source,
+                title: event.summary.clone(),
+                date: start.date().format("%Y-%m-%d").to_string(),
+                time: time_text(start),
+                end_date: Some(last_day).filter(|last| *last > start.date()).map(|last| last.format("%Y-%m-%d").to_string()),
+                end_time: time_text(end),
+                all_day: matches!(start, EventTime::Date(_)),
+                start: start_instant.map(|instant| instant.to_rfc3339_opts(SecondsFormat::Secs, true)),
+                end: end_instant.map(|instant| instant.to_rfc3339_opts(SecondsFormat::Secs, true)),
+                time_zone: event.time_zone.map(|zone| zone.name().to_string()),
+                location: event.location.clone(),
+                description: event.description.clone(),
+                categories: event.categories.clone(),
+            });
+        }
+    }
+    occurrences.sort_by(|a, b| (&a.date, !a.all_day, &a.time).cmp(&(&b.date, !b.all_day, &b.time)));
+    Ok(occurrences)
+}
+
+/// List the occurrences of stored and passed-in events from `options.from` through `options.to`
+/// (both required), with recurring events such as congregation meetings expanded.
+/// Takes the same options as `export_ics`.
+#[tauri::command]
+pub async fn list_occurrences(db: State<'_, DbConnection>, options: IcsExportOptions) -> Result<Vec<Occurrence>, String> {
+    db.read(move |conn| list(conn, &options)).await
+}
+
+/// The `ordinal`-th `weekday` of a month; negative ordinals count from the end (-1 is the last)
+pub fn nth_weekday(year: i32, month: u32, weekday: Weekday, ordinal: i32) -> Option<NaiveDate> {
+    if ordinal > 0 {
+        return NaiveDate::from_weekday_of_month_opt(year, month, weekday, u8::try_from(ordinal).ok()?);
+    }
+    let next_month = if month == 12 {
+        NaiveDate::from_ymd_opt(year + 1, 1, 1)?
+    } else {
+        NaiveDate::from_ymd_opt(year, month + 1, 1)?
+    };
+    let last = next_month.pred_opt()?;
+    let back = (last.weekday().num_days_from_monday() + 7 - weekday.num_days_from_monday()) % 7;
+    let date = last - chrono::Duration::days(i64::from(back) + 7 * i64::from(-ordinal - 1));
+    Some(date).filter(|date| date.month() == month)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+
+    fn options(from: &str, to: &str) -> IcsExportOptions {
+        IcsExportOptions { from: Some(from.to_string()), to: Some(to.to_string()), ..IcsExportOptions::default() }
+    }
+
+    #[test]
+    fn ranges_at_the_ends_of_the_calendar_are_refused() {
+        let conn = db::test_connection();
+        for (from, to) in [(NaiveDate::MIN, NaiveDate::MIN), (NaiveDate::MAX, NaiveDate::MAX)] {
+            let options = options(&from.format("%Y-%m-%d").to_string(), &to.format("%Y-%m-%d").to_string());
+            assert!(list(&conn, &options).is_err());
+        }
+        assert!(list(&conn, &options("2026-01-01", "2026-01-31")).unwrap().is_empty());
+    }
+
+    fn time(text: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    fn date(text: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(text, "%Y-%m-%d").unwrap()
+    }
+
+    /// The days of the starts of a rule from `first` through the end of 2030
+    fn days(rule: &str, first: &str) -> Vec<String> {
+        Rule::parse(rule)
+            .unwrap()
+            .starts(time(first), time("2030-12-31 23:59"), Zone::Device)
+            .iter()
+            .map(|start| start.format("%Y-%m-%d").to_string())
+            .collect()
+    }
+
+    #[test]
+    fn rules_pick_their_days() {
+        assert_eq!(days("FREQ=MONTHLY;BYDAY=2TU;COUNT=4", "2026-01-13 19:00"), ["2026-01-13", "2026-02-10", "2026-03-10", "2026-04-14"]);
+        assert_eq!(days("FREQ=MONTHLY;BYDAY=-1SU;COUNT=3", "2026-01-25 10:00"), ["2026-01-25", "2026-02-22", "2026-03-29"]);
+        assert_eq!(
+            days("FREQ=WEEKLY;INTERVAL=2;BYDAY=TU,TH;UNTIL=20260122", "2026-01-06 19:00"),
+            ["2026-01-06", "2026-01-08", "2026-01-20", "2026-01-22"]
+        );
+        assert_eq!(
+            days("FREQ=MONTHLY;BYDAY=MO,TU,WE,TH,FR;BYSETPOS=-1;COUNT=3", "2026-01-30 09:00"),
+            ["2026-01-30", "2026-02-27", "2026-03-31"]
+        );
+        assert_eq!(days("FREQ=YEARLY;BYMONTH=3;BYDAY=SU;BYSETPOS=2,-1;COUNT=4", "2026-03-08 10:00"), ["2026-03-08", "2026-03-29", "2027-03-14", "2027-03-28"]);
+        // Months without a 31st are left out, and a day that never comes ends the rule
+        assert_eq!(days("FREQ=MONTHLY;BYMONTHDAY=31;COUNT=3", "2026-01-31 09:00"), ["2026-01-31", "2026-03-31", "2026-05-31"]);
+        assert_eq!(days("FREQ=YEARLY;BYMONTH=2;BYMONTHDAY=30", "2026-01-01 09:00"), ["2026-01-01"]);
+        assert!(days("FREQ=DAILY;COUNT=0", "2026-01-01 09:00").is_empty());
+    }
+
+    #[test]
+    fn until_in_utc_is_read_in_the_zone_of_the_event() {
+        let berlin = Zone::Named(crate::time_zones::parse("Europe/Berlin").unwrap());
+        // 18:00 UTC is 19:00 in Berlin in winter, so the last Tuesday is still included
+        let rule = Rule::parse("FREQ=WEEKLY;BYDAY=TU;UNTIL=20260120T180000Z").unwrap();
+        let starts = rule.starts(time("2026-01-06 19:00"), time("2026-12-31 00:00"), berlin);
+        assert_eq!(starts.last(), Some(&time("2026-01-20 19:00")));
+        assert_eq!(starts.len(), 3);
+        let rule = Rule::parse("FREQ=WEEKLY;BYDAY=TU;UNTIL=20260120T175959Z").unwrap();
+        assert_eq!(rule.starts(time("2026-01-06 19:00"), time("2026-12-31 00:00"), berlin).len(), 2);
+    }
+
+    #[test]
+    fn rules_are_written_back_as_read() {
+        let rule = Rule::parse("FREQ=weekly;BYDAY=-1SU,2MO;WKST=SU;INTERVAL=3;COUNT=4").unwrap();
+        assert_eq!(rule.to_ics(|_| String::new()), "FREQ=WEEKLY;INTERVAL=3;COUNT=4;BYDAY=-1SU,2MO;WKST=SU");
+        let rule = Rule::parse("FREQ=MONTHLY;BYDAY=MO,FR;BYSETPOS=1,-1;UNTIL=20261231").unwrap();
+        assert_eq!(rule.to_ics(|_| "20261231".to_string()), "FREQ=MONTHLY;UNTIL=20261231;BYDAY=MO,FR;BYSETPOS=1,-1");
+        assert!(Rule::parse("FREQ=HOURLY").is_err());
+        assert!(Rule::parse("FREQ=WEEKLY;BYDAY=XX").is_err());
+    }
+
+    #[test]
+    fn series_add_dates_and_leave_out_exceptions() {
+        let series = Series {
+            rule: Some(Rule::weekly(Weekday::Thu)),
+            dates: vec![EventTime::Local(time("2026-01-24 10:00")), EventTime::Date(date("2026-02-02"))],
+            exdates: vec![EventTime::Date(date("2026-01-15")), EventTime::Local(time("2026-01-29 19:00"))],
+        };
+        let occurrences = series.occurrences(EventTime::Local(time("2026-01-01 19:00")), date("2026-01-10"), date("2026-02-06"), Zone::Device);
+        let ids: Vec<String> = occurrences.into_iter().map(occurrence_id).collect();
+        assert_eq!(ids, ["2026-01-22T19:00", "2026-01-24T10:00", "2026-02-02T19:00", "2026-02-05T19:00"]);
+
+        // An exception at another time of day leaves the occurrence in place
+        let series = Series { exdates: vec![EventTime::Local(time("2026-01-22 18:00"))], ..series };
+        let occurrences = series.occurrences(EventTime::Local(time("2026-01-01 19:00")), date("2026-01-22"), date("2026-01-22"), Zone::Device);
+        assert_eq!(occurrences, [EventTime::Local(time("2026-01-22 19:00"))]);
+        assert_eq!(parse_occurrence_id("2026-01-22T19:00"), Some(EventTime::Local(time("2026-01-22 19:00"))));
+    }
+
+    #[test]
+    fn large_intervals_end_the_rule() {
+        for frequency in ["DAILY", "WEEKLY", "MONTHLY", "YEARLY"] {
+            let rule = Rule::parse(&format!("FREQ={};INTERVAL=4000000000", frequency)).unwrap();
+            assert_eq!(rule.starts(time("2025-01-01 10:00"), time("9000-01-01 00:00"), Zone::Device).len(), 1, "{}", frequency);
+        }
+    }
+}
@@ -0,0 +1,195 @@
+use rusqlite::{params, Connection};
+use serde_json::{json, Map, Value};
+use crate::db;
+
+/// What happens to referencing records when the referenced record is deleted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OnDelete {
+    /// Refuse the delete while any record still points at it
+    Restrict,
+    /// Clear the reference field
+    SetNull,
+    /// Delete the referencing records too
+    Cascade,
+}
+
+/// A field on one model that holds the id of a record of another model
+#[derive(Debug)]
+pub struct Relation {
+    pub model: &'static str,
+    pub field: &'static str,
+    pub target: &'static str,
+    pub on_delete: OnDelete,
+}
+
+const fn relation(model: &'static str, field: &'static str, target: &'static str, on_delete: OnDelete) -> Relation {
+    Relation { model, field, target, on_delete }
+}
+
+/// Every id reference between models.
+/// Records holding history (visits, routing, people) block deletes of what they point at;
+/// records that only make sense as part of their parent are deleted along with it.
+pub const RELATIONS: &[Relation] = &[
+    relation("AssemblyOrganizationObject", "person", "PersonObject", OnDelete::Cascade),
+    relation("ChecklistItemObject", "checklistId", "ChecklistObject", OnDelete::Cascade),
+    relation("CircuitEventObject", "eventVenue", "VenueObject", OnDelete::Restrict),
+    relation("CircuitEventObject", "rehearsalVenue", "VenueObject", OnDelete::SetNull),
+    relation("CircuitEventObject", "circuit", "CircuitObject", OnDelete::Restrict),
+    relation("CircuitEventPartObject", "event", "CircuitEventObject", OnDelete::Cascade),
+    relation("CongregationObject", "venue", "VenueObject", OnDelete::Restrict),
+    relation("CongregationObject", "circuit", "CircuitObject", OnDelete::Restrict),
+    relation("CongregationObject", "hostCongregation", "CongregationObject", OnDelete::SetNull),
+    relation("ConventionPartObject", "convention", "ConventionObject", OnDelete::Cascade),
+    relation("PersonObject", "congregation", "CongregationObject", OnDelete::Restrict),
+    relation("PersonObject", "spouse", "PersonObject", OnDelete::SetNull),
+    relation("PersonObject", "familyHeadId", "PersonObject", OnDelete::SetNull),
+    relation("PQRObject", "personId", "PersonObject", OnDelete::Cascade),
+    relation("PssClassWorkshopObject", "pssClass", "PssObject", OnDelete::Cascade),
+    relation("PssClassWorkshopObject", "rehearsalVenue", "VenueObject", OnDelete::SetNull),
+    relation("PssObject", "classVenue", "VenueObject", OnDelete::SetNull),
+    relation("PssObject", "rehearsalVenueDefault", "VenueObject", OnDelete::SetNull),
+    relation("PssObject", "hostCongregation", "CongregationObject", OnDelete::SetNull),
+    relation("RecommendationObject", "person", "PersonObject", OnDelete::Cascade),
+    relation("RecommendationObject", "visit", "VisitObject", OnDelete::Cascade),
+    relation("RoutingObject", "congregation", "CongregationObject", OnDelete::Restrict),
+    relation("RoutingObject", "circuit", "CircuitObject", OnDelete::Restrict),
+    relation("RoutingObject", "subCO", "CircuitOverseerObject", OnDelete::SetNull),
+    relation("RoutingObject", "specialEventVenue", "VenueObject", OnDelete::SetNull),
+    relation("SceClassAssignmentObject", "sceClass", "SceObject", OnDelete::Cascade),
+    relation("SceObject", "classVenue", "VenueObject", OnDelete::SetNull),
+    relation("VisitObject", "congregation", "CongregationObject", OnDelete::Restrict),
+];
+
+/// Key under which `expand` embeds referenced records. It is never stored.
+pub const EXPANDED_KEY: &str = "_expanded";
+
+/// Read a reference field as an id; null, empty strings and missing fields are "no reference"
+fn reference_id(record: &Value, field: &str) -> Option<String> {
+    match record.get(field) {
+        Some(Value::String(id)) if !id.is_empty() => Some(id.clone()),
+        Some(Value::Number(id)) => Some(id.to_string()),
+        _ => None,
+    }
+}
+
+fn record_exists(conn: &Connection, model_name: &str, id: &str) -> Result<bool, String> {
+    conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM model_records WHERE model_name = ?1 AND id = ?2)",
+        params![model_name, id],
+        |row| row.get(0),
+    )
+    .map_err(|e| format!("Failed to check reference: {}", e))
+}
+
+/// Ids of `relation.model` records whose `relation.field` points at `id`
+fn referencing_ids(conn: &Connection, relation: &Relation, id: &str) -> Result<Vec<String>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id FROM model_records
+             WHERE model_name = ?1 AND CAST(json_extract(data, ?2) AS TEXT) = ?3
+             ORDER BY position",
+        )
+        .map_err(|e| format!("Failed to prepare reference lookup: {}", e))?;
+    let rows = stmt
+        .query_map(params![relation.model, format!("$.{}", relation.field), id], |row| row.get(0))
+        .map_err(|e| format!("Failed to look up references: {}", e))?;
+
+    rows.collect::<Result<Vec<String>, _>>()
+        .map_err(|e| format!("Failed to read reference: {}", e))
+}
+
+/// Check that every reference field on `record` points at an existing record
+pub fn validate_references(conn: &Connection, model_name: &str, record: &Value) -> Result<(), String> {
+    for relation in RELATIONS.iter().filter(|r| r.model == model_name) {
+        if let Some(id) = reference_id(record, relation.field) {
+            if !record_exists(conn, relation.target, &id)? {
+                return Err(format!(
+                    "{}.{} refers to missing {} record {}",
+                    model_name, relation.field, relation.target, id
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Clear the fields of `record` that point at another `model_name` record being deleted (`is_deleted`)
+/// through a `SetNull` relation
+pub fn clear_references(model_name: &str, record: &mut Value, is_deleted: impl Fn(&str) -> bool) {
+    for relation in RELATIONS
+        .iter()
+        .filter(|r| r.model == model_name && r.target == model_name && r.on_delete == OnDelete::SetNull)
+    {
+        if reference_id(record, relation.field).is_some_and(|id| is_deleted(&id)) {
+            if let Some(fields) = record.as_object_mut() {
+                fields.insert(relation.field.to_string(), Value::Null);
+            }
+        }
+    }
+}
+
+/// Apply the delete rules of every relation pointing at `model_name` record `id`.
+/// Must run inside the transaction that deletes the record, so a refused delete
+/// leaves everything as it was. Cascaded deletes go to the trash in `batch_id`.
+pub fn apply_delete_rules(conn: &Connection, model_name: &str, id: &str, batch_id: &str) -> Result<(), String> {
+    // Check every restriction before changing anything
+    for relation in RELATIONS.iter().filter(|r| r.target == model_name && r.on_delete == OnDelete::Restrict) {
+        let referencing = referencing_ids(conn, relation, id)?;
+        if !referencing.is_empty() {
+            return Err(format!(
+                "Cannot delete {} {}: still referenced by {} {} record(s) through {}",
+                model_name,
+                id,
+                referencing.len(),
+                relation.model,
+                relation.field
+            ));
+        }
+    }
+
+    for relation in RELATIONS.iter().filter(|r| r.target == model_name) {
+        match relation.on_delete {
+            OnDelete::Restrict => {}
+            OnDelete::SetNull => {
+                for referencing_id in referencing_ids(conn, relation, id)? {
+                    db::update_record_in_tx(conn, relation.model, &referencing_id, json!({ relation.field: null }))?;
+                }
+            }
+            OnDelete::Cascade => {
+                for referencing_id in referencing_ids(conn, relation, id)? {
+                    db::delete_record_in_batch(conn, relation.model, &referencing_id, batch_id)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Embed the records referenced by `fields` under `_expanded` on each record.
+/// Missing targets are embedded as null.
+pub fn expand_records(conn: &Connection, model_name: &str, records: &mut [Value], fields: &[String]) -> Result<(), String> {
+    let relations = fields
+        .iter()
+        .map(|field| {
+            RELATIONS
+                .iter()
+                .find(|r| r.model == model_name && r.field == field)
+                .ok_or_else(|| format!("{}.{} is not a reference field", model_name, field))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    for record in records.iter_mut() {
+        let mut expanded = Map::new();
+        for relation in &relations {
+            let target = match reference_id(record, relation.field) {
+                Some(id) => db::get_record(conn, relation.target, &id).ok(),
+                None => None,
+            };
+            expanded.insert(relation.field.to_string(), target.unwrap_or(Value::Null));
+        }
+        if let Some(fields) = record.as_object_mut() {
+            fields.insert(EXPANDED_KEY.to_string(), Value::Object(expanded));
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,121 @@
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use serde_json::Value;
+use crate::db;
+
+/// All records of a model together with the model's revision when they were read
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelData {
+    pub revision: i64,
+    pub records: Vec<Value>,
+}
+
+/// A write was based on an older version than the one stored.
+/// Sent to the frontend as `CONFLICT: {json}`, so it can show the stored version or reload.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Conflict {
+    pub message: String,
+    pub model_name: String,
+    /// None for whole-model writes
+    pub record_id: Option<String>,
+    pub expected_revision: i64,
+    /// None when the record has been deleted
+    pub current_revision: Option<i64>,
+    /// The stored record (null if deleted), or all records of the model for whole-model writes
+    pub current: Value,
+}
+
+impl From<Conflict> for String {
+    fn from(conflict: Conflict) -> Self {
+        match serde_json::to_string(&conflict) {
+            Ok(json) => format!("CONFLICT: {}", json),
+            Err(_) => format!("CONFLICT: {}", conflict.message),
+        }
+    }
+}
+
+/// Current revision of a model; 0 if its records have never been written.
+/// Triggers on `model_records` bump it on every insert, update and delete.
+pub fn model_revision(conn: &Connection, model_name: &str) -> Result<i64, String> {
+    conn.query_row(
+        "SELECT revision FROM model_revisions WHERE model_name = ?1",
+        params![model_name],
+        |row| row.get(0),
+    )
+    .optional()
+    .map(Option::unwrap_or_default)
+    .map_err(|e| format!("Failed to read revision of {}: {}", model_name, e))
+}
+
+/// Read all records of a model along with its revision
+pub fn read_model(conn: &Connection, model_name: &str) -> Result<ModelData, String> {
+    Ok(ModelData {
+        revision: model_revision(conn, model_name)?,
+        records: db::read_records(conn, model_name)?,
+    })
+}
+
+/// Refuse a whole-model write unless the model is still at the revision the caller read
+pub fn check_model(conn: &Connection, model_name: &str, expected: i64) -> Result<(), String> {
+    let current = model_revision(conn, model_name)?;
+    if current == expected {
+        return Ok(());
+    }
+    Err(Conflict {
+        message: format!(
+            "{} records were changed elsewhere (revision {}, expected {}). Reload them and try again.",
+            model_name, current, expected
+        ),
+        model_name: model_name.to_string(),
+        record_id: None,
+        expected_revision: expected,
+        current_revision: Some(current),
+        current: Value::Array(db::read_records(conn, model_name)?),
+    }
+    .into())
+}
+
+/// Refuse a write to one record unless it still exists at the revision the caller read
+pub fn check_record(conn: &Connection, model_name: &str, id: &str, expected: i64) -> Result<(), String> {
+    // Only a missing record is a conflict; a failure to read it is reported as it is
+    let current = db::find_record(conn, model_name, id)?;
+    let current_revision = current.as_ref().map(db::revision_of);
+    if current_revision == Some(expected) {
+        return Ok(());
+    }
+    let message = match current_revision {
+        Some(revision) => format!(
+            "{} record {} was changed elsewhere (revision {}, expected {}). Reload it and try again.",
+            model_name, id, revision, expected
+        ),
+        None => format!("{} record {} was deleted elsewhere", model_name, id),
+    };
+    Err(Conflict {
+        message,
+        model_name: model_name.to_string(),
+        record_id: Some(id.to_string()),
+        expected_revision: expected,
+        current_revision,
+        current: current.unwrap_or(Value::Null),
+    }
+    .into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_missing_records_are_conflicts() {
+        let conn = db::test_connection();
+        let error = check_record(&conn, "PersonObject", "missing", 1).unwrap_err();
+        assert!(error.starts_with("CONFLICT: "), "{}", error);
+
+        // A database that cannot be read is not taken for a deleted record
+        let unreadable = Connection::open_in_memory().unwrap();
+        let error = check_record(&unreadable, "PersonObject", "missing", 1).unwrap_err();
+        assert!(error.starts_with("Failed to read record"), "{}", error);
+    }
+}
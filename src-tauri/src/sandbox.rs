@@ -0,0 +1,125 @@
+use std::fmt;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use tauri::AppHandle;
+use crate::commands::get_data_directory;
+use crate::exports::get_export_directory;
+use crate::{profiles, schema};
+
+/// Why a model name or file path passed to a command was refused.
+/// Shown as `CODE: message`, so the frontend can tell the cases apart by the code.
+#[derive(Debug)]
+pub enum AccessError {
+    /// Not one of the models in `schema::MODELS`
+    UnknownModel(String),
+    /// Empty, contains `..`, or is not a regular file
+    InvalidPath(String),
+    /// Resolves to a file outside the data and export directories
+    OutsideSandbox(PathBuf),
+    /// Lies inside an allowed directory, but a symlink points it somewhere else
+    SymlinkEscape(PathBuf),
+    NotFound(PathBuf),
+}
+
+impl AccessError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            AccessError::UnknownModel(_) => "UNKNOWN_MODEL",
+            AccessError::InvalidPath(_) => "INVALID_PATH",
+            AccessError::OutsideSandbox(_) => "PATH_OUTSIDE_SANDBOX",
+            AccessError::SymlinkEscape(_) => "SYMLINK_ESCAPE",
+            AccessError::NotFound(_) => "FILE_NOT_FOUND",
+        }
+    }
+}
+
+impl fmt::Display for AccessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: ", self.code())?;
+        match self {
+            AccessError::UnknownModel(name) => write!(f, "Unknown model {:?}", name),
+            AccessError::InvalidPath(reason) => write!(f, "{}", reason),
+            AccessError::OutsideSandbox(path) => {
+                write!(f, "{} is outside the app's data and export folders", path.display())
+            }
+            AccessError::SymlinkEscape(path) => {
+                write!(f, "{} links to a location outside the app's folders", path.display())
+            }
+            AccessError::NotFound(path) => write!(f, "{} does not exist", path.display()),
+        }
+    }
+}
+
+impl From<AccessError> for String {
+    fn from(error: AccessError) -> Self {
+        error.to_string()
+    }
+}
+
+/// Check that a model name from the frontend is one of the known models
+pub fn model_name(name: &str) -> Result<&'static str, AccessError> {
+    schema::model_schema(name)
+        .map(|schema| schema.name)
+        .ok_or_else(|| AccessError::UnknownModel(name.to_string()))
+}
+
+/// Check a list of model names, e.g. a search filter
+pub fn model_names(names: &[String]) -> Result<(), AccessError> {
+    names.iter().try_for_each(|name| model_name(name).map(|_| ()))
+}
+
+/// Whether `path` lies inside `root`, but not in the folder of another profile
+/// (the default profile's folders contain those of all other profiles)
+fn is_within(path: &Path, root: &Path) -> bool {
+    path.strip_prefix(root)
+        .is_ok_and(|rest| !rest.starts_with(profiles::PROFILES_DIR))
+}
+
+/// The folders of the active profile that commands may read files from, as (path, canonical path) pairs.
+/// The export folder comes first; relative paths are resolved against it.
+fn allowed_roots(app: &AppHandle) -> Result<Vec<(PathBuf, PathBuf)>, String> {
+    let mut roots = Vec::new();
+    for dir in [get_export_directory(app)?, get_data_directory(app)?] {
+        let canonical = fs::canonicalize(&dir)
+            .map_err(|e| format!("Failed to resolve {}: {}", dir.display(), e))?;
+        roots.push((dir, canonical));
+    }
+    Ok(roots)
+}
+
+/// Resolve a file path passed to a command.
+/// The file must exist and, after following symlinks, lie inside the active profile's export or data folder.
+pub fn resolve_file(app: &AppHandle, path: &str) -> Result<PathBuf, String> {
+    if path.trim().is_empty() || path.contains('\0') {
+        return Err(AccessError::InvalidPath("File path is empty or malformed".to_string()).into());
+    }
+    let roots = allowed_roots(app)?;
+
+    let requested = Path::new(path);
+    let requested = if requested.is_absolute() {
+        requested.to_path_buf()
+    } else {
+        roots[0].0.join(requested)
+    };
+    // Paths the app hands out never need `..`, so don't try to reason about them
+    if requested.components().any(|c| c == Component::ParentDir) {
+        return Err(AccessError::InvalidPath(format!("{} must not contain ..", path)).into());
+    }
+
+    let canonical = fs::canonicalize(&requested).map_err(|_| AccessError::NotFound(requested.clone()))?;
+    if roots.iter().any(|(_, root)| is_within(&canonical, root)) {
+        if !canonical.is_file() {
+            return Err(AccessError::InvalidPath(format!("{} is not a file", requested.display())).into());
+        }
+        return Ok(canonical);
+    }
+
+    let looks_inside = roots
+        .iter()
+        .any(|(dir, root)| is_within(&requested, dir) || is_within(&requested, root));
+    if looks_inside {
+        Err(AccessError::SymlinkEscape(requested).into())
+    } else {
+        Err(AccessError::OutsideSandbox(requested).into())
+    }
+}
@@ -0,0 +1,1016 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use serde::Serialize;
+use serde_json::Value;
+use crate::sandbox;
+use crate::time_zones;
+
+/// The kind of value a model field holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FieldType {
+    Bool,
+    Number,
+    Text,
+    /// `YYYY-MM-DD`, `YYYY/MM/DD` or an ISO-8601 date-time
+    Date,
+    /// The id of another record; see `relations::RELATIONS`
+    Id,
+    Array,
+    Object,
+    /// An IANA time zone name, e.g. `Europe/Berlin`
+    TimeZone,
+    /// Not checked; used where the frontend gives no type (default `null`)
+    Any,
+}
+
+/// A field of a model, as defined by the frontend's `new*Object` template
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Field {
+    pub name: &'static str,
+    #[serde(rename = "type")]
+    pub field_type: FieldType,
+    /// Must be present and not empty
+    pub required: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelSchema {
+    pub name: &'static str,
+    pub fields: &'static [Field],
+}
+
+/// A problem with one field of a record, for display next to that field
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+const fn field(name: &'static str, field_type: FieldType) -> Field {
+    Field { name, field_type, required: false }
+}
+
+const fn required(name: &'static str, field_type: FieldType) -> Field {
+    Field { name, field_type, required: true }
+}
+
+// Field lists mirror src/models/*Object.js. Keep them in step when a model changes:
+// a field's type comes from its default value there, or from its name where the default is null.
+
+const ASSEMBLY_ORGANIZATION_FIELDS: &[Field] = &[
+    field("circuit", FieldType::Any),
+    field("circuitSection", FieldType::Any),
+    field("department", FieldType::Any),
+    field("assignmentRole", FieldType::Any),
+    field("person", FieldType::Id),
+    field("deleted", FieldType::Bool),
+];
+
+const BRANCH_OFFICE_FIELDS: &[Field] = &[
+    required("name", FieldType::Text),
+    field("country", FieldType::Text),
+    field("selectedCountry", FieldType::Text),
+    field("address", FieldType::Text),
+    field("city", FieldType::Text),
+    field("stateOrCode", FieldType::Text),
+    field("postalCode", FieldType::Text),
+    field("jwEmail", FieldType::Text),
+    field("emailCode", FieldType::Text),
+    field("correspondenceLanguage", FieldType::Text),
+    field("notes", FieldType::Text),
+];
+
+const CHECKLIST_ITEM_FIELDS: &[Field] = &[
+    field("checklistId", FieldType::Id),
+    field("itemCompleted", FieldType::Bool),
+    required("itemName", FieldType::Text),
+    field("itemDescription", FieldType::Text),
+    field("dateDue", FieldType::Date),
+    field("cChildId", FieldType::Text),
+    field("assignedTo", FieldType::Array),
+];
+
+const CHECKLIST_FIELDS: &[Field] = &[
+    required("checklistName", FieldType::Text),
+    field("checklistDescription", FieldType::Text),
+    field("cParentId", FieldType::Any),
+    field("cParentType", FieldType::Any),
+    field("archived", FieldType::Bool),
+    field("expanded", FieldType::Bool),
+    field("cardColor", FieldType::Text),
+];
+
+const CIRCUIT_EVENT_FIELDS: &[Field] = &[
+    field("eventDate", FieldType::Date),
+    field("eventType", FieldType::Any),
+    field("eventVenue", FieldType::Id),
+    field("rehearsalDate", FieldType::Date),
+    field("rehearsalTimeStart", FieldType::Any),
+    field("rehearsalTimeEnd", FieldType::Any),
+    field("rehearsalVenue", FieldType::Id),
+    field("rehearsalCalendarSequence", FieldType::Number),
+    field("rehearsalCalendarUId", FieldType::Text),
+    field("walkThroughTime", FieldType::Any),
+    field("walkThroughTimePM", FieldType::Any),
+    field("includeRehearsalCal", FieldType::Bool),
+    field("notes", FieldType::Text),
+    field("coNotes", FieldType::Text),
+    field("branchRep", FieldType::Any),
+    field("fieldInstructor", FieldType::Any),
+    field("sharedCO", FieldType::Any),
+    field("circuit", FieldType::Id),
+    field("circuitSection", FieldType::Array),
+    field("eventLanguage", FieldType::Any),
+    field("deliveryMedium", FieldType::Text),
+    field("jwssURL", FieldType::Text),
+    field("jwssOverseer", FieldType::Any),
+    field("jwssOperator1", FieldType::Any),
+    field("jwssOperator2", FieldType::Any),
+    field("videoConferenceHost", FieldType::Any),
+    field("meetingId", FieldType::Any),
+    field("meetingPassword", FieldType::Text),
+    field("meetingURL", FieldType::Text),
+    field("eventOutlineFiles", FieldType::Array),
+    field("speakerDirectionFiles", FieldType::Array),
+    field("notice317Created", FieldType::Bool),
+    field("notice317DateCreated", FieldType::Date),
+    field("auditor", FieldType::Any),
+    field("auditAssignedDate", FieldType::Date),
+    field("auditCompleted", FieldType::Bool),
+    field("peakAttendance", FieldType::Number),
+    field("numberBaptized", FieldType::Number),
+    field("report318EventId", FieldType::Any),
+    field("circuitParticipantsOnly", FieldType::Bool),
+    field("chairmenArray", FieldType::Array),
+    field("routingEventId", FieldType::Any),
+];
+
+const CIRCUIT_EVENT_PART_FIELDS: &[Field] = &[
+    field("partTime", FieldType::Any),
+    field("partDurationInMinutes", FieldType::Number),
+    field("partTheme", FieldType::Any),
+    field("partOutlineNumber", FieldType::Any),
+    field("partBranchRep", FieldType::Bool),
+    field("partFieldRep", FieldType::Bool),
+    field("partSharedCO", FieldType::Bool),
+    field("partCOTalk", FieldType::Bool),
+    field("partSpeaker", FieldType::Any),
+    field("partType", FieldType::Any),
+    field("talkIncludes", FieldType::Array),
+    field("symposium", FieldType::Bool),
+    field("partInstructions", FieldType::Any),
+    field("videoLink", FieldType::Any),
+    field("partConfirmed", FieldType::Bool),
+    field("emailSent", FieldType::Bool),
+    field("jwEmailSent", FieldType::Bool),
+    field("partOutlineFiles", FieldType::Array),
+    field("speakerDirectionFiles", FieldType::Array),
+    field("event", FieldType::Id),
+    field("participants", FieldType::Array),
+];
+
+const CIRCUIT_FIELDS: &[Field] = &[
+    field("country", FieldType::Text),
+    field("stateOrCode", FieldType::Any),
+    field("number", FieldType::Any),
+    field("language", FieldType::Any),
+    field("userToggle", FieldType::Bool),
+    field("numberOnly", FieldType::Bool),
+];
+
+const CIRCUIT_OVERSEER_FIELDS: &[Field] = &[
+    field("nameFirst", FieldType::Text),
+    required("nameLast", FieldType::Text),
+    field("nameSuffix", FieldType::Text),
+    field("namePreferred", FieldType::Text),
+    field("emailMain", FieldType::Text),
+    field("emailJWOrg", FieldType::Text),
+    field("phoneMobile", FieldType::Text),
+    field("phoneHome", FieldType::Text),
+    field("circuit", FieldType::Text),
+    field("branchRep", FieldType::Bool),
+    field("fieldInstructor", FieldType::Bool),
+    field("photo", FieldType::Any),
+    field("notes", FieldType::Text),
+];
+
+const CONGREGATION_FIELDS: &[Field] = &[
+    field("number", FieldType::Text),
+    required("name", FieldType::Text),
+    field("branchOfficeCongregationName", FieldType::Text),
+    field("city", FieldType::Text),
+    field("cityNone", FieldType::Bool),
+    field("stateProvince", FieldType::Text),
+    field("stateProvinceNone", FieldType::Bool),
+    field("country", FieldType::Text),
+    field("venue", FieldType::Id),
+    field("timeZone", FieldType::TimeZone),
+    field("midweekMeetingDay", FieldType::Any),
+    field("midweekMeetingTime", FieldType::Any),
+    field("weekendMeetingDay", FieldType::Any),
+    field("weekendMeetingTime", FieldType::Any),
+    field("circuit", FieldType::Id),
+    field("circuitSection", FieldType::Any),
+    field("language", FieldType::Any),
+    field("languageForms", FieldType::Any),
+    field("group", FieldType::Bool),
+    field("hostCongregation", FieldType::Id),
+    field("sponsorsGroup", FieldType::Bool),
+    field("prisonContact", FieldType::Bool),
+    field("participatesSMPW", FieldType::Bool),
+    field("sponsorsPregroup", FieldType::Bool),
+    field("notes", FieldType::Text),
+    field("branch", FieldType::Any),
+    field("dataSource", FieldType::Text),
+    field("sourceId", FieldType::Text),
+    field("vCardUId", FieldType::Text),
+    field("kmlData", FieldType::Text),
+    field("jwOrgCongURL", FieldType::Text),
+];
+
+const CONVENTION_FIELDS: &[Field] = &[
+    field("conventionIdentifier", FieldType::Text),
+    field("conventionStartDate", FieldType::Date),
+    field("venue", FieldType::Text),
+    field("eventLanguage", FieldType::Text),
+    field("congregationsAssignedArray", FieldType::Array),
+    field("serviceYear", FieldType::Text),
+    field("conventionCommitteeCoordinator", FieldType::Text),
+    field("assitantToCCC", FieldType::Text),
+    field("programOverseer", FieldType::Text),
+    field("assistantToPO", FieldType::Text),
+    field("roomingOverseer", FieldType::Text),
+    field("assistantToRO", FieldType::Text),
+    field("conventionChairman", FieldType::Text),
+    field("conventionCommitteeEmail", FieldType::Text),
+    field("routingEventId", FieldType::Text),
+    field("walkthroughTimeNote", FieldType::Text),
+    field("officeAssignments", FieldType::Array),
+    field("officePersonnel", FieldType::Array),
+    field("reportQ1", FieldType::Text),
+    field("reportQ2", FieldType::Text),
+    field("reportQ3", FieldType::Text),
+    field("reportQ4", FieldType::Text),
+    field("notes", FieldType::Text),
+];
+
+const CONVENTION_PART_FIELDS: &[Field] = &[
+    field("partDay", FieldType::Text),
+    field("partTime", FieldType::Any),
+    field("partTheme", FieldType::Text),
+    field("partNumber", FieldType::Any),
+    field("partSpeaker", FieldType::Text),
+    field("partViaVideoOrStream", FieldType::Bool),
+    field("rehearsalRequired", FieldType::Bool),
+    field("rehearsalCompleted", FieldType::Bool),
+    field("rehearsalDetails", FieldType::Text),
+    field("dateAssigned", FieldType::Date),
+    field("partConfirmed", FieldType::Bool),
+    field("partPresentationFormat", FieldType::Text),
+    field("speakerVisitor", FieldType::Text),
+    field("speakerBethelite", FieldType::Text),
+    field("convention", FieldType::Id),
+    field("notes", FieldType::Text),
+    field("nextPartNote", FieldType::Text),
+];
+
+const EDITOR_TEMPLATE_FIELDS: &[Field] = &[
+    field("templateId", FieldType::Text),
+    field("templateType", FieldType::Text),
+    field("templateName", FieldType::Text),
+    field("templateContent", FieldType::Text),
+    field("templateEmail", FieldType::Bool),
+    field("dateUpdated", FieldType::Date),
+    field("dateCreated", FieldType::Date),
+];
+
+const EMAIL_FIELDS: &[Field] = &[
+    field("emailUserName", FieldType::Text),
+    field("emailPassword", FieldType::Text),
+    field("emailTo", FieldType::Text),
+    field("emailCc", FieldType::Text),
+    field("userSettingWordForCc", FieldType::Text),
+    field("emailBcc", FieldType::Text),
+    field("emailSubject", FieldType::Text),
+    field("emailBody", FieldType::Text),
+    field("emailAttachments", FieldType::Array),
+    field("userSettingWordForAttach", FieldType::Text),
+];
+
+const EXPENSE_REPORT_FIELDS: &[Field] = &[
+    field("reportDate", FieldType::Date),
+    field("submittedDate", FieldType::Date),
+    field("reimbursedDate", FieldType::Date),
+    field("reportTitle", FieldType::Text),
+    field("reportTotal", FieldType::Number),
+    field("reportType", FieldType::Any),
+    field("lineItems", FieldType::Array),
+    field("submitTo", FieldType::Any),
+    field("submitToBranch", FieldType::Bool),
+    field("reportCurrency", FieldType::Any),
+    field("reportComments", FieldType::Text),
+    field("reportStatus", FieldType::Any),
+    field("receiptsFilePath", FieldType::Array),
+    field("pdfFilePath", FieldType::Text),
+];
+
+const LETTER_FIELDS: &[Field] = &[
+    field("letterName", FieldType::Text),
+    field("letterDate", FieldType::Date),
+    field("letterDateCentered", FieldType::Bool),
+    field("letterContent", FieldType::Text),
+    field("letterRegarding", FieldType::Text),
+    field("letterPathPDF", FieldType::Text),
+    field("archived", FieldType::Bool),
+    field("externalLetter", FieldType::Bool),
+    field("includeLetterhead", FieldType::Bool),
+    field("letterHeadTemplateContent", FieldType::Text),
+    field("letterHeadTemplateId", FieldType::Any),
+    field("includeSignature", FieldType::Bool),
+    field("sentTo", FieldType::Array),
+    field("sentCc", FieldType::Array),
+    field("sentFrom", FieldType::Any),
+    field("attention", FieldType::Text),
+    field("letterSummary", FieldType::Text),
+    field("letterPostscript", FieldType::Text),
+    field("submitted", FieldType::Bool),
+    field("dateSubmitted", FieldType::Date),
+    field("connectedToArray", FieldType::Array),
+    field("hideTo", FieldType::Bool),
+    field("hideCc", FieldType::Bool),
+    field("attachmentsPaths", FieldType::Array),
+];
+
+const PERSON_FIELDS: &[Field] = &[
+    field("blind", FieldType::Bool),
+    field("deaf", FieldType::Bool),
+    field("nameFirst", FieldType::Text),
+    field("nameMiddle", FieldType::Text),
+    required("nameLast", FieldType::Text),
+    field("nameNickname", FieldType::Text),
+    field("nameSuffix", FieldType::Text),
+    field("gender", FieldType::Any),
+    field("hope", FieldType::Any),
+    field("maritalStatus", FieldType::Any),
+    field("spouse", FieldType::Id),
+    field("familyHeadId", FieldType::Id),
+    field("dateBirth", FieldType::Date),
+    field("dateBaptism", FieldType::Date),
+    field("isUnbaptized", FieldType::Bool),
+    field("isDeceased", FieldType::Bool),
+    field("dateDeceased", FieldType::Date),
+    field("congregation", FieldType::Id),
+    field("languageGroup", FieldType::Any),
+    field("inactive", FieldType::Bool),
+    field("notAPublisher", FieldType::Bool),
+    field("privilegePioneer", FieldType::Bool),
+    field("privilegePioneerInfirm", FieldType::Bool),
+    field("pioneerNumber", FieldType::Any),
+    field("pioneerStartDate", FieldType::Date),
+    field("pioneerSchoolYear", FieldType::Any),
+    field("privilegePioneerSpecial", FieldType::Bool),
+    field("privilegePioneerSpecialInfirm", FieldType::Bool),
+    field("privilegePioneerSpecialTemp", FieldType::Bool),
+    field("privilegeFieldMissionary", FieldType::Bool),
+    field("theoSchoolsGrad", FieldType::Array),
+    field("schoolsGradClass", FieldType::Any),
+    field("schoolsGradYear", FieldType::Any),
+    field("privilegeBethelRegular", FieldType::Bool),
+    field("privilegeBethelExpatriate", FieldType::Bool),
+    field("privilegeBethelTemporary", FieldType::Bool),
+    field("privilegeBethelCommuter", FieldType::Bool),
+    field("privilegeBethelCommuterFT", FieldType::Bool),
+    field("privilegeBethelRemote", FieldType::Bool),
+    field("privilegeBethelRemoteServant", FieldType::Bool),
+    field("privilegeBethelConsultant", FieldType::Bool),
+    field("privilegeLDCVolunteer", FieldType::Bool),
+    field("privilegeLDCServant", FieldType::Bool),
+    field("privilegeLDCServantExpatriate", FieldType::Bool),
+    field("privilegeSchoolsFacilityServant", FieldType::Bool),
+    field("privilegeElder", FieldType::Bool),
+    field("privilegeCO", FieldType::Bool),
+    field("privilegeCOWife", FieldType::Bool),
+    field("privilegeSCOWife", FieldType::Bool),
+    field("sceLastGraduationYear", FieldType::Any),
+    field("privilegeMS", FieldType::Bool),
+    field("emailJW", FieldType::Text),
+    field("elderCBOE", FieldType::Bool),
+    field("elderSec", FieldType::Bool),
+    field("elderSO", FieldType::Bool),
+    field("elderCLMO", FieldType::Bool),
+    field("elderWTSC", FieldType::Bool),
+    field("elderAuxiliaryCounselor", FieldType::Bool),
+    field("privilegeGA", FieldType::Bool),
+    field("privilegeGS", FieldType::Bool),
+    field("privilegeLangInst", FieldType::Bool),
+    field("privilegePSSInst", FieldType::Bool),
+    field("privilegeAccounts", FieldType::Bool),
+    field("privilegeAudioVideo", FieldType::Bool),
+    field("privilegeLiterature", FieldType::Bool),
+    field("privilegePTC", FieldType::Bool),
+    field("privilegeTerritory", FieldType::Bool),
+    field("privilegeKHOC", FieldType::Bool),
+    field("elderSCO", FieldType::Bool),
+    field("elderSPC", FieldType::Bool),
+    field("elderGO", FieldType::Bool),
+    field("spcRank", FieldType::Number),
+    field("specialCommitteeNotes", FieldType::Text),
+    field("conventionPersonnelNotes", FieldType::Text),
+    field("assemblySpeaker", FieldType::Bool),
+    field("assemblySpeakerNotes", FieldType::Text),
+    field("convCommittee", FieldType::Bool),
+    field("convPersonnel", FieldType::Bool),
+    field("elderAO", FieldType::Bool),
+    field("elderAAO", FieldType::Bool),
+    field("elderAHOverseer", FieldType::Bool),
+    field("elderAHServant", FieldType::Bool),
+    field("elderHLC", FieldType::Bool),
+    field("elderPVG", FieldType::Bool),
+    field("elderFldInst", FieldType::Bool),
+    field("elderBC", FieldType::Bool),
+    field("addressHomeStreet", FieldType::Text),
+    field("addressHomeStreetLine2", FieldType::Text),
+    field("addressHomePOBox", FieldType::Text),
+    field("addressHomeApt", FieldType::Text),
+    field("addressHomeCity", FieldType::Text),
+    field("addressHomeStateProvince", FieldType::Text),
+    field("addressHomePostal", FieldType::Any),
+    field("addressHomeCountry", FieldType::Text),
+    field("phoneMobileCountryCode", FieldType::Any),
+    field("phoneMobile", FieldType::Any),
+    field("phoneHome", FieldType::Any),
+    field("emailMain", FieldType::Text),
+    field("notes", FieldType::Text),
+    field("notesPrivileges", FieldType::Text),
+    field("photo", FieldType::Any),
+    field("tags", FieldType::Array),
+    field("dataSource", FieldType::Text),
+    field("diseveratedFrom", FieldType::Array),
+    field("sourceId", FieldType::Text),
+    field("vCardUId", FieldType::Text),
+];
+
+const PQR_FIELDS: &[Field] = &[
+    field("personId", FieldType::Id),
+    field("submitted", FieldType::Bool),
+    field("nameWithBranch", FieldType::Text),
+    field("datePQR", FieldType::Date),
+    field("age", FieldType::Text),
+    field("assignment", FieldType::Text),
+    field("address", FieldType::Text),
+    field("email", FieldType::Text),
+    field("phone", FieldType::Text),
+    field("citizen", FieldType::Text),
+    field("languages", FieldType::Text),
+    field("skills", FieldType::Text),
+    field("hope", FieldType::Text),
+    field("theoTraining", FieldType::Array),
+    field("currentServing", FieldType::Array),
+    field("currentServingOther", FieldType::Text),
+    field("pastServicePriv", FieldType::Array),
+    field("pastServiceOther", FieldType::Text),
+    field("q10", FieldType::Text),
+    field("q11", FieldType::Text),
+    field("q12", FieldType::Text),
+    field("q13", FieldType::Text),
+    field("q14", FieldType::Text),
+    field("q15", FieldType::Text),
+    field("q16", FieldType::Text),
+    field("q17", FieldType::Array),
+    field("q17other", FieldType::Text),
+    field("q18", FieldType::Text),
+    field("q19", FieldType::Text),
+];
+
+const PSS_CLASS_WORKSHOP_FIELDS: &[Field] = &[
+    field("classWorkshopAssignmentNumber", FieldType::Any),
+    field("participantsArray", FieldType::Array),
+    field("pssClass", FieldType::Id),
+    field("participants", FieldType::Array),
+    field("confirmed", FieldType::Bool),
+    field("emailSent", FieldType::Bool),
+    field("pdfFilePath", FieldType::Text),
+    field("automationSendResult", FieldType::Array),
+    field("calendarSequence", FieldType::Number),
+    field("rehearsalDate", FieldType::Date),
+    field("rehearsalTimeStart", FieldType::Any),
+    field("rehearsalTimeEnd", FieldType::Any),
+    field("rehearsalVenue", FieldType::Id),
+    field("rehearsalContactElder", FieldType::Any),
+];
+
+const PSS_FIELDS: &[Field] = &[
+    field("studentsArray", FieldType::Array),
+    field("students", FieldType::Array),
+    field("classDate", FieldType::Date),
+    field("serviceYear", FieldType::Any),
+    field("confirmDate", FieldType::Date),
+    field("language", FieldType::Any),
+    field("classVenue", FieldType::Id),
+    field("otherCoIsInstructorB", FieldType::Bool),
+    field("instructorB", FieldType::Any),
+    field("altInstructorA", FieldType::Any),
+    field("localElder", FieldType::Any),
+    field("videoConferenceHost", FieldType::Any),
+    field("deliveryMedium", FieldType::Text),
+    field("meetingId", FieldType::Any),
+    field("meetingPassword", FieldType::Text),
+    field("meetingURL", FieldType::Text),
+    field("hostCongregation", FieldType::Id),
+    field("bookPickupLocation", FieldType::Any),
+    field("bookPickupDate", FieldType::Date),
+    field("bookPickupTime", FieldType::Any),
+    field("bookPickupCalendarSequence", FieldType::Number),
+    field("bookPickupCalendarUId", FieldType::Text),
+    field("classNotes", FieldType::Text),
+    field("includeWorkshopCal", FieldType::Bool),
+    field("hideConfirmations", FieldType::Bool),
+    field("hideBooks", FieldType::Bool),
+    field("prayerSchedule", FieldType::Array),
+    field("prayerSchedulePath", FieldType::Text),
+    field("prayerScheduleEmailSent", FieldType::Bool),
+    field("lastGradYearUpdated", FieldType::Bool),
+    field("rehearsalDateDefault", FieldType::Date),
+    field("rehearsalTimeStartDefault", FieldType::Any),
+    field("rehearsalTimeEndDefault", FieldType::Any),
+    field("rehearsalVenueDefault", FieldType::Id),
+];
+
+const RECOMMENDATION_FIELDS: &[Field] = &[
+    field("actionType", FieldType::Any),
+    field("deleteReason", FieldType::Any),
+    field("appointmentType", FieldType::Any),
+    field("apptDeleteCompleted", FieldType::Bool),
+    field("notes", FieldType::Text),
+    field("notesPersonal", FieldType::Text),
+    field("person", FieldType::Id),
+    field("rejected", FieldType::Bool),
+    field("appointDeleteDate", FieldType::Date),
+    field("visit", FieldType::Id),
+    field("listOrder", FieldType::Number),
+];
+
+const REMINDER_FIELDS: &[Field] = &[
+    field("objectType", FieldType::Text),
+    field("objectId", FieldType::Text),
+    field("eventDate", FieldType::Date),
+    field("reminderType", FieldType::Text),
+];
+
+const ROUTING_FIELDS: &[Field] = &[
+    field("dateStart", FieldType::Date),
+    field("eventType", FieldType::Any),
+    field("congregation", FieldType::Id),
+    field("circuit", FieldType::Id),
+    field("notes", FieldType::Text),
+    field("submitted", FieldType::Bool),
+    field("sharedCOcontacted", FieldType::Bool),
+    field("emailCOsent", FieldType::Bool),
+    field("noConflict", FieldType::Bool),
+    field("visitPackageSent", FieldType::Bool),
+    field("s302sent", FieldType::Bool),
+    field("s302confirmed", FieldType::Bool),
+    field("subCO", FieldType::Id),
+    field("coBeingServed", FieldType::Any),
+    field("visitingShepherd", FieldType::Any),
+    field("visitCreated", FieldType::Bool),
+    field("branchNotified", FieldType::Bool),
+    field("emailReminderSent", FieldType::Bool),
+    field("jwssReminderSent", FieldType::Bool),
+    field("specialEventStartTime", FieldType::Any),
+    field("specialEventVenue", FieldType::Id),
+    field("calendarUId", FieldType::Text),
+    field("calendarSequence", FieldType::Number),
+    field("pdfFilePath", FieldType::Text),
+    field("isImported", FieldType::Bool),
+];
+
+const SCE_CLASS_ASSIGNMENT_FIELDS: &[Field] = &[
+    field("classAssignmentNumber", FieldType::Any),
+    field("participants", FieldType::Array),
+    field("participantsArray", FieldType::Array),
+    field("emailSent", FieldType::Bool),
+    field("dateSent", FieldType::Date),
+    field("sceClass", FieldType::Id),
+    field("pdfFilePath", FieldType::Text),
+    field("confirmed", FieldType::Bool),
+];
+
+const SCE_FIELDS: &[Field] = &[
+    field("studentsArray", FieldType::Array),
+    field("classNumber", FieldType::Any),
+    field("classDate", FieldType::Date),
+    field("classVenue", FieldType::Id),
+    field("altInstructorA", FieldType::Any),
+    field("instructorB", FieldType::Any),
+    field("locationCoordinator", FieldType::Any),
+    field("studentSource", FieldType::Text),
+    field("manuallyAddedStudents", FieldType::Bool),
+    field("prayerSchedule", FieldType::Array),
+    field("prayerSchedulePath", FieldType::Text),
+    field("prayerScheduleEmailSent", FieldType::Bool),
+    field("branchTextStudent", FieldType::Text),
+    field("branchTextCBOE", FieldType::Text),
+    field("branchTextStudentCBOE", FieldType::Text),
+    field("notAttendingArray", FieldType::Array),
+    field("attendanceReportPath", FieldType::Text),
+    field("attendanceReportSent", FieldType::Bool),
+    field("classNotes", FieldType::Text),
+    field("calendarUId", FieldType::Text),
+    field("calendarSequence", FieldType::Number),
+];
+
+const USER_FIELDS: &[Field] = &[
+    field("nameFirst", FieldType::Text),
+    field("nameMiddle", FieldType::Text),
+    field("nameLast", FieldType::Text),
+    field("nameSuffix", FieldType::Text),
+    field("nameNickname", FieldType::Text),
+    field("wifeName", FieldType::Text),
+    field("wifePhone", FieldType::Any),
+    field("wifePhoto", FieldType::Any),
+    field("addressHomeStreet", FieldType::Text),
+    field("addressHomeApt", FieldType::Text),
+    field("addressHomeCity", FieldType::Text),
+    field("addressHomeStateProvince", FieldType::Text),
+    field("addressHomePostal", FieldType::Any),
+    field("phoneHome", FieldType::Any),
+    field("phoneMobile", FieldType::Any),
+    field("emailMain", FieldType::Text),
+    field("emailMainPassword", FieldType::Text),
+    field("emailJW", FieldType::Text),
+    field("emailJWPubPassword", FieldType::Text),
+    field("dateBirth", FieldType::Date),
+    field("dateBaptism", FieldType::Date),
+    field("hope", FieldType::Any),
+    field("theoSchoolsGrad", FieldType::Any),
+    field("personnelNumber", FieldType::Any),
+    field("sceInstructor", FieldType::Bool),
+    field("sceInstructorFulltime", FieldType::Bool),
+    field("circuit", FieldType::Any),
+    field("circuitNext", FieldType::Any),
+    field("circuitNextEffectiveDate", FieldType::Date),
+    field("timeZone", FieldType::TimeZone),
+    field("s307sent", FieldType::Bool),
+    field("pdfFilePath307", FieldType::Text),
+    field("photo", FieldType::Any),
+    field("petAllergies", FieldType::Bool),
+    field("notes", FieldType::Text),
+    field("brotherFollowingMe", FieldType::Text),
+    field("brotherFollowed", FieldType::Text),
+    field("licenseInfo", FieldType::Object),
+];
+
+const VENUE_FIELDS: &[Field] = &[
+    required("name", FieldType::Text),
+    field("nameOther", FieldType::Text),
+    field("street", FieldType::Text),
+    field("city", FieldType::Text),
+    field("stateProvince", FieldType::Text),
+    field("postal", FieldType::Text),
+    field("phone", FieldType::Text),
+    field("emailMain", FieldType::Text),
+    field("latitude", FieldType::Text),
+    field("longitude", FieldType::Text),
+    field("timeZone", FieldType::TimeZone),
+    field("alarmCode", FieldType::Text),
+    field("wifiNetworkName", FieldType::Text),
+    field("wifiNetworkPassword", FieldType::Text),
+    field("overlappingCircuits", FieldType::Array),
+];
+
+const VISIT_FIELDS: &[Field] = &[
+    field("dateStart", FieldType::Date),
+    field("congregation", FieldType::Id),
+    field("packageSent", FieldType::Bool),
+    field("packageSentDate", FieldType::Date),
+    field("reviewPeriodStartingMonth", FieldType::Any),
+    field("packageReceived", FieldType::Bool),
+    field("countTerritories", FieldType::Number),
+    field("countTerritoriesNotWorked", FieldType::Number),
+    field("countTerritoriesBusiness", FieldType::Number),
+    field("countTerritoriesBusinessNotWorked", FieldType::Number),
+    field("territoryComments", FieldType::Text),
+    field("countTerritoryContacts", FieldType::Number),
+    field("accountsAuditLastQuarter", FieldType::Any),
+    field("accountsAuditFirstQuarter", FieldType::Any),
+    field("accountsAuditRegular", FieldType::Bool),
+    field("accountsAuditFirstDate", FieldType::Date),
+    field("accountsAuditLastDate", FieldType::Date),
+    field("accountsAuditFirstAuditor", FieldType::Text),
+    field("accountsAuditLastAuditor", FieldType::Text),
+    field("accountsAuditDiffBrothers", FieldType::Bool),
+    field("accountsAuditIssues", FieldType::Bool),
+    field("accountsAuditIssuesNote", FieldType::Text),
+    field("accountsAuditReconicliation", FieldType::Bool),
+    field("accountsDonationsMonthly", FieldType::Bool),
+    field("accountsAverageFundsHeld", FieldType::Number),
+    field("accountsAverageFundsHeldMonth1", FieldType::Number),
+    field("accountsAverageFundsHeldMonth2", FieldType::Number),
+    field("accountsAverageFundsHeldMonth3", FieldType::Number),
+    field("accountsAverageFundsHeldMonth4", FieldType::Number),
+    field("accountsAverageFundsHeldMonth5", FieldType::Number),
+    field("accountsAverageFundsHeldMonth6", FieldType::Number),
+    field("accountsAverageFundsNeeded", FieldType::Number),
+    field("accountsAverageFundsNeededMonth1", FieldType::Number),
+    field("accountsAverageFundsNeededMonth2", FieldType::Number),
+    field("accountsAverageFundsNeededMonth3", FieldType::Number),
+    field("accountsAverageFundsNeededMonth4", FieldType::Number),
+    field("accountsAverageFundsNeededMonth5", FieldType::Number),
+    field("accountsAverageFundsNeededMonth6", FieldType::Number),
+    field("accountsTargetBalance", FieldType::Number),
+    field("accountsTargetBalanceLastDate", FieldType::Date),
+    field("accountsFundsBalanceAcceptable", FieldType::Bool),
+    field("accountsGeneralComments", FieldType::Text),
+    field("accountsMonthlyApprovedResolution", FieldType::Bool),
+    field("accountsMonthlyWWW", FieldType::Number),
+    field("accountsMonthlyWWWResolutionDate", FieldType::Date),
+    field("accountsMonthlyApprovedResolutionReasonable", FieldType::Bool),
+    field("titleHoldingCong", FieldType::Bool),
+    field("visitNotesPrivate", FieldType::Text),
+    field("pubsBaptizedCountTotal", FieldType::Number),
+    field("pioneersCountTotal", FieldType::Number),
+    field("pubsUnaptizedCountTotal", FieldType::Number),
+    field("inactiveTotal", FieldType::Number),
+    field("irregularTotal", FieldType::Number),
+    field("reactivatedTotal", FieldType::Number),
+    field("newPublishersTotal", FieldType::Number),
+    field("baptizedSinceLastVisit", FieldType::Number),
+    field("auxPioneersCount", FieldType::Number),
+    field("auxPioneersThisVisitCount", FieldType::Number),
+    field("topicsElders", FieldType::Text),
+    field("topicsCO", FieldType::Text),
+    field("groupMidweekMeetingDay", FieldType::Text),
+    field("weekdayMorningServiceTime", FieldType::Text),
+    field("weekdayAfternoonServiceTime", FieldType::Text),
+    field("saturdayServiceTime", FieldType::Text),
+    field("sundayServiceTime", FieldType::Text),
+    field("halfDayOff", FieldType::Text),
+    field("tuesdayMeetingTime", FieldType::Text),
+    field("weekendMeetingDay", FieldType::Any),
+    field("weekendMeetingTime", FieldType::Text),
+    field("eldersMSMeetingDay", FieldType::Any),
+    field("eldersMSMeetingTime", FieldType::Text),
+    field("pioneerMeetingDay", FieldType::Any),
+    field("pioneerMeetingTime", FieldType::Text),
+    field("pioneerMeetingTheme", FieldType::Text),
+    field("pioneerMeetingForGroup", FieldType::Bool),
+    field("coPioneerOutline", FieldType::Bool),
+    field("eldersMeetingTheme", FieldType::Text),
+    field("tuesdayServiceTalkTheme", FieldType::Text),
+    field("tuesdayConcludingSong", FieldType::Text),
+    field("publicTalkTheme", FieldType::Text),
+    field("publicTalkSong", FieldType::Text),
+    field("weekendServiceTalkTheme", FieldType::Text),
+    field("weekendConcludingSong", FieldType::Text),
+    field("meetingCBOENotes", FieldType::Text),
+    field("serviceTalkNotes", FieldType::Text),
+    field("reportBody", FieldType::Text),
+    field("s303sentBranch", FieldType::Bool),
+    field("s303sentCong", FieldType::Bool),
+    field("s303printed", FieldType::Bool),
+    field("trackRecomendationPrinted", FieldType::Bool),
+    field("trackRecomendationSent", FieldType::Bool),
+    field("trackRecomendationJworg", FieldType::Bool),
+    field("calendarUId", FieldType::Text),
+    field("narrowMargin", FieldType::Bool),
+    field("calendarSequence", FieldType::Number),
+    field("pdfFilePathS303", FieldType::Text),
+    field("pdfFilePathVisitPackage", FieldType::Text),
+    field("pdfFilePathAppointmentLetter", FieldType::Text),
+    field("pdfFilePathBOEAgenda", FieldType::Text),
+    field("pdfFilePathBOEAgendaPersonal", FieldType::Text),
+    field("externalAttachmentsCustomFill", FieldType::Array),
+    field("agendaPrayerOpenBrother", FieldType::Any),
+    field("agendaPrayerCloseBrother", FieldType::Any),
+    field("pubsBSsMonth1", FieldType::Number),
+    field("pubsBSsMonth2", FieldType::Number),
+    field("pubsBSsMonth3", FieldType::Number),
+    field("pubsBSsMonth4", FieldType::Number),
+    field("pubsBSsMonth5", FieldType::Number),
+    field("pubsBSsMonth6", FieldType::Number),
+    field("pubsReportingMonth1", FieldType::Number),
+    field("pubsReportingMonth2", FieldType::Number),
+    field("pubsReportingMonth3", FieldType::Number),
+    field("pubsReportingMonth4", FieldType::Number),
+    field("pubsReportingMonth5", FieldType::Number),
+    field("pubsReportingMonth6", FieldType::Number),
+    field("auxPiosHoursMonth1", FieldType::Number),
+    field("auxPiosHoursMonth2", FieldType::Number),
+    field("auxPiosHoursMonth3", FieldType::Number),
+    field("auxPiosHoursMonth4", FieldType::Number),
+    field("auxPiosHoursMonth5", FieldType::Number),
+    field("auxPiosHoursMonth6", FieldType::Number),
+    field("auxPiosBSsMonth1", FieldType::Number),
+    field("auxPiosBSsMonth2", FieldType::Number),
+    field("auxPiosBSsMonth3", FieldType::Number),
+    field("auxPiosBSsMonth4", FieldType::Number),
+    field("auxPiosBSsMonth5", FieldType::Number),
+    field("auxPiosBSsMonth6", FieldType::Number),
+    field("auxPiosReportingMonth1", FieldType::Number),
+    field("auxPiosReportingMonth2", FieldType::Number),
+    field("auxPiosReportingMonth3", FieldType::Number),
+    field("auxPiosReportingMonth4", FieldType::Number),
+    field("auxPiosReportingMonth5", FieldType::Number),
+    field("auxPiosReportingMonth6", FieldType::Number),
+    field("piosHoursMonth1", FieldType::Number),
+    field("piosHoursMonth2", FieldType::Number),
+    field("piosHoursMonth3", FieldType::Number),
+    field("piosHoursMonth4", FieldType::Number),
+    field("piosHoursMonth5", FieldType::Number),
+    field("piosHoursMonth6", FieldType::Number),
+    field("piosBSsMonth1", FieldType::Number),
+    field("piosBSsMonth2", FieldType::Number),
+    field("piosBSsMonth3", FieldType::Number),
+    field("piosBSsMonth4", FieldType::Number),
+    field("piosBSsMonth5", FieldType::Number),
+    field("piosBSsMonth6", FieldType::Number),
+    field("piosReportingMonth1", FieldType::Number),
+    field("piosReportingMonth2", FieldType::Number),
+    field("piosReportingMonth3", FieldType::Number),
+    field("piosReportingMonth4", FieldType::Number),
+    field("piosReportingMonth5", FieldType::Number),
+    field("piosReportingMonth6", FieldType::Number),
+    field("activePublisherCountMonth1", FieldType::Number),
+    field("activePublisherCountMonth2", FieldType::Number),
+    field("activePublisherCountMonth3", FieldType::Number),
+    field("activePublisherCountMonth4", FieldType::Number),
+    field("activePublisherCountMonth5", FieldType::Number),
+    field("activePublisherCountMonth6", FieldType::Number),
+    field("altS88meetingMidweekCountMonth1", FieldType::Number),
+    field("altS88meetingMidweekTotalMonth1", FieldType::Number),
+    field("altS88meetingMidweekAverageMonth1", FieldType::Number),
+    field("altS88meetingMidweekCountMonth2", FieldType::Number),
+    field("altS88meetingMidweekTotalMonth2", FieldType::Number),
+    field("altS88meetingMidweekAverageMonth2", FieldType::Number),
+    field("altS88meetingMidweekCountMonth3", FieldType::Number),
+    field("altS88meetingMidweekTotalMonth3", FieldType::Number),
+    field("altS88meetingMidweekAverageMonth3", FieldType::Number),
+    field("altS88meetingMidweekCountMonth4", FieldType::Number),
+    field("altS88meetingMidweekTotalMonth4", FieldType::Number),
+    field("altS88meetingMidweekAverageMonth4", FieldType::Number),
+    field("altS88meetingMidweekCountMonth5", FieldType::Number),
+    field("altS88meetingMidweekTotalMonth5", FieldType::Number),
+    field("altS88meetingMidweekAverageMonth5", FieldType::Number),
+    field("altS88meetingMidweekCountMonth6", FieldType::Number),
+    field("altS88meetingMidweekTotalMonth6", FieldType::Number),
+    field("altS88meetingMidweekAverageMonth6", FieldType::Number),
+    field("altS88meetingWeekendCountMonth1", FieldType::Number),
+    field("altS88meetingWeekendTotalMonth1", FieldType::Number),
+    field("altS88meetingWeekendAverageMonth1", FieldType::Number),
+    field("altS88meetingWeekendCountMonth2", FieldType::Number),
+    field("altS88meetingWeekendTotalMonth2", FieldType::Number),
+    field("altS88meetingWeekendAverageMonth2", FieldType::Number),
+    field("altS88meetingWeekendCountMonth3", FieldType::Number),
+    field("altS88meetingWeekendTotalMonth3", FieldType::Number),
+    field("altS88meetingWeekendAverageMonth3", FieldType::Number),
+    field("altS88meetingWeekendCountMonth4", FieldType::Number),
+    field("altS88meetingWeekendTotalMonth4", FieldType::Number),
+    field("altS88meetingWeekendAverageMonth4", FieldType::Number),
+    field("altS88meetingWeekendCountMonth5", FieldType::Number),
+    field("altS88meetingWeekendTotalMonth5", FieldType::Number),
+    field("altS88meetingWeekendAverageMonth5", FieldType::Number),
+    field("altS88meetingWeekendCountMonth6", FieldType::Number),
+    field("altS88meetingWeekendTotalMonth6", FieldType::Number),
+    field("altS88meetingWeekendAverageMonth6", FieldType::Number),
+    field("routingEventId", FieldType::Text),
+];
+
+/// Every model the frontend defines, keyed by model name
+pub const MODELS: &[ModelSchema] = &[
+    ModelSchema { name: "AssemblyOrganizationObject", fields: ASSEMBLY_ORGANIZATION_FIELDS },
+    ModelSchema { name: "BranchOfficeObject", fields: BRANCH_OFFICE_FIELDS },
+    ModelSchema { name: "ChecklistItemObject", fields: CHECKLIST_ITEM_FIELDS },
+    ModelSchema { name: "ChecklistObject", fields: CHECKLIST_FIELDS },
+    ModelSchema { name: "CircuitEventObject", fields: CIRCUIT_EVENT_FIELDS },
+    ModelSchema { name: "CircuitEventPartObject", fields: CIRCUIT_EVENT_PART_FIELDS },
+    ModelSchema { name: "CircuitObject", fields: CIRCUIT_FIELDS },
+    ModelSchema { name: "CircuitOverseerObject", fields: CIRCUIT_OVERSEER_FIELDS },
+    ModelSchema { name: "CongregationObject", fields: CONGREGATION_FIELDS },
+    ModelSchema { name: "ConventionObject", fields: CONVENTION_FIELDS },
+    ModelSchema { name: "ConventionPartObject", fields: CONVENTION_PART_FIELDS },
+    ModelSchema { name: "EditorTemplateObject", fields: EDITOR_TEMPLATE_FIELDS },
+    ModelSchema { name: "EmailObject", fields: EMAIL_FIELDS },
+    ModelSchema { name: "ExpenseReportObject", fields: EXPENSE_REPORT_FIELDS },
+    ModelSchema { name: "LetterObject", fields: LETTER_FIELDS },
+    ModelSchema { name: "PersonObject", fields: PERSON_FIELDS },
+    ModelSchema { name: "PQRObject", fields: PQR_FIELDS },
+    ModelSchema { name: "PssClassWorkshopObject", fields: PSS_CLASS_WORKSHOP_FIELDS },
+    ModelSchema { name: "PssObject", fields: PSS_FIELDS },
+    ModelSchema { name: "RecommendationObject", fields: RECOMMENDATION_FIELDS },
+    ModelSchema { name: "ReminderObject", fields: REMINDER_FIELDS },
+    ModelSchema { name: "RoutingObject", fields: ROUTING_FIELDS },
+    ModelSchema { name: "SceClassAssignmentObject", fields: SCE_CLASS_ASSIGNMENT_FIELDS },
+    ModelSchema { name: "SceObject", fields: SCE_FIELDS },
+    ModelSchema { name: "UserObject", fields: USER_FIELDS },
+    ModelSchema { name: "VenueObject", fields: VENUE_FIELDS },
+    ModelSchema { name: "VisitObject", fields: VISIT_FIELDS },
+];
+
+pub fn model_schema(model_name: &str) -> Option<&'static ModelSchema> {
+    MODELS.iter().find(|schema| schema.name == model_name)
+}
+
+pub fn is_date(text: &str) -> bool {
+    NaiveDate::parse_from_str(text, "%Y-%m-%d").is_ok()
+        || NaiveDate::parse_from_str(text, "%Y/%m/%d").is_ok()
+        || DateTime::parse_from_rfc3339(text).is_ok()
+        || NaiveDateTime::parse_from_str(text, "%Y-%m-%dT%H:%M:%S%.f").is_ok()
+        || NaiveDateTime::parse_from_str(text, "%Y-%m-%dT%H:%M").is_ok()
+}
+
+/// null and "" mean "not set" for every field type
+fn is_empty(value: &Value) -> bool {
+    match value {
+        Value::Null => true,
+        Value::String(text) => text.trim().is_empty(),
+        _ => false,
+    }
+}
+
+/// Check one field's value, returning the problem if there is one
+fn check_field(field: &Field, value: Option<&Value>) -> Option<&'static str> {
+    let value = match value {
+        Some(value) if !is_empty(value) => value,
+        _ if field.required => return Some("Required"),
+        _ => return None,
+    };
+
+    match field.field_type {
+        FieldType::Bool if !value.is_boolean() => Some("Must be true or false"),
+        FieldType::Number if !value.is_number() => Some("Must be a number"),
+        FieldType::Text if !value.is_string() => Some("Must be text"),
+        FieldType::Date if !value.as_str().is_some_and(is_date) => Some("Must be a date (YYYY-MM-DD)"),
+        FieldType::Id if !(value.is_string() || value.is_number()) => Some("Must be a record id"),
+        FieldType::Array if !value.is_array() => Some("Must be a list"),
+        FieldType::Object if !value.is_object() => Some("Must be an object"),
+        FieldType::TimeZone if value.as_str().is_none_or(|name| time_zones::parse(name).is_err()) => {
+            Some("Must be a time zone (e.g. Europe/Berlin)")
+        }
+        _ => None,
+    }
+}
+
+/// Check a record against its model's fields.
+/// Fields the model does not define are left alone, so legacy data survives a round trip.
+/// Records of models without a schema are not checked.
+pub fn validate(model_name: &str, record: &Value) -> Vec<FieldError> {
+    let fields = match record.as_object() {
+        Some(fields) => fields,
+        None => {
+            return vec![FieldError {
+                field: String::new(),
+                message: "Record must be a JSON object".to_string(),
+            }]
+        }
+    };
+    let schema = match model_schema(model_name) {
+        Some(schema) => schema,
+        None => return Vec::new(),
+    };
+
+    schema
+        .fields
+        .iter()
+        .filter_map(|field| {
+            check_field(field, fields.get(field.name)).map(|message| FieldError {
+                field: field.name.to_string(),
+                message: message.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// `validate` for write paths: all problems in one error message
+pub fn check_record(model_name: &str, record: &Value) -> Result<(), String> {
+    let errors = validate(model_name, record);
+    if errors.is_empty() {
+        return Ok(());
+    }
+
+    let problems = errors
+        .iter()
+        .map(|error| format!("{}: {}", error.field, error.message))
+        .collect::<Vec<_>>()
+        .join("; ");
+    match record.get("id").and_then(Value::as_str) {
+        Some(id) => Err(format!("Invalid {} record {}: {}", model_name, id, problems)),
+        None => Err(format!("Invalid {} record: {}", model_name, problems)),
+    }
+}
+
+/// The fields of a model and their types, or null for an unknown model
+#[tauri::command]
+pub fn get_model_schema(model_name: String) -> Option<&'static ModelSchema> {
+    model_schema(&model_name)
+}
+
+/// Check a record without saving it; returns one entry per invalid field
+#[tauri::command]
+pub fn validate_record(model_name: String, record: Value) -> Result<Vec<FieldError>, String> {
+    sandbox::model_name(&model_name)?;
+    Ok(validate(&model_name, &record))
+}
@@ -0,0 +1,124 @@
+use rusqlite::{params_from_iter, types::Value as SqlValue, Connection};
+use serde::Serialize;
+use tauri::State;
+use crate::commands::DbConnection;
+use crate::sandbox;
+
+/// Default number of hits returned by `search`
+const DEFAULT_LIMIT: u32 = 50;
+
+/// A record that matched a search, best matches first
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchHit {
+    pub model_name: String,
+    pub record_id: String,
+    pub title: String,
+    /// Matching text with the matched words wrapped in `[` and `]`
+    pub snippet: String,
+    pub rank: f64,
+}
+
+/// Turn free text into an FTS5 query.
+/// Every word must appear (as a word prefix); FTS5 operators typed by the user are treated as text.
+fn match_expression(text: &str) -> Option<String> {
+    let terms: Vec<String> = text
+        .split_whitespace()
+        .map(|word| word.replace('"', ""))
+        .filter(|word| !word.is_empty())
+        .map(|word| format!("\"{}\"*", word))
+        .collect();
+
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" "))
+    }
+}
+
+/// Build the search index again from the stored records
+pub fn rebuild_index(conn: &Connection) -> Result<(), String> {
+    let tx = conn
+        .unchecked_transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    tx.execute_batch(
+        "DELETE FROM search_index;
+         INSERT INTO search_index (rowid, model_name, record_id, title, body)
+             SELECT doc_id, model_name, record_id, title, body FROM search_documents;",
+    )
+    .map_err(|e| format!("Failed to rebuild search index: {}", e))?;
+    tx.commit()
+        .map_err(|e| format!("Failed to commit search index: {}", e))
+}
+
+/// Search names, notes, letters and congregation names across all models.
+/// Matching ignores case and diacritics; matches in names rank above matches in notes.
+pub fn search_records(
+    conn: &Connection,
+    text: &str,
+    models: &[String],
+    limit: u32,
+) -> Result<Vec<SearchHit>, String> {
+    let expression = match match_expression(text) {
+        Some(expression) => expression,
+        None => return Ok(Vec::new()),
+    };
+
+    let mut params = vec![SqlValue::Text(expression)];
+    let mut model_filter = String::new();
+    if !models.is_empty() {
+        model_filter = format!(" AND model_name IN ({})", vec!["?"; models.len()].join(", "));
+        params.extend(models.iter().map(|m| SqlValue::Text(m.clone())));
+    }
+    params.push(SqlValue::Integer(i64::from(limit)));
+
+    let sql = format!(
+        "SELECT model_name, record_id, title,
+                snippet(search_index, -1, '[', ']', '…', 12),
+                bm25(search_index, 0.0, 0.0, 10.0, 1.0) AS rank
+         FROM search_index
+         WHERE search_index MATCH ?{}
+         ORDER BY rank
+         LIMIT ?",
+        model_filter
+    );
+
+    let mut stmt = conn
+        .prepare(&sql)
+        .map_err(|e| format!("Failed to prepare search: {}", e))?;
+    let rows = stmt
+        .query_map(params_from_iter(params.iter()), |row| {
+            Ok(SearchHit {
+                model_name: row.get(0)?,
+                record_id: row.get(1)?,
+                title: row.get(2)?,
+                snippet: row.get(3)?,
+                rank: row.get(4)?,
+            })
+        })
+        .map_err(|e| format!("Failed to search: {}", e))?;
+
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to read search result: {}", e))
+}
+
+/// Full-text search across all models (or only `models`, if given)
+#[tauri::command]
+pub async fn search(
+    db: State<'_, DbConnection>,
+    query: String,
+    models: Option<Vec<String>>,
+    limit: Option<u32>,
+) -> Result<Vec<SearchHit>, String> {
+    let models = models.unwrap_or_default();
+    sandbox::model_names(&models)?;
+    db.read(move |conn| {
+        search_records(
+            conn,
+            &query,
+            &models,
+            limit.unwrap_or(DEFAULT_LIMIT),
+        )
+    })
+    .await
+}
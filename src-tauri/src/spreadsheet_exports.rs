@@ -0,0 +1,301 @@
+use chrono::{Datelike, NaiveDate};
+use rust_xlsxwriter::{ExcelDateTime, Format, Workbook};
+use serde::Deserialize;
+use serde_json::Value;
+use std::borrow::Cow;
+use std::path::PathBuf;
+use tauri::{AppHandle, State};
+use crate::commands::DbConnection;
+use crate::exports::get_export_directory;
+use crate::files::write_atomic;
+use crate::query::{self, ModelQuery};
+use crate::schema::{self, FieldType};
+use crate::sandbox;
+
+/// A column of an exported spreadsheet
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Column {
+    /// Field to export; nested fields are addressed with dots, e.g. `licenseInfo.number`
+    /// or `_expanded.congregation.name` when the query expands `congregation`
+    pub field: String,
+    /// Heading of the column; defaults to the field
+    pub header: Option<String>,
+}
+
+impl Column {
+    fn new(field: impl Into<String>) -> Self {
+        Column { field: field.into(), header: None }
+    }
+
+    fn header(&self) -> &str {
+        self.header.as_deref().unwrap_or(&self.field)
+    }
+}
+
+/// A spreadsheet cell, typed so XLSX files get real booleans, numbers and dates
+enum Cell {
+    Empty,
+    Bool(bool),
+    Number(serde_json::Number),
+    Date(NaiveDate),
+    Text(String),
+}
+
+/// The header row and the rows of an export
+struct Table {
+    headers: Vec<String>,
+    rows: Vec<Vec<Cell>>,
+}
+
+fn value_at<'a>(record: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.').try_fold(record, |value, key| value.get(key))
+}
+
+fn parse_date(text: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(text, "%Y-%m-%d")
+        .or_else(|_| NaiveDate::parse_from_str(text, "%Y/%m/%d"))
+        .ok()
+}
+
+fn scalar_text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(text) => Some(text.clone()),
+        Value::Number(number) => Some(number.to_string()),
+        Value::Bool(flag) => Some(flag.to_string()),
+        _ => None,
+    }
+}
+
+/// Flatten a field value into one cell.
+/// Lists of plain values are joined with `; `; other lists and objects are written as JSON.
+fn to_cell(value: Option<&Value>, is_date: bool) -> Cell {
+    match value {
+        None | Some(Value::Null) => Cell::Empty,
+        Some(Value::Bool(flag)) => Cell::Bool(*flag),
+        Some(Value::Number(number)) => Cell::Number(number.clone()),
+        Some(Value::String(text)) => match parse_date(text).filter(|_| is_date) {
+            Some(date) => Cell::Date(date),
+            None => Cell::Text(text.clone()),
+        },
+        Some(Value::Array(items)) => match items.iter().map(scalar_text).collect::<Option<Vec<_>>>() {
+            Some(texts) => Cell::Text(texts.join("; ")),
+            None => Cell::Text(Value::Array(items.clone()).to_string()),
+        },
+        Some(object) => Cell::Text(object.to_string()),
+    }
+}
+
+/// All fields of a model in template order, with object fields split into one
+/// column per key found in the records (e.g. `licenseInfo.number`)
+fn default_columns(model_name: &str, records: &[Value]) -> Vec<Column> {
+    let mut columns = vec![Column::new("id")];
+    let fields = schema::model_schema(model_name).map(|schema| schema.fields).unwrap_or_default();
+    for field in fields {
+        let mut keys: Vec<&String> = Vec::new();
+        if field.field_type == FieldType::Object {
+            for key in records.iter().filter_map(|r| r.get(field.name)?.as_object()).flat_map(|o| o.keys()) {
+                if !keys.contains(&key) {
+                    keys.push(key);
+                }
+            }
+        }
+        if keys.is_empty() {
+            columns.push(Column::new(field.name));
+        } else {
+            columns.extend(keys.into_iter().map(|key| Column::new(format!("{}.{}", field.name, key))));
+        }
+    }
+    columns
+}
+
+/// Query a model's records and lay them out as rows of the chosen columns
+fn build_table(
+    conn: &rusqlite::Connection,
+    model_name: &str,
+    query: &ModelQuery,
+    columns: Option<Vec<Column>>,
+) -> Result<Table, String> {
+    let records = query::run_query(conn, model_name, query)?.records;
+    let columns = match columns {
+        Some(columns) if columns.iter().any(|column| column.field.trim().is_empty()) => {
+            return Err("Column fields must not be empty".to_string())
+        }
+        Some(columns) if !columns.is_empty() => columns,
+        _ => default_columns(model_name, &records),
+    };
+
+    let date_fields: Vec<&str> = schema::model_schema(model_name)
+        .map(|schema| schema.fields)
+        .unwrap_or_default()
+        .iter()
+        .filter(|field| field.field_type == FieldType::Date)
+        .map(|field| field.name)
+        .collect();
+    let rows = records
+        .iter()
+        .map(|record| {
+            columns
+                .iter()
+                .map(|column| to_cell(value_at(record, &column.field), date_fields.contains(&column.field.as_str())))
+                .collect()
+        })
+        .collect();
+
+    Ok(Table {
+        headers: columns.iter().map(|column| column.header().to_string()).collect(),
+        rows,
+    })
+}
+
+/// Spreadsheet apps run text starting with `=`, `@`, `+` or `-` as a formula, and some still do
+/// after a leading tab or carriage return. Such text is prefixed with `'`, except plain numbers
+/// like `-12.5` and phone numbers like `+1 555-1234`.
+fn neutralize_formula(text: &str) -> Cow<'_, str> {
+    let is_number = |text: &str| {
+        let digits = text.strip_prefix(['+', '-']).unwrap_or(text);
+        let mut parts = digits.splitn(2, ['.', ',']);
+        parts.all(|part| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit()))
+    };
+    // A `+`, a digit, then digits and the usual separators, with enough digits to dial
+    let is_phone_number = |text: &str| {
+        text.strip_prefix('+').is_some_and(|rest| {
+            rest.starts_with(|c: char| c.is_ascii_digit())
+                && rest.chars().all(|c| c.is_ascii_digit() || " -().".contains(c))
+                && rest.chars().filter(char::is_ascii_digit).count() >= 7
+        })
+    };
+    match text.chars().next() {
+        Some('=') | Some('@') | Some('\t') | Some('\r') => Cow::Owned(format!("'{}", text)),
+        Some('+') | Some('-') if !is_number(text) && !is_phone_number(text) => Cow::Owned(format!("'{}", text)),
+        _ => Cow::Borrowed(text),
+    }
+}
+
+fn csv_bytes(table: &Table) -> Result<Vec<u8>, String> {
+    // A byte order mark makes Excel read the file as UTF-8
+    let mut writer = csv::Writer::from_writer(b"\xEF\xBB\xBF".to_vec());
+    writer
+        .write_record(&table.headers)
+        .map_err(|e| format!("Failed to write CSV: {}", e))?;
+    for row in &table.rows {
+        let fields = row.iter().map(|cell| match cell {
+            Cell::Empty => Cow::Borrowed(""),
+            Cell::Bool(flag) => Cow::Owned(flag.to_string()),
+            Cell::Number(number) => Cow::Owned(number.to_string()),
+            Cell::Date(date) => Cow::Owned(date.format("%Y-%m-%d").to_string()),
+            Cell::Text(text) => neutralize_formula(text),
+        });
+        writer
+            .write_record(fields.map(|field| field.into_owned()))
+            .map_err(|e| format!("Failed to write CSV: {}", e))?;
+    }
+    writer
+        .into_inner()
+        .map_err(|e| format!("Failed to write CSV: {}", e))
+}
+
+fn xlsx_bytes(table: &Table, sheet_name: &str) -> Result<Vec<u8>, String> {
+    let failed = |e: rust_xlsxwriter::XlsxError| format!("Failed to write XLSX: {}", e);
+    let header_format = Format::new().set_bold();
+    let date_format = Format::new().set_num_format("yyyy-mm-dd");
+
+    let mut workbook = Workbook::new();
+    let sheet = workbook.add_worksheet();
+    // Sheet names are limited to 31 characters
+    sheet.set_name(sheet_name.chars().take(31).collect::<String>()).map_err(failed)?;
+    for (col, header) in table.headers.iter().enumerate() {
+        sheet.write_string_with_format(0, col as u16, header, &header_format).map_err(failed)?;
+    }
+    for (index, row) in table.rows.iter().enumerate() {
+        let row_num = index as u32 + 1;
+        for (col, cell) in row.iter().enumerate() {
+            let col = col as u16;
+            match cell {
+                Cell::Empty => continue,
+                Cell::Bool(flag) => sheet.write_boolean(row_num, col, *flag),
+                Cell::Number(number) => sheet.write_number(row_num, col, number.as_f64().unwrap_or_default()),
+                Cell::Date(date) => {
+                    // Excel only has dates from 1900 through 9999; others are written as text
+                    let excel_date = u16::try_from(date.year())
+                        .ok()
+                        .and_then(|year| ExcelDateTime::from_ymd(year, date.month() as u8, date.day() as u8).ok());
+                    match excel_date {
+                        Some(excel_date) => sheet.write_datetime_with_format(row_num, col, &excel_date, &date_format),
+                        None => sheet.write_string(row_num, col, date.format("%Y-%m-%d").to_string()),
+                    }
+                }
+                Cell::Text(text) => sheet.write_string(row_num, col, text),
+            }
+            .map_err(failed)?;
+        }
+    }
+    sheet.set_freeze_panes(1, 0).map_err(failed)?;
+    sheet.autofit();
+
+    workbook.save_to_buffer().map_err(failed)
+}
+
+/// `Person-20251109-143000.csv` for `PersonObject`
+fn export_path(app: &AppHandle, model_name: &str, extension: &str) -> Result<PathBuf, String> {
+    let base = model_name.strip_suffix("Object").unwrap_or(model_name);
+    let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S");
+    Ok(get_export_directory(app)?.join(format!("{}-{}.{}", base, stamp, extension)))
+}
+
+/// Export a model's records as a CSV file in the export directory.
+/// `query` filters and sorts the records (see `query_model_data`); `columns` picks
+/// and orders the columns, defaulting to every field. Returns the file path.
+#[tauri::command]
+pub async fn export_model_csv(
+    app: AppHandle,
+    db: State<'_, DbConnection>,
+    model_name: String,
+    query: Option<ModelQuery>,
+    columns: Option<Vec<Column>>,
+) -> Result<String, String> {
+    sandbox::model_name(&model_name)?;
+    db.read(move |conn| {
+        let table = build_table(conn, &model_name, &query.unwrap_or_default(), columns)?;
+        let path = export_path(&app, &model_name, "csv")?;
+        write_atomic(&path, &csv_bytes(&table)?)?;
+        Ok(path.to_string_lossy().to_string())
+    })
+    .await
+}
+
+/// Export a model's records as an Excel workbook in the export directory.
+/// Takes the same `query` and `columns` as `export_model_csv`. Returns the file path.
+#[tauri::command]
+pub async fn export_model_xlsx(
+    app: AppHandle,
+    db: State<'_, DbConnection>,
+    model_name: String,
+    query: Option<ModelQuery>,
+    columns: Option<Vec<Column>>,
+) -> Result<String, String> {
+    sandbox::model_name(&model_name)?;
+    db.read(move |conn| {
+        let table = build_table(conn, &model_name, &query.unwrap_or_default(), columns)?;
+        let sheet_name = model_name.strip_suffix("Object").unwrap_or(&model_name);
+        let path = export_path(&app, &model_name, "xlsx")?;
+        write_atomic(&path, &xlsx_bytes(&table, sheet_name)?)?;
+        Ok(path.to_string_lossy().to_string())
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formulas_are_prefixed() {
+        for formula in ["=1+1", "@SUM(A1)", "+(2)-3", "-1-1", "+1-1", "-2+3", "\t=1+1", "\r=1+1", "+cmd|' /C calc'!A0"] {
+            assert_eq!(neutralize_formula(formula), format!("'{}", formula));
+        }
+        for plain in ["-12", "+3", "-12.5", "-1,5", "+1 555-1234", "+49 (30) 123.456", "Anna", "1-1"] {
+            assert_eq!(neutralize_formula(plain), plain);
+        }
+    }
+}
@@ -0,0 +1,682 @@
+use calamine::{Data, Reader};
+use chrono::{Datelike, NaiveDate, Timelike};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use tauri::{AppHandle, State};
+use crate::commands::DbConnection;
+use crate::importer::{self, ImportPreview, ImportResult, Resolution};
+use crate::relations::{self, Relation};
+use crate::schema::{self, Field, FieldError, FieldType};
+use crate::{db, sandbox, time_zones};
+
+/// Common column headings for model fields, compared without case, spaces or punctuation.
+/// A heading that is the field name itself always matches, so only other wordings are listed.
+const HEADER_ALIASES: &[(&str, &[&str])] = &[
+    ("nameFirst", &["firstname", "first", "givenname", "forename", "vorname", "prenom", "nombre"]),
+    ("nameMiddle", &["middlename", "middle", "secondname"]),
+    ("nameLast", &["lastname", "last", "surname", "familyname", "nachname", "nom", "apellido", "apellidos"]),
+    ("nameNickname", &["nickname", "preferredname", "rufname"]),
+    ("nameSuffix", &["suffix"]),
+    ("gender", &["sex", "geschlecht", "sexo", "sexe"]),
+    ("dateBirth", &["birthdate", "birthday", "dateofbirth", "born", "dob", "geburtsdatum", "fechadenacimiento", "datedenaissance"]),
+    ("dateBaptism", &["baptismdate", "baptism", "baptized", "dateofbaptism", "taufdatum", "taufe", "fechadebautismo", "datedebapteme"]),
+    ("isUnbaptized", &["unbaptized", "unbaptizedpublisher", "ungetauft"]),
+    ("privilegeElder", &["elder", "aeltester", "ältester", "anciano", "ancien"]),
+    ("privilegeMS", &["ministerialservant", "ms", "dienstamtgehilfe", "siervoministerial", "assistantministeriel"]),
+    ("privilegePioneer", &["regularpioneer", "pioneer", "rp", "pionier", "allgemeinerpionier", "precursorregular", "pionnierpermanent"]),
+    ("privilegePioneerSpecial", &["specialpioneer", "sp", "sonderpionier", "precursorespecial", "pionnierspecial"]),
+    ("pioneerStartDate", &["pioneerdate", "pioneersince", "pioneerappointed"]),
+    ("inactive", &["inaktiv", "inactivo", "inactif"]),
+    ("emailMain", &["email", "emailaddress", "mail", "correo", "courriel"]),
+    ("phoneMobile", &["mobile", "mobilephone", "cell", "cellphone", "handy", "movil", "celular", "portable"]),
+    ("phoneHome", &["phone", "homephone", "telephone", "telefon", "telefono", "telephonefixe"]),
+    ("addressHomeStreet", &["street", "address", "streetaddress", "strasse", "straße", "calle", "direccion", "adresse", "rue"]),
+    ("addressHomeCity", &["city", "town", "ort", "stadt", "wohnort", "ciudad", "ville"]),
+    ("addressHomeStateProvince", &["state", "province", "region", "bundesland", "provincia"]),
+    ("addressHomePostal", &["zip", "zipcode", "postalcode", "postcode", "plz", "codigopostal", "codepostal"]),
+    ("addressHomeCountry", &["country", "land", "pais", "pays"]),
+    ("congregation", &["congregationname", "versammlung", "congregacion"]),
+    ("name", &["congregation", "congregationname", "versammlung", "congregacion"]),
+    ("number", &["congregationnumber", "congno", "nummer", "numero", "no"]),
+    ("city", &["town", "ort", "stadt", "ciudad", "ville"]),
+    ("stateProvince", &["state", "province", "region", "bundesland", "provincia"]),
+    ("country", &["land", "pais", "pays"]),
+    ("language", &["sprache", "idioma", "langue"]),
+];
+
+const TRUE_WORDS: &[&str] = &["true", "yes", "y", "x", "1", "ja", "j", "si", "sí", "oui", "wahr", "✓", "✔"];
+const FALSE_WORDS: &[&str] = &["false", "no", "n", "0", "nein", "non", "falsch", "-"];
+
+/// How to read numeric dates like `03/04/2024`.
+/// Dates starting with a four-digit year are always read year-month-day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DateOrder {
+    DayMonthYear,
+    MonthDayYear,
+}
+
+/// Which spreadsheet column goes into which field
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ColumnMapping {
+    /// Position of the column, counting from 0
+    pub column: usize,
+    pub header: String,
+    /// Field the column is imported into; None leaves the column out
+    pub field: Option<String>,
+}
+
+/// The file to import and how to read it.
+/// Preview and apply must be given the same options.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpreadsheetOptions {
+    /// Path of a CSV or XLSX file in the export or data directory
+    pub file_path: String,
+    /// Worksheet to read from a workbook; defaults to the first one
+    pub sheet: Option<String>,
+    /// Defaults to the mapping proposed from the column headers
+    pub mapping: Option<Vec<ColumnMapping>>,
+    /// Defaults to the order the file's dates reveal, or day-month-year if none do
+    pub date_order: Option<DateOrder>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpreadsheetPreview {
+    /// Worksheets of a workbook; empty for CSV files
+    pub sheets: Vec<String>,
+    pub sheet: Option<String>,
+    pub headers: Vec<String>,
+    pub mapping: Vec<ColumnMapping>,
+    pub date_order: DateOrder,
+    /// Spreadsheet row (the header row being 1) of each import entry
+    pub rows: Vec<usize>,
+    /// Entries with errors; they must be skipped when applying
+    pub invalid: usize,
+    /// Matches against stored records, with cells that could not be read added to each entry's errors
+    pub import: ImportPreview,
+}
+
+/// The cells of one worksheet or CSV file as text
+struct Sheet {
+    names: Vec<String>,
+    name: Option<String>,
+    headers: Vec<String>,
+    /// Spreadsheet row number and cells of each non-empty row below the headers
+    rows: Vec<(usize, Vec<String>)>,
+}
+
+impl Sheet {
+    /// The first non-empty row holds the headers; empty rows are dropped
+    fn from_rows(names: Vec<String>, name: Option<String>, rows: Vec<(usize, Vec<String>)>) -> Result<Self, String> {
+        let mut rows = rows.into_iter().filter(|(_, cells)| cells.iter().any(|cell| !cell.is_empty()));
+        let (_, headers) = rows.next().ok_or_else(|| "The spreadsheet is empty".to_string())?;
+        Ok(Sheet { names, name, headers, rows: rows.collect() })
+    }
+}
+
+/// Excel on Windows saves CSV files in the ANSI code page rather than UTF-8
+fn decode(bytes: &[u8]) -> String {
+    let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes);
+    match std::str::from_utf8(bytes) {
+        Ok(text) => text.to_string(),
+        Err(_) => encoding_rs::WINDOWS_1252.decode(bytes).0.into_owned(),
+    }
+}
+
+/// Excel writes `;` instead of `,` where the comma is the decimal separator
+fn detect_delimiter(text: &str) -> u8 {
+    let first_line = text.lines().find(|line| !line.trim().is_empty()).unwrap_or_default();
+    [b',', b';', b'\t']
+        .into_iter()
+        .max_by_key(|&delimiter| first_line.matches(delimiter as char).count())
+        .filter(|&delimiter| first_line.contains(delimiter as char))
+        .unwrap_or(b',')
+}
+
+/// Undo the `'` that CSV exports put before text a spreadsheet app would run as a formula
+fn unescape_formula(text: &str) -> &str {
+    match text.strip_prefix('\'') {
+        Some(rest) if rest.starts_with(['=', '+', '-', '@', '\t', '\r']) => rest,
+        _ => text,
+    }
+}
+
+fn read_csv(path: &Path) -> Result<Sheet, String> {
+    let bytes = fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let text = decode(&bytes);
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(detect_delimiter(&text))
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(text.as_bytes());
+
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let line = record.position().map_or(rows.len() + 1, |position| position.line() as usize);
+        rows.push((line, record.iter().map(|cell| unescape_formula(cell.trim()).to_string()).collect()));
+    }
+    Sheet::from_rows(Vec::new(), None, rows)
+}
+
+fn cell_text(cell: &Data) -> String {
+    match cell {
+        Data::Empty | Data::Error(_) => String::new(),
+        Data::String(text) | Data::DateTimeIso(text) | Data::DurationIso(text) => text.trim().to_string(),
+        Data::Int(number) => number.to_string(),
+        Data::Float(number) => number.to_string(),
+        Data::Bool(flag) => flag.to_string(),
+        Data::DateTime(date) => match date.as_datetime() {
+            Some(date) if date.num_seconds_from_midnight() == 0 => date.format("%Y-%m-%d").to_string(),
+            Some(date) => date.format("%Y-%m-%dT%H:%M:%S").to_string(),
+            None => date.as_f64().to_string(),
+        },
+    }
+}
+
+fn read_workbook(path: &Path, sheet: Option<&str>) -> Result<Sheet, String> {
+    let mut workbook = calamine::open_workbook_auto(path)
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let names = workbook.sheet_names();
+    let name = match sheet {
+        Some(sheet) => names
+            .iter()
+            .find(|name| *name == sheet)
+            .ok_or_else(|| format!("The workbook has no worksheet named {}", sheet))?,
+        None => names.first().ok_or_else(|| "The workbook has no worksheets".to_string())?,
+    }
+    .clone();
+    let range = workbook
+        .worksheet_range(&name)
+        .map_err(|e| format!("Failed to read worksheet {}: {}", name, e))?;
+
+    // The range starts at the first used cell rather than at A1
+    let (first_row, first_column) = range.start().unwrap_or((0, 0));
+    let rows = range
+        .rows()
+        .enumerate()
+        .map(|(index, cells)| {
+            let mut texts = vec![String::new(); first_column as usize];
+            texts.extend(cells.iter().map(cell_text));
+            (first_row as usize + index + 1, texts)
+        })
+        .collect();
+    Sheet::from_rows(names, Some(name), rows)
+}
+
+fn read_sheet(path: &Path, sheet: Option<&str>) -> Result<Sheet, String> {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default()
+        .to_lowercase();
+    match extension.as_str() {
+        "csv" | "tsv" | "txt" => read_csv(path),
+        "xlsx" | "xlsm" | "xls" | "ods" => read_workbook(path, sheet),
+        _ => Err(format!("Cannot import .{} files; use a CSV or XLSX file", extension)),
+    }
+}
+
+/// A header in lower case without spaces or punctuation, e.g. `First Name` -> `firstname`
+fn normalize_header(header: &str) -> String {
+    header.chars().filter(|c| c.is_alphanumeric()).flat_map(char::to_lowercase).collect()
+}
+
+fn model_fields(model_name: &str) -> &'static [Field] {
+    schema::model_schema(model_name).map(|schema| schema.fields).unwrap_or_default()
+}
+
+/// The field a column is mapped to, with the key inside it when the mapping is a path into
+/// an object field like `licenseInfo.number`, as spreadsheet exports name those columns
+fn mapped_field<'a, 'b>(fields: &'a [Field], target: &'b str) -> Option<(&'a Field, Option<&'b str>)> {
+    match target.split_once('.') {
+        Some((name, key)) => {
+            let field = fields.iter().find(|field| field.name == name && field.field_type == FieldType::Object)?;
+            let key = Some(key).filter(|key| !key.trim().is_empty() && !key.contains('.'))?;
+            Some((field, Some(key)))
+        }
+        None => fields.iter().find(|field| field.name == target).map(|field| (field, None)),
+    }
+}
+
+/// Guess the field of every column from its header: a path into an object field, the field's
+/// own name, then the usual wordings in `HEADER_ALIASES`. Each field is given to one column at most.
+fn propose_mapping(model_name: &str, headers: &[String]) -> Vec<ColumnMapping> {
+    let fields = model_fields(model_name);
+    let mut used: Vec<&str> = Vec::new();
+    let mut used_paths: Vec<&str> = Vec::new();
+    headers
+        .iter()
+        .enumerate()
+        .map(|(column, header)| {
+            let path = header.trim();
+            if mapped_field(fields, path).is_some_and(|(_, key)| key.is_some()) && !used_paths.contains(&path) {
+                used_paths.push(path);
+                return ColumnMapping { column, header: header.clone(), field: Some(path.to_string()) };
+            }
+            let key = normalize_header(header);
+            let by_name = fields.iter().map(|field| field.name).find(|name| normalize_header(name) == key);
+            let by_alias = || {
+                HEADER_ALIASES
+                    .iter()
+                    .filter(|(name, _)| fields.iter().any(|field| field.name == *name))
+                    .find(|(_, aliases)| aliases.contains(&key.as_str()))
+                    .map(|(name, _)| *name)
+            };
+            let field = by_name.or_else(by_alias).filter(|name| !key.is_empty() && !used.contains(name));
+            used.extend(field);
+            ColumnMapping { column, header: header.clone(), field: field.map(str::to_string) }
+        })
+        .collect()
+}
+
+/// Check a mapping from the frontend against the sheet and the model
+fn check_mapping(model_name: &str, headers: &[String], mapping: &[ColumnMapping]) -> Result<(), String> {
+    let fields = model_fields(model_name);
+    let mut used: Vec<(&str, Option<&str>)> = Vec::new();
+    for entry in mapping {
+        if entry.column >= headers.len() {
+            return Err(format!("The spreadsheet has no column {}", entry.column + 1));
+        }
+        let target = match entry.field.as_deref() {
+            Some(target) => target,
+            None => continue,
+        };
+        let (field, key) = mapped_field(fields, target).ok_or_else(|| format!("{} has no field {}", model_name, target))?;
+        // A whole object field and a path into it would both set it
+        if used.iter().any(|(name, used_key)| *name == field.name && (used_key.is_none() || key.is_none() || *used_key == key)) {
+            return Err(format!("More than one column is mapped to {}", target));
+        }
+        used.push((field.name, key));
+    }
+    Ok(())
+}
+
+/// The three numbers of a date like `03/04/2024` or `3.4.24`; None for other text
+fn date_parts(text: &str) -> Option<[u32; 3]> {
+    let parts: Vec<&str> = text.split(['/', '.', '-']).map(str::trim).filter(|part| !part.is_empty()).collect();
+    match parts.as_slice() {
+        [first, second, third] if parts.iter().all(|part| part.len() <= 4 && part.chars().all(|c| c.is_ascii_digit())) => {
+            Some([first.parse().ok()?, second.parse().ok()?, third.parse().ok()?])
+        }
+        _ => None,
+    }
+}
+
+/// Two-digit years are taken as this century unless that would be in the future
+fn full_year(year: u32, digits: usize) -> i32 {
+    if digits > 2 {
+        return year as i32;
+    }
+    let this_year = chrono::Local::now().year();
+    let century = this_year - this_year % 100;
+    if century + year as i32 <= this_year {
+        century + year as i32
+    } else {
+        century - 100 + year as i32
+    }
+}
+
+/// Read a date in one of the usual formats into `YYYY-MM-DD`:
+/// ISO dates, numeric dates in `order`, and English month names like `15 March 2024`
+fn parse_date(text: &str, order: DateOrder) -> Option<String> {
+    const NAMED_FORMATS: &[&str] = &["%d %B %Y", "%d %b %Y", "%B %d, %Y", "%b %d, %Y", "%d-%b-%Y", "%d. %B %Y"];
+    let date = match date_parts(text) {
+        Some([year, month, day]) if text.split(['/', '.', '-']).next()?.trim().len() == 4 => {
+            NaiveDate::from_ymd_opt(year as i32, month, day)
+        }
+        Some([first, second, year]) => {
+            let (day, month) = match order {
+                DateOrder::DayMonthYear => (first, second),
+                DateOrder::MonthDayYear => (second, first),
+            };
+            let digits = text.rsplit(['/', '.', '-']).next()?.trim().len();
+            NaiveDate::from_ymd_opt(full_year(year, digits), month, day)
+        }
+        None => NAMED_FORMATS.iter().find_map(|format| NaiveDate::parse_from_str(text, format).ok()),
+    };
+    date.map(|date| date.format("%Y-%m-%d").to_string())
+}
+
+/// The date order the file's own dates reveal: a first number above 12 can only be a day,
+/// a second one only a day in month-day-year order
+fn detect_date_order<'a>(dates: impl Iterator<Item = &'a str>) -> Option<DateOrder> {
+    let mut found = None;
+    for text in dates {
+        let order = match date_parts(text) {
+            Some([first, _, _]) if first > 31 => continue,
+            Some([first, second, _]) if first > 12 && second <= 12 => DateOrder::DayMonthYear,
+            Some([first, second, _]) if second > 12 && first <= 12 => DateOrder::MonthDayYear,
+            _ => continue,
+        };
+        if found.is_some_and(|found| found != order) {
+            return None;
+        }
+        found = Some(order);
+    }
+    found
+}
+
+/// Read a number with `.` or `,` as the decimal separator, whichever comes last.
+/// A single separator followed by three digits, as in `1,234` or `1.234`, may also group
+/// thousands; such numbers are refused rather than read one way or the other.
+fn parse_number(text: &str) -> Result<Value, String> {
+    let not_a_number = || format!("Not a number: {}", text);
+    let mut text: String = text.chars().filter(|c| !c.is_whitespace() && *c != '\'').collect();
+    if let [(position, separator)] = text.match_indices(['.', ',']).collect::<Vec<_>>()[..] {
+        let whole = text[..position].trim_start_matches(['-', '+']);
+        let fraction = &text[position + 1..];
+        let grouped = (1..=3).contains(&whole.len()) && !whole.starts_with('0') && whole.bytes().all(|b| b.is_ascii_digit());
+        if grouped && fraction.len() == 3 && fraction.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(format!(
+                "Ambiguous number: {} (the {} may separate thousands or decimals; write it without thousands separators)",
+                text, separator
+            ));
+        }
+    }
+    // Whichever of `.` and `,` comes last is the decimal separator; the other groups thousands.
+    // A separator that appears more than once, as in `1.234.567`, only groups thousands.
+    let decimal = text.rfind(['.', ',']).map(|position| text.as_bytes()[position]);
+    if decimal.is_some_and(|decimal| text.bytes().filter(|b| *b == decimal).count() > 1) {
+        text = text.replace(['.', ','], "");
+    } else if decimal == Some(b',') {
+        text = text.replace('.', "").replace(',', ".");
+    } else {
+        text = text.replace(',', "");
+    }
+    let number: f64 = text.parse().map_err(|_| not_a_number())?;
+    if number.fract() == 0.0 && number.abs() < i64::MAX as f64 {
+        Ok(Value::from(number as i64))
+    } else {
+        serde_json::Number::from_f64(number).map(Value::Number).ok_or_else(not_a_number)
+    }
+}
+
+/// Turn a cell into a value of the field's type.
+/// References are left as text here; `resolve_reference` turns them into ids.
+fn convert(field_type: FieldType, text: &str, order: DateOrder) -> Result<Value, String> {
+    let lower = text.to_lowercase();
+    match field_type {
+        FieldType::Bool if TRUE_WORDS.contains(&lower.as_str()) => Ok(Value::Bool(true)),
+        FieldType::Bool if FALSE_WORDS.contains(&lower.as_str()) => Ok(Value::Bool(false)),
+        FieldType::Bool => Err(format!("Not yes or no: {}", text)),
+        FieldType::Number => parse_number(text),
+        FieldType::Date if schema::is_date(text) => Ok(Value::String(text.to_string())),
+        FieldType::Date => parse_date(text, order)
+            .map(Value::String)
+            .ok_or_else(|| format!("Not a date: {}", text)),
+        FieldType::Array if text.starts_with('[') => match serde_json::from_str(text) {
+            Ok(Value::Array(items)) => Ok(Value::Array(items)),
+            _ => Err(format!("Not a list: {}", text)),
+        },
+        // Lists are exported joined with `; `
+        FieldType::Array => Ok(Value::Array(
+            text.split(';').map(str::trim).filter(|item| !item.is_empty()).map(Value::from).collect(),
+        )),
+        FieldType::Object => match serde_json::from_str(text) {
+            Ok(Value::Object(fields)) => Ok(Value::Object(fields)),
+            _ => Err(format!("Not a JSON object: {}", text)),
+        },
+        FieldType::TimeZone => time_zones::parse(text).map(|zone| Value::String(zone.name().to_string())),
+        FieldType::Text | FieldType::Id | FieldType::Any => Ok(Value::String(text.to_string())),
+    }
+}
+
+/// Find the record a reference cell points at, by id or by name
+/// (e.g. a congregation's name, or a person's first and last name)
+fn resolve_reference(target: &str, records: &[Value], text: &str) -> Result<String, String> {
+    if records.iter().any(|record| db::record_id_of(record).as_deref() == Some(text)) {
+        return Ok(text.to_string());
+    }
+    let fields = importer::name_fields(target);
+    let last = fields.last().ok_or_else(|| format!("{} records cannot be found by name", target))?;
+    let mut by_name = Map::new();
+    by_name.insert(last.to_string(), Value::from(text));
+    let key = importer::name_key(&Value::Object(by_name), &[last]);
+
+    let matches: Vec<String> = records
+        .iter()
+        .filter(|record| key.is_some() && importer::name_key(record, fields) == key)
+        .filter_map(db::record_id_of)
+        .collect();
+    match matches.as_slice() {
+        [id] => Ok(id.clone()),
+        [] => Err(format!("No {} named {}", target, text)),
+        _ => Err(format!("{} matches {} {} records", text, matches.len(), target)),
+    }
+}
+
+/// Rows may be shorter than the header row
+fn cell_at(cells: &[String], column: usize) -> &str {
+    cells.get(column).map(String::as_str).unwrap_or_default()
+}
+
+/// The sheet's rows as records, with one list of errors per row
+struct Converted {
+    date_order: DateOrder,
+    rows: Vec<usize>,
+    records: Vec<Value>,
+    errors: Vec<Vec<FieldError>>,
+}
+
+/// Turn every row into a record of the model through the mapping.
+/// Cells that cannot be read are left out of the record and reported as errors of their row.
+fn convert_rows(
+    conn: &Connection,
+    model_name: &str,
+    sheet: &Sheet,
+    mapping: &[ColumnMapping],
+    date_order: Option<DateOrder>,
+) -> Result<Converted, String> {
+    let fields = model_fields(model_name);
+    let columns: Vec<(usize, &Field, Option<&str>, Option<&Relation>)> = mapping
+        .iter()
+        .filter_map(|entry| {
+            let (field, key) = mapped_field(fields, entry.field.as_deref()?)?;
+            let relation = relations::RELATIONS.iter().find(|r| r.model == model_name && r.field == field.name && key.is_none());
+            Some((entry.column, field, key, relation))
+        })
+        .collect();
+
+    let date_order = date_order
+        .or_else(|| {
+            let dates = columns.iter().filter(|(_, field, key, _)| field.field_type == FieldType::Date && key.is_none());
+            detect_date_order(dates.flat_map(|&(column, ..)| sheet.rows.iter().map(move |(_, cells)| cell_at(cells, column))))
+        })
+        .unwrap_or(DateOrder::DayMonthYear);
+
+    // Records that reference cells may point at, loaded once per target model
+    let mut targets: HashMap<&str, Vec<Value>> = HashMap::new();
+    for relation in columns.iter().filter_map(|(.., relation)| *relation) {
+        if !targets.contains_key(relation.target) {
+            targets.insert(relation.target, db::read_records(conn, relation.target)?);
+        }
+    }
+
+    let mut converted = Converted { date_order, rows: Vec::new(), records: Vec::new(), errors: Vec::new() };
+    for (row, cells) in &sheet.rows {
+        let mut record = Map::new();
+        let mut errors = Vec::new();
+        for &(column, field, key, relation) in &columns {
+            let text = cell_at(cells, column);
+            if text.is_empty() {
+                continue;
+            }
+            // The keys of an object field are kept as text, as exports write them
+            if let Some(key) = key {
+                let object = record.entry(field.name).or_insert_with(|| Value::Object(Map::new()));
+                if let Some(object) = object.as_object_mut() {
+                    object.insert(key.to_string(), Value::from(text));
+                }
+                continue;
+            }
+            let value = match relation {
+                Some(relation) => resolve_reference(relation.target, &targets[relation.target], text).map(Value::String),
+                None => convert(field.field_type, text, date_order),
+            };
+            match value {
+                Ok(value) => {
+                    record.insert(field.name.to_string(), value);
+                }
+                Err(message) => errors.push(FieldError { field: field.name.to_string(), message }),
+            }
+        }
+        converted.rows.push(*row);
+        converted.records.push(Value::Object(record));
+        converted.errors.push(errors);
+    }
+    Ok(converted)
+}
+
+/// Read the file and convert its rows, using the given mapping or proposing one
+fn load(
+    app: &AppHandle,
+    conn: &Connection,
+    model_name: &str,
+    options: SpreadsheetOptions,
+) -> Result<(Sheet, Vec<ColumnMapping>, Converted), String> {
+    let path = sandbox::resolve_file(app, &options.file_path)?;
+    let sheet = read_sheet(&path, options.sheet.as_deref())?;
+    let mapping = match options.mapping {
+        Some(mapping) => {
+            check_mapping(model_name, &sheet.headers, &mapping)?;
+            mapping
+        }
+        None => propose_mapping(model_name, &sheet.headers),
+    };
+    let converted = convert_rows(conn, model_name, &sheet, &mapping, options.date_order)?;
+    Ok((sheet, mapping, converted))
+}
+
+/// Read a CSV or XLSX file and preview importing its rows into a model, without saving anything.
+/// Returns the proposed (or given) column mapping and each row's match and errors.
+#[tauri::command]
+pub async fn preview_spreadsheet_import(
+    app: AppHandle,
+    db: State<'_, DbConnection>,
+    model_name: String,
+    options: SpreadsheetOptions,
+) -> Result<SpreadsheetPreview, String> {
+    sandbox::model_name(&model_name)?;
+    db.read(move |conn| {
+        let (sheet, mapping, converted) = load(&app, conn, &model_name, options)?;
+
+        let mut import = importer::plan(conn, &model_name, converted.records)?;
+        for (item, errors) in import.items.iter_mut().zip(converted.errors) {
+            item.errors.extend(errors);
+        }
+        Ok(SpreadsheetPreview {
+            sheets: sheet.names,
+            sheet: sheet.name,
+            headers: sheet.headers,
+            mapping,
+            date_order: converted.date_order,
+            rows: converted.rows,
+            invalid: import.items.iter().filter(|item| !item.errors.is_empty()).count(),
+            import,
+        })
+    })
+    .await
+}
+
+/// Import the rows of a CSV or XLSX file as previewed by `preview_spreadsheet_import`.
+/// `revision` is the preview's; `resolutions` maps entry indexes to what to do with them,
+/// and every row with errors must be resolved as `skip`. Saved in one transaction.
+#[tauri::command]
+pub async fn apply_spreadsheet_import(
+    app: AppHandle,
+    db: State<'_, DbConnection>,
+    model_name: String,
+    options: SpreadsheetOptions,
+    revision: i64,
+    resolutions: Option<HashMap<usize, Resolution>>,
+) -> Result<ImportResult, String> {
+    sandbox::model_name(&model_name)?;
+    let resolutions = resolutions.unwrap_or_default();
+    db.write(move |conn| {
+        let (_, _, converted) = load(&app, conn, &model_name, options)?;
+
+        let unresolved = converted
+            .errors
+            .iter()
+            .enumerate()
+            .find(|(index, errors)| !errors.is_empty() && !matches!(resolutions.get(index), Some(Resolution::Skip)));
+        if let Some((index, errors)) = unresolved {
+            let problems: Vec<String> = errors.iter().map(|error| format!("{}: {}", error.field, error.message)).collect();
+            return Err(format!("Row {}: {}", converted.rows[index], problems.join("; ")));
+        }
+
+        let tx = conn
+            .unchecked_transaction()
+            .map_err(|e| format!("Failed to start transaction: {}", e))?;
+        let result = importer::apply(&tx, &model_name, converted.records, revision, &resolutions)?;
+        tx.commit().map_err(|e| format!("Failed to commit import: {}", e))?;
+        Ok(result)
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn strings(items: &[&str]) -> Vec<String> {
+        items.iter().map(|item| item.to_string()).collect()
+    }
+
+    #[test]
+    fn numbers_that_may_group_thousands_are_refused() {
+        assert_eq!(parse_number("1.234,5"), Ok(json!(1234.5)));
+        assert_eq!(parse_number("1,234.5"), Ok(json!(1234.5)));
+        assert_eq!(parse_number("1.234.567"), Ok(json!(1234567)));
+        assert_eq!(parse_number("2,5"), Ok(json!(2.5)));
+        assert_eq!(parse_number("0,125"), Ok(json!(0.125)));
+        assert_eq!(parse_number("1234,567"), Ok(json!(1234.567)));
+        for ambiguous in ["1,234", "1.234", "-12,500", "999.000"] {
+            let error = parse_number(ambiguous).unwrap_err();
+            assert!(error.starts_with("Ambiguous number"), "{}", error);
+        }
+        assert_eq!(parse_number("abc"), Err("Not a number: abc".to_string()));
+    }
+
+    #[test]
+    fn paths_into_object_fields_are_imported() {
+        let headers = strings(&["nameFirst", "licenseInfo.number", "licenseInfo.state"]);
+        let mapping = propose_mapping("UserObject", &headers);
+        let fields: Vec<Option<&str>> = mapping.iter().map(|entry| entry.field.as_deref()).collect();
+        assert_eq!(fields, [Some("nameFirst"), Some("licenseInfo.number"), Some("licenseInfo.state")]);
+        check_mapping("UserObject", &headers, &mapping).unwrap();
+
+        let conn = db::test_connection();
+        let rows = vec![(1, headers.clone()), (2, strings(&["Ann", "0042", "IL"])), (3, strings(&["Bob", "", ""]))];
+        let sheet = Sheet::from_rows(Vec::new(), None, rows).unwrap();
+        let converted = convert_rows(&conn, "UserObject", &sheet, &mapping, None).unwrap();
+        assert_eq!(converted.records[0], json!({"nameFirst": "Ann", "licenseInfo": {"number": "0042", "state": "IL"}}));
+        assert_eq!(converted.records[1], json!({"nameFirst": "Bob"}));
+        assert!(converted.errors.iter().all(Vec::is_empty));
+    }
+
+    #[test]
+    fn mappings_set_each_field_once() {
+        let headers = strings(&["a", "b"]);
+        let mapped = |first: &str, second: &str| {
+            vec![
+                ColumnMapping { column: 0, header: "a".to_string(), field: Some(first.to_string()) },
+                ColumnMapping { column: 1, header: "b".to_string(), field: Some(second.to_string()) },
+            ]
+        };
+        assert!(check_mapping("UserObject", &headers, &mapped("licenseInfo.number", "licenseInfo.state")).is_ok());
+        assert!(check_mapping("UserObject", &headers, &mapped("licenseInfo.number", "licenseInfo.number")).is_err());
+        assert!(check_mapping("UserObject", &headers, &mapped("licenseInfo", "licenseInfo.number")).is_err());
+        assert!(check_mapping("UserObject", &headers, &mapped("nameFirst.x", "licenseInfo.")).is_err());
+        assert!(check_mapping("UserObject", &headers, &mapped("licenseInfo.a.b", "nameLast")).is_err());
+    }
+}
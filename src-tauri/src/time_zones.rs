@@ -0,0 +1,275 @@
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime, Offset, TimeZone, Timelike, Utc};
+use chrono_tz::{OffsetComponents, OffsetName, Tz};
+use crate::recurrence;
+
+/// Where wall-clock times are read: in a named IANA zone, or in the device's own zone
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Zone {
+    #[default]
+    Device,
+    Named(Tz),
+}
+
+/// An IANA time zone by name, e.g. `Europe/Berlin`
+pub fn parse(name: &str) -> Result<Tz, String> {
+    name.trim()
+        .parse::<Tz>()
+        .map_err(|_| format!("Unknown time zone: {}", name.trim()))
+}
+
+/// An optional time zone field: blank means none
+pub fn parse_optional(name: Option<&str>) -> Result<Option<Tz>, String> {
+    match name.map(str::trim).filter(|name| !name.is_empty()) {
+        Some(name) => parse(name).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// The instant of a wall-clock time. A time that occurs twice when clocks go back is the first of the two;
+/// a time skipped when clocks go forward is taken as the same time an hour later, as clocks show it.
+fn instant<Z: TimeZone>(zone: &Z, local: NaiveDateTime) -> DateTime<Utc> {
+    zone.from_local_datetime(&local)
+        .earliest()
+        .or_else(|| zone.from_local_datetime(&(local + Duration::hours(1))).earliest())
+        .map(|time| time.with_timezone(&Utc))
+        .unwrap_or_else(|| Utc.from_utc_datetime(&local))
+}
+
+impl Zone {
+    /// The instant of a wall-clock time in this zone
+    pub fn to_utc(self, local: NaiveDateTime) -> DateTime<Utc> {
+        match self {
+            Zone::Named(tz) => instant(&tz, local),
+            Zone::Device => instant(&Local, local),
+        }
+    }
+
+    /// The wall-clock time of an instant in this zone
+    pub fn wall_clock(self, time: DateTime<Utc>) -> NaiveDateTime {
+        match self {
+            Zone::Named(tz) => time.with_timezone(&tz).naive_local(),
+            Zone::Device => time.with_timezone(&Local).naive_local(),
+        }
+    }
+}
+
+/// The UTC offset of a zone at an instant, in seconds, whether it is daylight saving time, and its abbreviation
+fn offset_at(tz: Tz, time: DateTime<Utc>) -> (i32, bool, Option<String>) {
+    let offset = tz.offset_from_utc_datetime(&time.naive_utc());
+    (
+        offset.fix().local_minus_utc(),
+        !offset.dst_offset().is_zero(),
+        offset.abbreviation().map(str::to_string),
+    )
+}
+
+/// A change of a zone's UTC offset
+struct Transition {
+    at: DateTime<Utc>,
+    offset_from: i32,
+    offset_to: i32,
+    daylight: bool,
+    name: Option<String>,
+}
+
+impl Transition {
+    /// Wall-clock time of the change, before it (RFC 5545 section 3.6.5)
+    fn local(&self) -> NaiveDateTime {
+        self.at.naive_utc() + Duration::seconds(self.offset_from.into())
+    }
+
+    /// What a yearly rule must reproduce: offsets, month, nth (or last) weekday and time of day
+    fn pattern(&self) -> (i32, i32, u32, chrono::Weekday, i32, u32) {
+        let local = self.local();
+        let date = local.date();
+        let last = date.checked_add_days(chrono::Days::new(7)).is_none_or(|week_later| week_later.month() != date.month());
+        let position = if last { -1 } else { (date.day0() / 7 + 1) as i32 };
+        (self.offset_from, self.offset_to, date.month(), date.weekday(), position, local.num_seconds_from_midnight())
+    }
+}
+
+/// Every offset change of a zone from the start of `first_year` through the end of `last_year`
+fn transitions(tz: Tz, first_year: i32, last_year: i32) -> Vec<Transition> {
+    let (Some(start), Some(end)) = (
+        Utc.with_ymd_and_hms(first_year, 1, 1, 0, 0, 0).single(),
+        Utc.with_ymd_and_hms(last_year, 12, 31, 23, 59, 0).single(),
+    ) else {
+        return Vec::new();
+    };
+    let mut found = Vec::new();
+    let mut time = start;
+    let mut current = offset_at(tz, time);
+    while time < end {
+        let next = time + Duration::days(1);
+        let offset = offset_at(tz, next);
+        if (offset.0, offset.1) != (current.0, current.1) {
+            // Narrow the day down to the minute of the change
+            let (mut before, mut after) = (0, 24 * 60);
+            while after - before > 1 {
+                let middle = (before + after) / 2;
+                let at = offset_at(tz, time + Duration::minutes(middle));
+                if (at.0, at.1) == (current.0, current.1) {
+                    before = middle;
+                } else {
+                    after = middle;
+                }
+            }
+            let at = time + Duration::minutes(after);
+            found.push(Transition { at, offset_from: current.0, offset_to: offset.0, daylight: offset.1, name: offset.2.clone() });
+        }
+        current = offset;
+        time = next;
+    }
+    found
+}
+
+fn offset_text(seconds: i32) -> String {
+    let sign = if seconds < 0 { '-' } else { '+' };
+    let seconds = seconds.abs();
+    let (hours, minutes, rest) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    match rest {
+        0 => format!("{}{:02}{:02}", sign, hours, minutes),
+        _ => format!("{}{:02}{:02}{:02}", sign, hours, minutes, rest),
+    }
+}
+
+fn observance(daylight: bool, start: NaiveDateTime, offset_from: i32, offset_to: i32, name: Option<&str>, rule: Option<String>) -> Vec<String> {
+    let kind = if daylight { "DAYLIGHT" } else { "STANDARD" };
+    let mut lines = vec![
+        format!("BEGIN:{}", kind),
+        format!("DTSTART:{}", start.format("%Y%m%dT%H%M%S")),
+        format!("TZOFFSETFROM:{}", offset_text(offset_from)),
+        format!("TZOFFSETTO:{}", offset_text(offset_to)),
+    ];
+    if let Some(rule) = rule {
+        lines.push(format!("RRULE:{}", rule));
+    }
+    if let Some(name) = name {
+        lines.push(format!("TZNAME:{}", name));
+    }
+    lines.push(format!("END:{}", kind));
+    lines
+}
+
+/// The VTIMEZONE of a zone, valid for times from `first_year` on. Offset changes that repeat every year
+/// are written as yearly rules, so recurring events keep the right offsets after `last_year` too.
+pub fn vtimezone(tz: Tz, first_year: i32, last_year: i32) -> Vec<String> {
+    let mut lines = vec!["BEGIN:VTIMEZONE".to_string(), format!("TZID:{}", tz.name())];
+    // The offset in effect at the start of the first year
+    let Some(start) = NaiveDate::from_ymd_opt(first_year, 1, 1).map(|date| date.and_time(NaiveTime::MIN)) else {
+        return Vec::new();
+    };
+    let (offset, daylight, name) = offset_at(tz, Zone::Named(tz).to_utc(start));
+    lines.extend(observance(daylight, start, offset, offset, name.as_deref(), None));
+
+    // One more year than needed shows whether the last rules still hold
+    let changes = transitions(tz, first_year, last_year.max(first_year) + 1);
+    let mut written = vec![false; changes.len()];
+    for index in 0..changes.len() {
+        if written[index] {
+            continue;
+        }
+        // The changes of the same kind in the following years that follow the same pattern
+        let first = &changes[index];
+        let mut run = vec![index];
+        for (next, change) in changes.iter().enumerate().skip(index + 1) {
+            if change.daylight != first.daylight {
+                continue;
+            }
+            if change.pattern() == first.pattern() && change.at.year() == changes[run[run.len() - 1]].at.year() + 1 {
+                run.push(next);
+            } else {
+                break;
+            }
+        }
+        for &covered in &run {
+            written[covered] = true;
+        }
+
+        let last = &changes[run[run.len() - 1]];
+        let rule = (run.len() > 1).then(|| {
+            let (_, _, month, weekday, position, _) = first.pattern();
+            let mut rule = format!("FREQ=YEARLY;BYMONTH={};BYDAY={}{}", month, position, recurrence::weekday_code(weekday));
+            // A rule still in use in the year after the range is left open
+            if last.at.year() <= last_year.max(first_year) {
+                rule.push_str(&format!(";UNTIL={}", last.at.format("%Y%m%dT%H%M%SZ")));
+            }
+            rule
+        });
+        lines.extend(observance(first.daylight, first.local(), first.offset_from, first.offset_to, first.name.as_deref(), rule));
+    }
+    lines.push("END:VTIMEZONE".to_string());
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(text: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    fn berlin() -> Zone {
+        Zone::Named(parse("Europe/Berlin").unwrap())
+    }
+
+    #[test]
+    fn names_are_checked() {
+        assert_eq!(parse(" America/New_York ").unwrap(), Tz::America__New_York);
+        assert_eq!(parse("Mars/Olympus"), Err("Unknown time zone: Mars/Olympus".to_string()));
+        assert_eq!(parse_optional(Some("  ")), Ok(None));
+        assert!(parse_optional(Some("Nowhere")).is_err());
+    }
+
+    #[test]
+    fn times_skipped_or_repeated_by_clock_changes_have_one_instant() {
+        let utc = |text: &str| berlin().to_utc(time(text)).naive_utc();
+        assert_eq!(utc("2026-01-05 19:00"), time("2026-01-05 18:00"));
+        assert_eq!(utc("2026-07-07 19:00"), time("2026-07-07 17:00"));
+        // 02:30 does not exist when clocks go forward; it is read as 03:30
+        assert_eq!(utc("2026-03-29 02:30"), time("2026-03-29 01:30"));
+        assert_eq!(berlin().wall_clock(berlin().to_utc(time("2026-03-29 02:30"))), time("2026-03-29 03:30"));
+        // 02:30 happens twice when clocks go back; the first one is taken
+        assert_eq!(utc("2026-10-25 02:30"), time("2026-10-25 00:30"));
+        assert_eq!(berlin().wall_clock(berlin().to_utc(time("2026-10-25 02:30"))), time("2026-10-25 02:30"));
+    }
+
+    #[test]
+    fn offsets_are_written_with_seconds_only_when_needed() {
+        assert_eq!(offset_text(3600), "+0100");
+        assert_eq!(offset_text(-19800), "-0530");
+        assert_eq!(offset_text(0), "+0000");
+        assert_eq!(offset_text(-(3600 + 45)), "-010045");
+    }
+
+    #[test]
+    fn changes_that_repeat_every_year_become_rules() {
+        let text = vtimezone(parse("Europe/Berlin").unwrap(), 2026, 2026).join("\n");
+        assert!(text.starts_with("BEGIN:VTIMEZONE\nTZID:Europe/Berlin\nBEGIN:STANDARD\nDTSTART:20260101T000000\nTZOFFSETFROM:+0100\nTZOFFSETTO:+0100\n"));
+        assert!(text.contains(
+            "BEGIN:DAYLIGHT\nDTSTART:20260329T020000\nTZOFFSETFROM:+0100\nTZOFFSETTO:+0200\nRRULE:FREQ=YEARLY;BYMONTH=3;BYDAY=-1SU\nTZNAME:CEST\nEND:DAYLIGHT"
+        ));
+        assert!(text.contains(
+            "BEGIN:STANDARD\nDTSTART:20261025T030000\nTZOFFSETFROM:+0200\nTZOFFSETTO:+0100\nRRULE:FREQ=YEARLY;BYMONTH=10;BYDAY=-1SU\nTZNAME:CET\nEND:STANDARD"
+        ));
+        assert!(text.ends_with("END:VTIMEZONE"));
+    }
+
+    #[test]
+    fn rules_that_stopped_end_with_their_last_change() {
+        // The United States moved the start of daylight saving time from April to March in 2007
+        let text = vtimezone(parse("America/New_York").unwrap(), 2000, 2026).join("\n");
+        assert!(text.contains("RRULE:FREQ=YEARLY;BYMONTH=4;BYDAY=1SU;UNTIL=20060402T070000Z"));
+        assert!(text.contains("DTSTART:20070311T020000\nTZOFFSETFROM:-0500\nTZOFFSETTO:-0400\nRRULE:FREQ=YEARLY;BYMONTH=3;BYDAY=2SU\n"));
+        assert!(text.contains("RRULE:FREQ=YEARLY;BYMONTH=11;BYDAY=1SU\n"));
+    }
+
+    #[test]
+    fn zones_without_changes_have_one_observance() {
+        let text = vtimezone(parse("Asia/Kolkata").unwrap(), 2020, 2026).join("\n");
+        assert_eq!(text.matches("BEGIN:STANDARD").count(), 1);
+        assert!(text.contains("TZOFFSETTO:+0530"));
+        assert!(!text.contains("RRULE") && !text.contains("DAYLIGHT"));
+    }
+}
@@ -0,0 +1,210 @@
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::State;
+use crate::commands::DbConnection;
+use crate::{db, sandbox};
+
+/// Days a deleted record stays in the trash unless the user changes it
+pub const DEFAULT_RETENTION_DAYS: u32 = 30;
+
+/// Setting that holds the retention period in days (0 keeps deleted records until purged by hand)
+const RETENTION_DAYS_SETTING: &str = "trash.retentionDays";
+
+/// A deleted record waiting in the trash
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrashItem {
+    pub id: i64,
+    pub model_name: String,
+    pub record_id: String,
+    pub deleted_at: String,
+    /// Records deleted together (a record and the ones its delete cascaded to) share a batch
+    /// and are restored together
+    pub batch_id: String,
+    pub record: Value,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrashSettings {
+    pub retention_days: u32,
+}
+
+fn retention_days(conn: &Connection) -> Result<u32, String> {
+    Ok(db::get_setting(conn, RETENTION_DAYS_SETTING)?
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(DEFAULT_RETENTION_DAYS))
+}
+
+/// Keep a copy of a record that is being deleted
+pub fn put(conn: &Connection, model_name: &str, record_id: &str, record: &Value, batch_id: &str) -> Result<(), String> {
+    let data = serde_json::to_string(record).map_err(|e| format!("Failed to serialize record: {}", e))?;
+    conn.execute(
+        "INSERT INTO trash (model_name, record_id, deleted_at, batch_id, data) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![model_name, record_id, db::now_timestamp(), batch_id, data],
+    )
+    .map(|_| ())
+    .map_err(|e| format!("Failed to move record to trash: {}", e))
+}
+
+/// Drop the trash copies of a record that exists again, e.g. after a delete was reverted
+pub fn forget(conn: &Connection, model_name: &str, record_id: &str) -> Result<(), String> {
+    conn.execute(
+        "DELETE FROM trash WHERE model_name = ?1 AND record_id = ?2",
+        params![model_name, record_id],
+    )
+    .map(|_| ())
+    .map_err(|e| format!("Failed to update trash: {}", e))
+}
+
+/// Permanently remove records that have been in the trash longer than the retention period.
+/// Returns how many were removed.
+pub fn purge_expired(conn: &Connection) -> Result<usize, String> {
+    let days = retention_days(conn)?;
+    if days == 0 {
+        return Ok(0);
+    }
+    let cutoff = (chrono::Utc::now() - chrono::Duration::days(days.into()))
+        .to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+    conn.execute("DELETE FROM trash WHERE deleted_at < ?1", params![cutoff])
+        .map_err(|e| format!("Failed to purge trash: {}", e))
+}
+
+fn read_item(row: &rusqlite::Row) -> rusqlite::Result<(TrashItem, String)> {
+    Ok((
+        TrashItem {
+            id: row.get(0)?,
+            model_name: row.get(1)?,
+            record_id: row.get(2)?,
+            deleted_at: row.get(3)?,
+            batch_id: row.get(4)?,
+            record: Value::Null,
+        },
+        row.get(5)?,
+    ))
+}
+
+fn parse_items(rows: Vec<(TrashItem, String)>) -> Result<Vec<TrashItem>, String> {
+    rows.into_iter()
+        .map(|(mut item, data)| {
+            item.record = serde_json::from_str(&data).map_err(|e| format!("Failed to parse trashed record: {}", e))?;
+            Ok(item)
+        })
+        .collect()
+}
+
+/// Put back every record of a batch, parents before the records that point at them.
+/// Returns the restored records.
+pub fn restore_batch(conn: &Connection, batch_id: &str) -> Result<Vec<Value>, String> {
+    // Cascaded records are trashed before the record whose delete caused them
+    let items = {
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, model_name, record_id, deleted_at, batch_id, data FROM trash
+                 WHERE batch_id = ?1 ORDER BY id DESC",
+            )
+            .map_err(|e| format!("Failed to prepare query: {}", e))?;
+        let rows = stmt
+            .query_map(params![batch_id], read_item)
+            .map_err(|e| format!("Failed to query trash: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to read trash: {}", e))?;
+        parse_items(rows)?
+    };
+
+    let mut restored = Vec::with_capacity(items.len());
+    for item in items {
+        let record = db::reinsert_record(conn, &item.model_name, item.record)
+            .map_err(|e| format!("Cannot restore {} {}: {}", item.model_name, item.record_id, e))?;
+        restored.push(record);
+    }
+    conn.execute("DELETE FROM trash WHERE batch_id = ?1", params![batch_id])
+        .map_err(|e| format!("Failed to update trash: {}", e))?;
+    Ok(restored)
+}
+
+/// List deleted records, newest first, for one model or for all models.
+/// Records past the retention period are purged first.
+#[tauri::command]
+pub async fn list_trash(db: State<'_, DbConnection>, model_name: Option<String>) -> Result<Vec<TrashItem>, String> {
+    if let Some(name) = &model_name {
+        sandbox::model_name(name)?;
+    }
+    db.write(move |conn| {
+        purge_expired(conn)?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, model_name, record_id, deleted_at, batch_id, data FROM trash
+                 WHERE ?1 IS NULL OR model_name = ?1
+                 ORDER BY id DESC",
+            )
+            .map_err(|e| format!("Failed to prepare query: {}", e))?;
+        let rows = stmt
+            .query_map(params![model_name], read_item)
+            .map_err(|e| format!("Failed to query trash: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to read trash: {}", e))?;
+        parse_items(rows)
+    })
+    .await
+}
+
+/// Restore a deleted record under its original id, together with the records deleted along with it.
+/// Fails if a record it refers to no longer exists; restore that one first.
+#[tauri::command]
+pub async fn restore_from_trash(db: State<'_, DbConnection>, id: i64) -> Result<Vec<Value>, String> {
+    db.write(move |conn| {
+        let batch_id: String = conn
+            .query_row("SELECT batch_id FROM trash WHERE id = ?1", params![id], |row| row.get(0))
+            .map_err(|e| format!("Trash item {} not found: {}", id, e))?;
+
+        let tx = conn
+            .unchecked_transaction()
+            .map_err(|e| format!("Failed to start transaction: {}", e))?;
+        let restored = restore_batch(&tx, &batch_id)?;
+        tx.commit().map_err(|e| format!("Failed to commit restore: {}", e))?;
+        Ok(restored)
+    })
+    .await
+}
+
+/// Permanently remove records from the trash; all of them when `ids` is not given.
+/// Returns how many were removed.
+#[tauri::command]
+pub async fn purge_trash(db: State<'_, DbConnection>, ids: Option<Vec<i64>>) -> Result<usize, String> {
+    db.write(move |conn| {
+        let ids = match ids {
+            Some(ids) => ids,
+            None => return conn.execute("DELETE FROM trash", []).map_err(|e| format!("Failed to empty trash: {}", e)),
+        };
+
+        let tx = conn
+            .unchecked_transaction()
+            .map_err(|e| format!("Failed to start transaction: {}", e))?;
+        let mut purged = 0;
+        for id in ids {
+            purged += tx
+                .execute("DELETE FROM trash WHERE id = ?1", params![id])
+                .map_err(|e| format!("Failed to purge trash: {}", e))?;
+        }
+        tx.commit().map_err(|e| format!("Failed to commit purge: {}", e))?;
+        Ok(purged)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn get_trash_settings(db: State<'_, DbConnection>) -> Result<TrashSettings, String> {
+    db.read(|conn| Ok(TrashSettings { retention_days: retention_days(conn)? })).await
+}
+
+/// Change how long deleted records are kept; records already past the new period are purged right away
+#[tauri::command]
+pub async fn set_trash_settings(db: State<'_, DbConnection>, settings: TrashSettings) -> Result<(), String> {
+    db.write(move |conn| {
+        db::set_setting(conn, RETENTION_DAYS_SETTING, &settings.retention_days.to_string())?;
+        purge_expired(conn).map(|_| ())
+    })
+    .await
+}
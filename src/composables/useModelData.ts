@@ -1,10 +1,14 @@
 import { ref } from 'vue'
 import { useQuasar } from 'quasar'
 import { invoke } from '@tauri-apps/api/core'
-import { getErrorMessage } from '../utils/errors'
+import { getErrorMessage, parseConflict } from '../utils/errors'
 import type { Ref } from 'vue'
 
 export interface ModelDataItem {
+  id?: string
+  createdAt?: string
+  updatedAt?: string
+  revision?: number
   [key: string]: any
 }
 
@@ -23,10 +27,10 @@ export function useModelData() {
   const loadModelData = async (modelName: string): Promise<void> => {
     loading.value = true
     try {
-      const result = await invoke<ModelDataItem[]>('read_model_data', {
+      const result = await invoke<{ revision: number; records: ModelDataItem[] }>('read_model_data', {
         modelName
       })
-      data.value = result
+      data.value = result.records
     } catch (error) {
       console.error('Failed to load model data:', error)
       $q.notify({
@@ -42,17 +46,16 @@ export function useModelData() {
   }
 
   /**
-   * Create a new instance and add it to the data array
+   * Create a new instance; the backend assigns its id and timestamps
    */
   const createItem = async (modelName: string, item: ModelDataItem): Promise<boolean> => {
     loading.value = true
     try {
-      const newData = [...data.value, item]
-      await invoke<string>('write_model_data', {
+      const created = await invoke<ModelDataItem>('insert_record', {
         modelName,
-        data: newData
+        record: item
       })
-      data.value = newData
+      data.value = [...data.value, created]
       $q.notify({
         type: 'positive',
         message: 'Item created successfully',
@@ -73,8 +76,23 @@ export function useModelData() {
     }
   }
 
+  /**
+   * Replace the local copy of a record that was changed or deleted elsewhere
+   * with the stored version, so the user can review it and edit again
+   */
+  const applyConflict = (error: unknown): void => {
+    const conflict = parseConflict(error)
+    if (!conflict?.recordId) {
+      return
+    }
+    data.value = conflict.current
+      ? data.value.map(item => (item.id === conflict.recordId ? conflict.current : item))
+      : data.value.filter(item => item.id !== conflict.recordId)
+  }
+
   /**
    * Update an existing instance at the given index
+   * Fails if the record was changed elsewhere since it was loaded
    */
   const updateItem = async (modelName: string, index: number, item: ModelDataItem): Promise<boolean> => {
     loading.value = true
@@ -82,12 +100,14 @@ export function useModelData() {
       if (index < 0 || index >= data.value.length) {
         throw new Error('Invalid index')
       }
-      const newData = [...data.value]
-      newData[index] = item
-      await invoke<string>('write_model_data', {
+      const updated = await invoke<ModelDataItem>('update_record', {
         modelName,
-        data: newData
+        id: data.value[index].id,
+        patch: item,
+        revision: data.value[index].revision
       })
+      const newData = [...data.value]
+      newData[index] = updated
       data.value = newData
       $q.notify({
         type: 'positive',
@@ -97,6 +117,7 @@ export function useModelData() {
       return true
     } catch (error) {
       console.error('Failed to update item:', error)
+      applyConflict(error)
       $q.notify({
         type: 'negative',
         message: 'Failed to update item',
@@ -118,12 +139,12 @@ export function useModelData() {
       if (index < 0 || index >= data.value.length) {
         throw new Error('Invalid index')
       }
-      const newData = data.value.filter((_, i) => i !== index)
-      await invoke<string>('write_model_data', {
+      await invoke<void>('delete_record', {
         modelName,
-        data: newData
+        id: data.value[index].id,
+        revision: data.value[index].revision
       })
-      data.value = newData
+      data.value = data.value.filter((_, i) => i !== index)
       $q.notify({
         type: 'positive',
         message: 'Item deleted successfully',
@@ -132,6 +153,7 @@ export function useModelData() {
       return true
     } catch (error) {
       console.error('Failed to delete item:', error)
+      applyConflict(error)
       $q.notify({
         type: 'negative',
         message: 'Failed to delete item',
@@ -8,6 +8,7 @@ export const newCongregationObject = {
   stateProvinceNone: false,
   country: '',
   venue: null,
+  timeZone: '',
   midweekMeetingDay: null,
   midweekMeetingTime: null,
   weekendMeetingDay: null,
@@ -28,6 +28,7 @@ export const newUserObject = {
   circuit: null,
   circuitNext: null,
   circuitNextEffectiveDate: null,
+  timeZone: '',
   s307sent: false,
   pdfFilePath307: '',
   photo: null,
@@ -9,6 +9,7 @@ export const newVenueObject = {
   emailMain: '',
   latitude: '',
   longitude: '',
+  timeZone: '',
   alarmCode: '',
   wifiNetworkName: '',
   wifiNetworkPassword: '',
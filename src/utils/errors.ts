@@ -3,6 +3,10 @@
  * Handles Error instances, string errors, and unknown error types
  */
 export function getErrorMessage(error: unknown): string {
+  const conflict = parseConflict(error)
+  if (conflict) {
+    return conflict.message
+  }
   if (error instanceof Error) {
     return error.message
   }
@@ -12,3 +16,32 @@ export function getErrorMessage(error: unknown): string {
   return 'Unknown error'
 }
 
+/**
+ * A write refused because the data changed since it was read (e.g. in another window)
+ */
+export interface ConflictError {
+  message: string
+  modelName: string
+  recordId: string | null
+  expectedRevision: number
+  /** null when the record has been deleted */
+  currentRevision: number | null
+  /** The stored record, or all records of the model for whole-model writes */
+  current: any
+}
+
+/**
+ * Extract the conflict details from a `CONFLICT: {...}` error, if it is one
+ */
+export function parseConflict(error: unknown): ConflictError | null {
+  const prefix = 'CONFLICT: '
+  if (typeof error !== 'string' || !error.startsWith(prefix)) {
+    return null
+  }
+  try {
+    return JSON.parse(error.slice(prefix.length)) as ConflictError
+  } catch {
+    return null
+  }
+}
+
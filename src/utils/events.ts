@@ -5,6 +5,56 @@ export interface CalendarEvent {
   location?: string
   description?: string
   color?: string
+  timeZone?: string // IANA name, e.g. Europe/Berlin; the device's time zone when missing
+  // Set on events imported from ICS files
+  endDate?: string
+  endTime?: string
+  allDay?: boolean
+  categories?: string[]
+  uid?: string
+  recurrence?: EventRecurrence
+  recurrenceId?: string
+}
+
+export interface EventRecurrence {
+  rule?: string // RRULE value, e.g. FREQ=WEEKLY;BYDAY=TU
+  dates: string[]
+  exdates: string[]
+}
+
+/**
+ * One occurrence of an event, as list_occurrences returns it
+ */
+export interface EventOccurrence {
+  uid: string
+  recurrenceId?: string
+  source: 'visits' | 'circuitEvents' | 'pssClasses' | 'routingEvents' | 'meetings' | 'calendarEvents'
+  title: string
+  date: string
+  time?: string
+  endDate?: string
+  endTime?: string
+  allDay: boolean
+  start?: string // UTC instant (RFC 3339); missing for all-day events
+  end?: string
+  timeZone?: string // Time zone the event is kept in; date and time are on this device
+  location?: string
+  description?: string
+  categories: string[]
+  color?: string
+}
+
+/**
+ * Result of read_ics_file: the events of the file and what could not be imported
+ */
+export interface IcsImport {
+  events: CalendarEvent[]
+  skipped: {
+    component: string
+    uid?: string
+    summary?: string
+    reason: string
+  }[]
 }
 
 /**
@@ -32,7 +82,15 @@ export function parseEventsData(jsonData: any): CalendarEvent[] {
       time: event.time,
       location: event.location,
       description: event.description,
-      color: event.color || 'primary'
+      color: event.color || 'primary',
+      timeZone: event.timeZone
     }))
 }
 
+
+/**
+ * Events read from an ICS file, with the default badge color
+ */
+export function parseIcsImport(result: IcsImport): CalendarEvent[] {
+  return result.events.map(event => ({ ...event, color: event.color || 'primary' }))
+}